@@ -1,97 +1,105 @@
+use anyhow::{Context, Result};
+use aoc::parse::parse_lines;
+use aoc::FromRegex;
 use itertools::Itertools;
-use regex::Regex;
 use std::collections::HashMap;
 
-fn main() {
+fn main() -> Result<()> {
     let input = include_str!("input.txt");
-
-    let answer = part_one(input).expect("no solution for part one");
-    println!("part 1: {}", answer);
-
-    let answer = part_two(input).expect("no solution for part two");
-    println!("part 2: {}", answer);
+    aoc::run!(
+        aoc::variants!(input, part_one_brute_force, part_one_held_karp),
+        141
+    )?;
+    aoc::run!(
+        aoc::variants!(input, part_two_brute_force, part_two_held_karp),
+        736
+    )?;
+    Ok(())
 }
 
-#[derive(Debug, PartialEq)]
-enum Error {
-    BadInput,
+#[derive(Debug, FromRegex)]
+#[regex(r"^(\w+) to (\w+) = (\d+)$")]
+struct Route {
+    from: String,
+    to: String,
+    distance: usize,
 }
 
-fn parse_input(input: &str) -> Result<HashMap<&str, HashMap<&str, usize>>, Error> {
-    let re = Regex::new(r"(\w+) to (\w+) = (\d+)").unwrap();
-    let mut map = HashMap::new();
-    for line in input.lines() {
-        let caps = re.captures(line).ok_or(Error::BadInput)?;
-        let city1 = caps.get(1).unwrap().as_str();
-        let city2 = caps.get(2).unwrap().as_str();
-        let cost = caps
-            .get(3)
-            .unwrap()
-            .as_str()
-            .parse::<usize>()
-            .map_err(|_| Error::BadInput)?;
-        map.entry(city1)
-            .or_insert_with(HashMap::new)
-            .insert(city2, cost);
-        map.entry(city2)
-            .or_insert_with(HashMap::new)
-            .insert(city1, cost);
-    }
-    Ok(map)
-}
+// distances[a][b] is the distance between cities a and b, if there is a route
+type Distances = Vec<Vec<Option<usize>>>;
 
-// The input is just 8 cities (8! permutations, and not all valid), so resort to brute force
-fn shortest_distance(map: &HashMap<&str, HashMap<&str, usize>>) -> usize {
-    let mut shortest = usize::MAX;
-    for cities in map.keys().permutations(map.keys().len()) {
-        let mut iter = cities.iter();
-        iter.next();
-        let mut current = 0;
-        for (src, dest) in cities.iter().zip(iter) {
-            if let Some(leg) = map[*src].get(*dest) {
-                current += leg;
-            } else {
-                current = usize::MAX;
-                break;
-            }
-        }
-        if current < shortest {
-            shortest = current;
+fn parse(input: &str) -> Result<Distances> {
+    let routes = parse_lines(input, str::parse::<Route>)?;
+    let mut cities = HashMap::new();
+    for route in routes.iter() {
+        for city in [&route.from, &route.to] {
+            let index = cities.len();
+            cities.entry(city.as_str()).or_insert(index);
         }
     }
-    shortest
+    let mut distances = vec![vec![None; cities.len()]; cities.len()];
+    for route in routes.iter() {
+        let (a, b) = (cities[route.from.as_str()], cities[route.to.as_str()]);
+        distances[a][b] = Some(route.distance);
+        distances[b][a] = Some(route.distance);
+    }
+    Ok(distances)
 }
 
 // The input is just 8 cities (8! permutations, and not all valid), so resort to brute force
-fn longest_distance(map: &HashMap<&str, HashMap<&str, usize>>) -> usize {
-    let mut longest = 0;
-    for cities in map.keys().permutations(map.keys().len()) {
-        let mut iter = cities.iter();
-        iter.next();
-        let mut current = 0;
-        for (src, dest) in cities.iter().zip(iter) {
-            if let Some(leg) = map[*src].get(*dest) {
-                current += leg;
-            } else {
-                current = 0;
-                break;
+fn brute_force(distances: &Distances, pick: fn(usize, usize) -> usize) -> Option<usize> {
+    (0..distances.len())
+        .permutations(distances.len())
+        .filter_map(|cities| {
+            cities
+                .iter()
+                .tuple_windows()
+                .map(|(&a, &b)| distances[a][b])
+                .sum::<Option<usize>>()
+        })
+        .reduce(pick)
+}
+
+// Held-Karp: best[visited][last] is the best distance of a route through the cities in the
+// bitmask `visited` that ends in `last`. O(2^n * n^2) instead of O(n!).
+fn held_karp(distances: &Distances, pick: fn(usize, usize) -> usize) -> Option<usize> {
+    let n = distances.len();
+    let mut best: Vec<Vec<Option<usize>>> = vec![vec![None; n]; 1 << n];
+    for city in 0..n {
+        best[1 << city][city] = Some(0);
+    }
+    for visited in 1..(1usize << n) {
+        for last in (0..n).filter(|&last| visited & (1 << last) != 0) {
+            let Some(so_far) = best[visited][last] else {
+                continue;
+            };
+            for next in (0..n).filter(|&next| visited & (1 << next) == 0) {
+                let Some(leg) = distances[last][next] else {
+                    continue;
+                };
+                let entry = &mut best[visited | (1 << next)][next];
+                let candidate = so_far + leg;
+                *entry = Some(entry.map_or(candidate, |current| pick(current, candidate)));
             }
         }
-        if current > longest {
-            longest = current;
-        }
     }
-    longest
+    best[(1 << n) - 1].iter().flatten().copied().reduce(pick)
+}
+
+fn part_one_brute_force(input: &str) -> Result<usize> {
+    brute_force(&parse(input)?, usize::min).context("no route")
+}
+
+fn part_one_held_karp(input: &str) -> Result<usize> {
+    held_karp(&parse(input)?, usize::min).context("no route")
 }
 
-fn part_one(input: &str) -> Result<usize, Error> {
-    let map = parse_input(input)?;
-    Ok(shortest_distance(&map))
+fn part_two_brute_force(input: &str) -> Result<usize> {
+    brute_force(&parse(input)?, usize::max).context("no route")
 }
 
-fn part_two(input: &str) -> Result<usize, Error> {
-    let map = parse_input(input)?;
-    Ok(longest_distance(&map))
+fn part_two_held_karp(input: &str) -> Result<usize> {
+    held_karp(&parse(input)?, usize::max).context("no route")
 }
 
 #[cfg(test)]
@@ -101,14 +109,18 @@ mod tests {
     const INPUT: &str = include_str!("test-input.txt");
 
     #[test]
-    fn test_shortest_distance() {
-        let map = parse_input(INPUT).unwrap();
-        assert_eq!(shortest_distance(&map), 605);
+    fn test_part_one() {
+        assert_eq!(
+            aoc::variants!(INPUT, part_one_brute_force, part_one_held_karp).unwrap(),
+            605
+        );
     }
 
     #[test]
-    fn test_longest_distance() {
-        let map = parse_input(INPUT).unwrap();
-        assert_eq!(longest_distance(&map), 982);
+    fn test_part_two() {
+        assert_eq!(
+            aoc::variants!(INPUT, part_two_brute_force, part_two_held_karp).unwrap(),
+            982
+        );
     }
 }
@@ -1,92 +1,94 @@
-use serde_json::Value;
+use anyhow::{anyhow, Result};
+use aoc::nested::Nested;
+use std::str::FromStr;
 
-fn main() {
+fn main() -> Result<()> {
     let input = include_str!("input.txt");
-
-    let answer = part_one(input).expect("no solution for part one");
-    println!("part 1: {}", answer);
-
-    let answer = part_two(input).expect("no solution for part two");
-    println!("part 2: {}", answer);
+    aoc::run!(part_one(input), 191164)?;
+    aoc::run!(part_two(input), 87842)?;
+    Ok(())
 }
 
 #[derive(Debug, PartialEq)]
-enum Error {
-    BadInput,
+enum Value {
+    Number(i64),
+    Text(String),
 }
 
-fn parse_input(input: &str) -> Result<Value, Error> {
-    serde_json::from_str(input).map_err(|_| Error::BadInput)
-}
+impl FromStr for Value {
+    type Err = anyhow::Error;
 
-fn sum(value: &Value, exclude_pattern: Option<&str>) -> i64 {
-    if let Some(number) = value.as_i64() {
-        return number;
-    }
-    if let Some(array) = value.as_array() {
-        return array
-            .iter()
-            .fold(0, |acc, item| acc + sum(item, exclude_pattern));
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(text) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            return Ok(Value::Text(text.to_string()));
+        }
+        s.parse()
+            .map(Value::Number)
+            .map_err(|_| anyhow!("bad value '{s}'"))
     }
-    if let Some(object) = value.as_object() {
-        if exclude_pattern.is_none()
-            || !object
-                .values()
-                .map(|v| v.as_str())
-                .any(|v| v == exclude_pattern)
-        {
-            return object
-                .values()
-                .fold(0, |acc, item| acc + sum(item, exclude_pattern));
+}
+
+type Json = Nested<Value>;
+
+fn sum(json: &Json, exclude_pattern: Option<&str>) -> i64 {
+    match json {
+        Nested::Leaf(Value::Number(number)) => *number,
+        Nested::Leaf(Value::Text(_)) => 0,
+        Nested::List(items) => items.iter().map(|item| sum(item, exclude_pattern)).sum(),
+        Nested::Object(entries) => {
+            let excluded = entries
+                .iter()
+                .any(|(_, value)| match (value, exclude_pattern) {
+                    (Nested::Leaf(Value::Text(text)), Some(pattern)) => text == pattern,
+                    _ => false,
+                });
+            if excluded {
+                return 0;
+            }
+            entries
+                .iter()
+                .map(|(_, value)| sum(value, exclude_pattern))
+                .sum()
         }
     }
-    0
 }
 
-fn part_one(input: &str) -> Result<i64, Error> {
-    Ok(sum(&parse_input(input)?, None))
+fn part_one(input: &str) -> Result<i64> {
+    Ok(sum(&input.parse()?, None))
 }
 
-fn part_two(input: &str) -> Result<i64, Error> {
-    Ok(sum(&parse_input(input)?, Some("red")))
+fn part_two(input: &str) -> Result<i64> {
+    Ok(sum(&input.parse()?, Some("red")))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn parse(s: &str) -> Json {
+        s.parse().unwrap()
+    }
+
     #[test]
     fn test_part_one() {
-        assert_eq!(sum(&parse_input(r#"[1,2,3]"#).unwrap(), None), 6);
-        assert_eq!(sum(&parse_input(r#"{"a":2,"b":4}"#).unwrap(), None), 6);
-        assert_eq!(sum(&parse_input(r#"[[[3]]]"#).unwrap(), None), 3);
-        assert_eq!(
-            sum(&parse_input(r#"{"a":{"b":4},"c":-1}"#).unwrap(), None),
-            3
-        );
-        assert_eq!(sum(&parse_input(r#"{"a":[-1,1]}"#).unwrap(), None), 0);
-        assert_eq!(sum(&parse_input(r#"[-1,{"a":1}]"#).unwrap(), None), 0);
-        assert_eq!(sum(&parse_input(r#"[]"#).unwrap(), None), 0);
-        assert_eq!(sum(&parse_input(r#"{}"#).unwrap(), None), 0);
+        assert_eq!(sum(&parse(r#"[1,2,3]"#), None), 6);
+        assert_eq!(sum(&parse(r#"{"a":2,"b":4}"#), None), 6);
+        assert_eq!(sum(&parse(r#"[[[3]]]"#), None), 3);
+        assert_eq!(sum(&parse(r#"{"a":{"b":4},"c":-1}"#), None), 3);
+        assert_eq!(sum(&parse(r#"{"a":[-1,1]}"#), None), 0);
+        assert_eq!(sum(&parse(r#"[-1,{"a":1}]"#), None), 0);
+        assert_eq!(sum(&parse(r#"[]"#), None), 0);
+        assert_eq!(sum(&parse(r#"{}"#), None), 0);
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(sum(&parse_input(r#"[1,2,3]"#).unwrap(), Some("red")), 6);
-        assert_eq!(
-            sum(
-                &parse_input(r#"[1,{"c":"red","b":2},3]"#).unwrap(),
-                Some("red")
-            ),
-            4
-        );
+        assert_eq!(sum(&parse(r#"[1,2,3]"#), Some("red")), 6);
+        assert_eq!(sum(&parse(r#"[1,{"c":"red","b":2},3]"#), Some("red")), 4);
         assert_eq!(
-            sum(
-                &parse_input(r#"{"d":"red","e":[1,2,3,4],"f":5}"#).unwrap(),
-                Some("red")
-            ),
+            sum(&parse(r#"{"d":"red","e":[1,2,3,4],"f":5}"#), Some("red")),
             0
         );
-        assert_eq!(sum(&parse_input(r#"[1,"red",5]"#).unwrap(), Some("red")), 6);
+        assert_eq!(sum(&parse(r#"[1,"red",5]"#), Some("red")), 6);
     }
 }
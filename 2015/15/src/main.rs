@@ -1,23 +1,19 @@
-use regex::{Captures, Regex};
+use anyhow::Result;
+use aoc::FromRegex;
 use std::collections::HashSet;
 
-fn main() {
+fn main() -> Result<()> {
     let input = include_str!("input.txt");
-
-    let answer = part_one(input).expect("no solution for part one");
-    println!("part 1: {}", answer);
-
-    let answer = part_two(input).expect("no solution for part two");
-    println!("part 2: {}", answer);
-}
-
-#[derive(Debug, PartialEq)]
-enum Error {
-    BadInput,
+    aoc::run!(part_one(input), 222870)?;
+    aoc::run!(part_two(input), 117936)?;
+    Ok(())
 }
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, FromRegex)]
+#[regex(
+    r"^(\w+): capacity (-?\d+), durability (-?\d+), flavor (-?\d+), texture (-?\d+), calories (\d+)$"
+)]
 struct Ingredient {
     name: String,
     capacity: i32,
@@ -27,29 +23,8 @@ struct Ingredient {
     calories: i32,
 }
 
-fn parse_input(input: &str) -> Result<Vec<Ingredient>, Error> {
-    fn read_i32(caps: &Captures, i: usize) -> Result<i32, Error> {
-        caps.get(i)
-            .unwrap()
-            .as_str()
-            .parse::<i32>()
-            .map_err(|_| Error::BadInput)
-    }
-
-    let re = Regex::new(r"^(\w+): capacity (-?\d+), durability (-?\d+), flavor (-?\d+), texture (-?\d+), calories (\d+)$").unwrap();
-    let mut ingredients = Vec::new();
-    for line in input.lines() {
-        let caps = re.captures(line).ok_or(Error::BadInput)?;
-        ingredients.push(Ingredient {
-            name: caps.get(1).unwrap().as_str().to_string(),
-            capacity: read_i32(&caps, 2)?,
-            durability: read_i32(&caps, 3)?,
-            flavor: read_i32(&caps, 4)?,
-            texture: read_i32(&caps, 5)?,
-            calories: read_i32(&caps, 6)?,
-        });
-    }
-    Ok(ingredients)
+fn parse_input(input: &str) -> Result<Vec<Ingredient>> {
+    aoc::parse::parse_lines(input, str::parse)
 }
 
 fn generate_weights(num: usize) -> HashSet<Vec<i32>> {
@@ -117,12 +92,12 @@ fn find_highest_score(ingredients: &[Ingredient], target_calories: Option<i32>)
     highest_score
 }
 
-fn part_one(input: &str) -> Result<i32, Error> {
+fn part_one(input: &str) -> Result<i32> {
     let ingredients = parse_input(input)?;
     Ok(find_highest_score(&ingredients, None))
 }
 
-fn part_two(input: &str) -> Result<i32, Error> {
+fn part_two(input: &str) -> Result<i32> {
     let ingredients = parse_input(input)?;
     Ok(find_highest_score(&ingredients, Some(500)))
 }
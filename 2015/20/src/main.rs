@@ -1,4 +1,4 @@
-use primes::{factors_uniq, is_prime};
+use aoc::math::{divisor_sum, divisors};
 use std::{
     sync::{Arc, Mutex},
     thread,
@@ -16,70 +16,15 @@ fn main() {
 
 fn presents_for_house_part_one(house_number: u64) -> u64 {
     debug_assert_ne!(house_number, 0);
-
-    // case one: house number is 1
-    if house_number == 1 {
-        return 10;
-    }
-
-    // case two: house number is a prime number
-    if is_prime(house_number) {
-        return 10 * (1 + house_number);
-    }
-
-    // case three: house number is not a prime number
-    let mut v = vec![1, house_number];
-    for factor in factors_uniq(house_number) {
-        for i in 1.. {
-            let number = factor * i;
-            if number >= house_number {
-                break;
-            }
-            if house_number.is_multiple_of(number) && !v.contains(&number) {
-                v.push(number);
-            }
-        }
-    }
-    10 * v.iter().sum::<u64>()
+    10 * divisor_sum(house_number).expect("no overflow")
 }
 
 fn presents_for_house_part_two(house_number: u64) -> u64 {
     debug_assert_ne!(house_number, 0);
-
-    // case one: house number is 1
-    if house_number == 1 {
-        return 11;
-    }
-
-    // case two: house number is a prime number
-    if is_prime(house_number) {
-        if house_number <= 50 {
-            return 11 * (1 + house_number);
-        } else {
-            return 11 * house_number;
-        }
-    }
-
-    // case three: house number is not a prime number
-    let mut v = vec![house_number];
-    if house_number <= 50 {
-        v.push(1);
-    }
-    for factor in factors_uniq(house_number) {
-        for i in 1.. {
-            let number = factor * i;
-            if number >= house_number {
-                break;
-            }
-            if house_number / number <= 50
-                && house_number.is_multiple_of(number)
-                && !v.contains(&number)
-            {
-                v.push(number);
-            }
-        }
-    }
-    11 * v.iter().sum::<u64>()
+    11 * divisors(house_number)
+        .into_iter()
+        .filter(|elf| house_number / elf <= 50)
+        .sum::<u64>()
 }
 
 struct Context {
@@ -1,193 +1,113 @@
-use regex::{Captures, Regex};
-use std::str::FromStr;
+use anyhow::{bail, Context, Result};
+use aoc::vm::{Flow, Instruction, Machine, Stop};
 
-fn main() {
+fn main() -> Result<()> {
     let input = include_str!("input.txt");
-
-    let answer = part_one(input).expect("no solution for part one");
-    println!("part 1: {}", answer);
-
-    let answer = part_two(input).expect("no solution for part two");
-    println!("part 2: {}", answer);
+    aoc::run!(part_one(input), 170)?;
+    aoc::run!(part_two(input), 247)?;
+    Ok(())
 }
 
-type RegisterId = char;
-
-#[derive(Debug)]
-struct Register {
-    id: RegisterId,
-    value: u32,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Register {
+    A,
+    B,
 }
 
-type RelativeOffset = i32;
-
-#[derive(Debug)]
-enum Instruction {
-    Hlf(RegisterId),
-    Tpl(RegisterId),
-    Inc(RegisterId),
-    Jmp(RelativeOffset),
-    Jie(RegisterId, RelativeOffset),
-    Jio(RegisterId, RelativeOffset),
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Registers {
+    a: u64,
+    b: u64,
 }
 
-struct Computer {
-    registers: Vec<Register>,
-    instructions: Vec<Instruction>,
-}
-
-impl Computer {
-    fn value_of(&self, id: RegisterId) -> Option<u32> {
-        self.registers.iter().find(|r| r.id == id).map(|r| r.value)
-    }
-
-    fn set(&mut self, id: RegisterId, value: u32) -> Result<(), Error> {
-        self.registers
-            .iter_mut()
-            .find(|r| r.id == id)
-            .ok_or(Error::NoSuchRegister(id))?
-            .value = value;
-        Ok(())
+impl Registers {
+    fn get(&self, r: Register) -> u64 {
+        match r {
+            Register::A => self.a,
+            Register::B => self.b,
+        }
     }
 
-    fn run(&mut self) -> Result<(), Error> {
-        Computer::run_inner(&self.instructions, &mut self.registers)
+    fn get_mut(&mut self, r: Register) -> &mut u64 {
+        match r {
+            Register::A => &mut self.a,
+            Register::B => &mut self.b,
+        }
     }
+}
 
-    fn run_inner(instructions: &[Instruction], registers: &mut [Register]) -> Result<(), Error> {
-        fn get_mut(registers: &mut [Register], id: RegisterId) -> Result<&mut Register, Error> {
-            match registers.iter_mut().find(|r| r.id == id) {
-                Some(r) => Ok(r),
-                None => Err(Error::NoSuchRegister(id)),
-            }
-        }
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Op {
+    Hlf(Register),
+    Tpl(Register),
+    Inc(Register),
+    Jmp(isize),
+    Jie(Register, isize),
+    Jio(Register, isize),
+}
 
-        let mut pc = 0i32;
-        loop {
-            if pc < 0 {
-                return Ok(());
-            }
-            match instructions.get(pc as usize) {
-                None => return Ok(()),
-                Some(Instruction::Hlf(id)) => {
-                    let r = get_mut(registers, *id)?;
-                    r.value /= 2;
-                    pc += 1;
-                }
-                Some(Instruction::Tpl(id)) => {
-                    let r = get_mut(registers, *id)?;
-                    r.value *= 3;
-                    pc += 1;
-                }
-                Some(Instruction::Inc(id)) => {
-                    let r = get_mut(registers, *id)?;
-                    r.value += 1;
-                    pc += 1;
-                }
-                Some(Instruction::Jmp(offset)) => {
-                    pc += *offset;
-                }
-                Some(Instruction::Jie(id, offset)) => {
-                    let r = get_mut(registers, *id)?;
-                    if r.value % 2 == 0 {
-                        pc += *offset;
-                    } else {
-                        pc += 1;
-                    }
-                }
-                Some(Instruction::Jio(id, offset)) => {
-                    let r = get_mut(registers, *id)?;
-                    if r.value == 1 {
-                        pc += *offset;
-                    } else {
-                        pc += 1;
-                    }
-                }
-            }
-        }
+fn parse_register(s: &str) -> Result<Register> {
+    match s {
+        "a" => Ok(Register::A),
+        "b" => Ok(Register::B),
+        _ => bail!("unknown register '{s}'"),
     }
 }
 
-impl FromStr for Computer {
-    type Err = Error;
+fn parse_offset(s: &str) -> Result<isize> {
+    s.parse().context("bad offset")
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        fn read_reg_id(caps: &Captures, index: usize) -> RegisterId {
-            caps.get(index).unwrap().as_str().chars().next().unwrap()
-        }
-        fn read_offset(caps: &Captures, index: usize) -> i32 {
-            caps.get(index).unwrap().as_str().parse::<i32>().unwrap()
+impl Instruction for Op {
+    type State = Registers;
+
+    fn parse(line: &str) -> Result<Self> {
+        let (op, args) = line.split_once(' ').context("missing argument")?;
+        let (register, offset) = match args.split_once(", ") {
+            Some((register, offset)) => (register, Some(offset)),
+            None => (args, None),
+        };
+        match (op, offset) {
+            ("hlf", None) => Ok(Op::Hlf(parse_register(register)?)),
+            ("tpl", None) => Ok(Op::Tpl(parse_register(register)?)),
+            ("inc", None) => Ok(Op::Inc(parse_register(register)?)),
+            ("jmp", None) => Ok(Op::Jmp(parse_offset(args)?)),
+            ("jie", Some(offset)) => Ok(Op::Jie(parse_register(register)?, parse_offset(offset)?)),
+            ("jio", Some(offset)) => Ok(Op::Jio(parse_register(register)?, parse_offset(offset)?)),
+            _ => bail!("unknown instruction '{line}'"),
         }
+    }
 
-        let re_hlf = Regex::new(r"^hlf (.)$").unwrap();
-        let re_tpl = Regex::new(r"^tpl (.)$").unwrap();
-        let re_inc = Regex::new(r"^inc (.)$").unwrap();
-        let re_jmp = Regex::new(r"^jmp ([-+]\d+)$").unwrap();
-        let re_jie = Regex::new(r"^jie (.), ([-+]\d+)$").unwrap();
-        let re_jio = Regex::new(r"^jio (.), ([-+]\d+)$").unwrap();
-
-        let mut instructions = Vec::new();
-        for line in s.lines() {
-            if let Some(caps) = re_hlf.captures(line) {
-                instructions.push(Instruction::Hlf(read_reg_id(&caps, 1)));
-                continue;
-            }
-            if let Some(caps) = re_tpl.captures(line) {
-                instructions.push(Instruction::Tpl(read_reg_id(&caps, 1)));
-                continue;
+    fn execute(&self, registers: &mut Registers) -> Result<Flow> {
+        match *self {
+            Op::Hlf(r) => *registers.get_mut(r) /= 2,
+            Op::Tpl(r) => *registers.get_mut(r) *= 3,
+            Op::Inc(r) => *registers.get_mut(r) += 1,
+            Op::Jmp(offset) => return Ok(Flow::Jump(offset)),
+            Op::Jie(r, offset) if registers.get(r).is_multiple_of(2) => {
+                return Ok(Flow::Jump(offset))
             }
-            if let Some(caps) = re_inc.captures(line) {
-                instructions.push(Instruction::Inc(read_reg_id(&caps, 1)));
-                continue;
-            }
-            if let Some(caps) = re_jmp.captures(line) {
-                instructions.push(Instruction::Jmp(read_offset(&caps, 1)));
-                continue;
-            }
-            if let Some(caps) = re_jie.captures(line) {
-                instructions.push(Instruction::Jie(
-                    read_reg_id(&caps, 1),
-                    read_offset(&caps, 2),
-                ));
-                continue;
-            }
-            if let Some(caps) = re_jio.captures(line) {
-                instructions.push(Instruction::Jio(
-                    read_reg_id(&caps, 1),
-                    read_offset(&caps, 2),
-                ));
-                continue;
-            }
-            return Err(Error::BadInput);
+            Op::Jio(r, offset) if registers.get(r) == 1 => return Ok(Flow::Jump(offset)),
+            Op::Jie(..) | Op::Jio(..) => {}
         }
-
-        Ok(Computer {
-            registers: vec![
-                Register { id: 'a', value: 0 },
-                Register { id: 'b', value: 0 },
-            ],
-            instructions,
-        })
+        Ok(Flow::Next)
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum Error {
-    BadInput,
-    NoSuchRegister(RegisterId),
+fn run(input: &str, registers: Registers) -> Result<Registers> {
+    let mut machine: Machine<Op> = Machine::parse(input, registers)?;
+    match machine.run()? {
+        Stop::Halted => Ok(machine.state),
+        stop => bail!("unexpected stop: {stop:?}"),
+    }
 }
 
-fn part_one(input: &str) -> Result<u32, Error> {
-    let mut computer = Computer::from_str(input).unwrap();
-    computer.run()?;
-    computer.value_of('b').ok_or(Error::BadInput)
+fn part_one(input: &str) -> Result<u64> {
+    Ok(run(input, Registers::default())?.b)
 }
 
-fn part_two(input: &str) -> Result<u32, Error> {
-    let mut computer = Computer::from_str(input).unwrap();
-    computer.set('a', 1)?;
-    computer.run()?;
-    computer.value_of('b').ok_or(Error::BadInput)
+fn part_two(input: &str) -> Result<u64> {
+    Ok(run(input, Registers { a: 1, b: 0 })?.b)
 }
 
 #[cfg(test)]
@@ -198,8 +118,6 @@ mod tests {
 
     #[test]
     fn test_example_program() {
-        let mut computer = Computer::from_str(INPUT).unwrap();
-        computer.run().unwrap();
-        assert_eq!(computer.value_of('a'), Some(2));
+        assert_eq!(run(INPUT, Registers::default()).unwrap().a, 2);
     }
 }
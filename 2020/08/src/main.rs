@@ -1,190 +1,70 @@
-use std::str::FromStr;
+use anyhow::{bail, Context, Result};
+use aoc::vm::{Flow, Instruction, Machine, Stop};
 
-fn main() {
+fn main() -> Result<()> {
     let input = include_str!("input.txt");
-
-    let answer = part_one(input);
-    println!("part 1: {}", answer);
-
-    let answer = part_two(input);
-    println!("part 2: {}", answer);
-}
-
-#[derive(Debug)]
-#[allow(dead_code)]
-enum Error {
-    BadInput(String),
-    ExecOutOfBounds(usize),
-    JumpOutOfBounds(usize, i64),
-    InfiniteLoop(usize),
-}
-
-#[derive(Debug, PartialEq, Eq)]
-enum State {
-    Running,
-    Terminated,
+    aoc::run!(part_one(input), 1675)?;
+    aoc::run!(part_two(input), 1532)?;
+    Ok(())
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 enum Op {
-    Nop(i64),
+    Nop(isize),
     Acc(i64),
-    Jmp(i64),
-    End,
+    Jmp(isize),
 }
 
-struct Program {
-    ops: Vec<Option<Op>>,
-    acc: i64,
-    pc: usize,
-}
+impl Instruction for Op {
+    type State = i64;
 
-impl Program {
-    fn step(&mut self) -> Result<(State, i64), Error> {
-        let ops_len = self.ops.len() as i64;
-        let op = self
-            .ops
-            .get_mut(self.pc)
-            .ok_or(Error::ExecOutOfBounds(self.pc))?;
-        match *op {
-            Some(Op::Nop(_)) => {
-                self.pc += 1;
-            }
-            Some(Op::Acc(v)) => {
-                self.pc += 1;
-                self.acc += v;
-            }
-            Some(Op::Jmp(v)) => {
-                let new_pc = self.pc as i64 + v;
-                if new_pc < 0 || new_pc >= ops_len {
-                    return Err(Error::JumpOutOfBounds(self.pc, v));
-                }
-                self.pc = new_pc as usize;
-            }
-            Some(Op::End) => return Ok((State::Terminated, self.acc)),
-            None => {
-                return Err(Error::InfiniteLoop(self.pc));
-            }
-        }
-        *op = None;
-        Ok((State::Running, self.acc))
-    }
-
-    fn run(&mut self) -> Result<i64, Error> {
-        loop {
-            match self.step() {
-                Ok((State::Running, _)) => { /* loop */ }
-                Ok((State::Terminated, acc)) => return Ok(acc),
-                Err(e) => return Err(e),
-            }
+    fn parse(line: &str) -> Result<Self> {
+        let (op, value) = line.split_once(' ').context("missing argument")?;
+        match op {
+            "nop" => Ok(Op::Nop(value.parse().context("bad int arg")?)),
+            "acc" => Ok(Op::Acc(value.parse().context("bad int arg")?)),
+            "jmp" => Ok(Op::Jmp(value.parse().context("bad int arg")?)),
+            _ => bail!("unknown instruction '{op}'"),
         }
     }
-}
-
-impl FromStr for Program {
-    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut ops = Vec::new();
-        for (lineno, line) in s.lines().enumerate() {
-            if line.len() < 6 {
-                return Err(Error::BadInput(format!(
-                    "{}: {}: line too short",
-                    lineno, line
-                )));
+    fn execute(&self, acc: &mut i64) -> Result<Flow> {
+        match self {
+            Op::Nop(_) => Ok(Flow::Next),
+            Op::Acc(v) => {
+                *acc += v;
+                Ok(Flow::Next)
             }
-            let value = &line[4..]
-                .parse::<i64>()
-                .map_err(|_| Error::BadInput(format!("{}: {}: bad int arg", lineno, line)))?;
-            let op = match &line[..3] {
-                "nop" => Op::Nop(*value),
-                "acc" => Op::Acc(*value),
-                "jmp" => Op::Jmp(*value),
-                _ => {
-                    return Err(Error::BadInput(format!(
-                        "{}: {}: unknown instruction",
-                        lineno, line
-                    )))
-                }
-            };
-            ops.push(Some(op));
+            Op::Jmp(v) => Ok(Flow::Jump(*v)),
         }
-        ops.push(Some(Op::End));
-        Ok(Program { ops, acc: 0, pc: 0 })
-    }
-}
-
-impl std::fmt::Debug for Program {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "Program(acc={}, pc={}, size={}, line={:?})",
-            self.acc,
-            self.pc,
-            self.ops.len(),
-            self.ops.get(self.pc),
-        )
     }
 }
 
-impl Clone for Program {
-    fn clone(&self) -> Program {
-        Program {
-            ops: self.ops.to_vec(),
-            acc: self.acc,
-            pc: self.pc,
-        }
+fn part_one(input: &str) -> Result<i64> {
+    let mut machine: Machine<Op> = Machine::parse(input, 0)?;
+    match machine.run_until_loop()? {
+        Stop::Loop(_) => Ok(machine.state),
+        stop => bail!("unexpected stop: {stop:?}"),
     }
 }
 
-fn part_one(input: &str) -> i64 {
-    let mut p = Program::from_str(input).unwrap();
-    let mut acc = 0;
-    loop {
-        match p.step() {
-            Ok((_, v)) => {
-                acc = v;
-            }
-            Err(Error::InfiniteLoop(_)) => {
-                return acc;
-            }
-            Err(e) => {
-                panic!("{:?}", e);
-            }
-        }
-    }
-}
-
-fn part_two(input: &str) -> i64 {
-    let original = Program::from_str(input).unwrap();
-    let mut patch_pos = 0;
-    loop {
-        let mut p = original.clone();
-        loop {
-            let op = p.ops.get_mut(patch_pos).unwrap();
-            match *op {
-                Some(Op::Nop(v)) => {
-                    *op = Some(Op::Jmp(v));
-                    patch_pos += 1;
-                    break;
-                }
-                Some(Op::Jmp(v)) => {
-                    *op = Some(Op::Nop(v));
-                    patch_pos += 1;
-                    break;
-                }
-                Some(_) => {
-                    patch_pos += 1;
-                }
-                None => {
-                    panic!("patching exhausted but no solution found");
-                }
-            }
-        }
-        if let Ok(acc) = p.run() {
-            return acc;
+fn part_two(input: &str) -> Result<i64> {
+    let original: Machine<Op> = Machine::parse(input, 0)?;
+    for (pc, op) in original.program().iter().enumerate() {
+        let patched_op = match op {
+            Op::Nop(v) => Op::Jmp(*v),
+            Op::Jmp(v) => Op::Nop(*v),
+            Op::Acc(_) => continue,
+        };
+        let mut machine = original.clone();
+        machine.patch(pc, patched_op);
+        if machine.run_until_loop()? == Stop::Halted
+            && machine.pc() == Some(machine.program().len())
+        {
+            return Ok(machine.state);
         }
     }
+    bail!("patching exhausted but no solution found");
 }
 
 #[cfg(test)]
@@ -194,22 +74,20 @@ mod tests {
     const INPUT: &str = include_str!("test-input.txt");
 
     #[test]
-    fn test_program_from_str() {
-        Program::from_str(INPUT).unwrap();
+    fn test_parse() {
+        Machine::<Op>::parse(INPUT, 0).unwrap();
 
-        let p = Program::from_str("jmp -10").unwrap();
-        assert_eq!(p.ops.len(), 2);
-        assert_eq!(p.ops[0], Some(Op::Jmp(-10)));
-        assert_eq!(p.ops[1], Some(Op::End));
+        let machine = Machine::<Op>::parse("jmp -10", 0).unwrap();
+        assert_eq!(machine.program(), &[Op::Jmp(-10)]);
     }
 
     #[test]
     fn test_part_one() {
-        assert_eq!(part_one(INPUT), 5);
+        assert_eq!(part_one(INPUT).unwrap(), 5);
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(part_two(INPUT), 8);
+        assert_eq!(part_two(INPUT).unwrap(), 8);
     }
 }
@@ -1,3 +1,5 @@
+use aoc::math::crt;
+
 fn main() {
     let input = include_str!("input.txt");
 
@@ -61,36 +63,22 @@ fn part_two(input: &str) -> Result<u64, Error> {
         }
     }
 
-    fn lcm(n: u64, m: u64) -> u64 {
-        // Our input is guaranteed to be only prime numbers, so lcm(n, m) == n * m.
-        n * m
-    }
-
-    // References: [1] https://en.wikipedia.org/wiki/Least_common_multiple#Gears_problem
-    //
     // Think of the buses as gears on the same axis. The gears have different number of teeth (the
     // bus IDs). Each gear has one marked tooth. At timestamp == 0 the gears are offset from each
     // other so that none of the marked teeth are aligned. Find the lowest timestamp when all gears
-    // are aligned.
-    //
-    // According to [1], two gears with m and n teeth re-align after lcm(m, n) rotations.
-    let (_, timestamp) =
-        gears
-            .iter()
-            .fold((1, 0), |(period, timestamp), (gear_period, gear_offset)| {
-                (
-                    // Calculate new period needed to align visited gears.
-                    lcm(period, *gear_period),
-                    // Find timestamp when visited gears become aligned with current gear.
-                    // Start counting from current timestamp.
-                    (0..)
-                        .map(|i| period * i + timestamp)
-                        .find(|i| (i + gear_offset) % gear_period == 0)
-                        .unwrap(),
-                )
-            });
+    // are aligned, i.e. the timestamp t for which (t + offset) % period == 0 for all gears. This
+    // is a system of congruences t ≡ -offset (mod period), solved by the Chinese Remainder
+    // Theorem.
+    let congruences: Vec<(i64, i64)> = gears
+        .iter()
+        .map(|(period, offset)| {
+            let period = *period as i64;
+            ((-(*offset as i64)).rem_euclid(period), period)
+        })
+        .collect();
+    let (timestamp, _) = crt(&congruences).ok_or(Error::BadInput("no solution"))?;
 
-    Ok(timestamp)
+    Ok(timestamp as u64)
 }
 
 #[cfg(test)]
@@ -1,22 +1,17 @@
+use anyhow::Result;
+use aoc::combinator::{alt, literal, many0, parse_all, value, PResult, Parser};
+use aoc::parse::parse_lines;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
-fn main() {
+fn main() -> Result<()> {
     let input = include_str!("input.txt");
-
-    let answer = part_one(input).expect("no solution for part one");
-    println!("part 1: {}", answer);
-
-    let answer = part_two(input, 100).expect("no solution for part two");
-    println!("part 2: {}", answer);
-}
-
-#[derive(Debug, PartialEq)]
-enum Error {
-    BadInput,
+    aoc::run!(part_one(input), 263)?;
+    aoc::run!(part_two(input, 100), 3649)?;
+    Ok(())
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 enum Direction {
     East,
     SouthEast,
@@ -34,54 +29,20 @@ enum Color {
 
 type TileId = (i32, i32);
 
-fn tokenize(input: &str) -> Result<Vec<Vec<Direction>>, Error> {
-    let mut outer = Vec::new();
-    for mut line in input.lines() {
-        let mut inner = Vec::new();
-        while !line.is_empty() {
-            let mut chars = line.chars();
-            let ch0 = chars.next();
-            let ch1 = chars.next();
-
-            if let Some('e') = ch0 {
-                inner.push(Direction::East);
-                line = &line[1..];
-                continue;
-            }
-            if let Some('w') = ch0 {
-                inner.push(Direction::West);
-                line = &line[1..];
-                continue;
-            }
-            if let Some('s') = ch0 {
-                if let Some('e') = ch1 {
-                    inner.push(Direction::SouthEast);
-                    line = &line[2..];
-                    continue;
-                }
-                if let Some('w') = ch1 {
-                    inner.push(Direction::SouthWest);
-                    line = &line[2..];
-                    continue;
-                }
-            }
-            if let Some('n') = ch0 {
-                if let Some('e') = ch1 {
-                    inner.push(Direction::NorthEast);
-                    line = &line[2..];
-                    continue;
-                }
-                if let Some('w') = ch1 {
-                    inner.push(Direction::NorthWest);
-                    line = &line[2..];
-                    continue;
-                }
-            }
-            return Err(Error::BadInput);
-        }
-        outer.push(inner);
-    }
-    Ok(outer)
+fn direction(input: &str) -> PResult<'_, Direction> {
+    alt((
+        value(literal("e"), Direction::East),
+        value(literal("se"), Direction::SouthEast),
+        value(literal("sw"), Direction::SouthWest),
+        value(literal("w"), Direction::West),
+        value(literal("nw"), Direction::NorthWest),
+        value(literal("ne"), Direction::NorthEast),
+    ))
+    .parse(input)
+}
+
+fn tokenize(input: &str) -> Result<Vec<Vec<Direction>>> {
+    parse_lines(input, |line| parse_all(line, many0(direction)))
 }
 
 fn setup(directions: &[Vec<Direction>]) -> HashMap<TileId, Color> {
@@ -129,13 +90,13 @@ fn setup(directions: &[Vec<Direction>]) -> HashMap<TileId, Color> {
     tiles
 }
 
-fn part_one(input: &str) -> Result<usize, Error> {
+fn part_one(input: &str) -> Result<usize> {
     let all_dirs = tokenize(input)?;
     let tiles = setup(&all_dirs);
     Ok(tiles.values().filter(|&c| *c == Color::Black).count())
 }
 
-fn part_two(input: &str, days: usize) -> Result<usize, Error> {
+fn part_two(input: &str, days: usize) -> Result<usize> {
     fn neighbours(id: &TileId) -> [TileId; 6] {
         [
             (id.0 + 2, id.1),     // East
@@ -195,43 +156,44 @@ mod tests {
     #[test]
     fn test_tokenize() {
         assert_eq!(
-            tokenize("eseswwnwne"),
-            Ok(vec![vec![
+            tokenize("eseswwnwne").unwrap(),
+            vec![vec![
                 Direction::East,
                 Direction::SouthEast,
                 Direction::SouthWest,
                 Direction::West,
                 Direction::NorthWest,
                 Direction::NorthEast
-            ]])
+            ]]
         );
+        assert!(tokenize("nwwswee\nsx").is_err());
     }
 
     #[test]
     fn test_part_one() {
-        assert_eq!(part_one(INPUT), Ok(10));
+        assert_eq!(part_one(INPUT).unwrap(), 10);
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(part_two(INPUT, 1), Ok(15));
-        assert_eq!(part_two(INPUT, 2), Ok(12));
-        assert_eq!(part_two(INPUT, 3), Ok(25));
-        assert_eq!(part_two(INPUT, 4), Ok(14));
-        assert_eq!(part_two(INPUT, 5), Ok(23));
-        assert_eq!(part_two(INPUT, 6), Ok(28));
-        assert_eq!(part_two(INPUT, 7), Ok(41));
-        assert_eq!(part_two(INPUT, 8), Ok(37));
-        assert_eq!(part_two(INPUT, 9), Ok(49));
-        assert_eq!(part_two(INPUT, 10), Ok(37));
-        assert_eq!(part_two(INPUT, 20), Ok(132));
-        assert_eq!(part_two(INPUT, 30), Ok(259));
-        assert_eq!(part_two(INPUT, 40), Ok(406));
-        assert_eq!(part_two(INPUT, 50), Ok(566));
-        assert_eq!(part_two(INPUT, 60), Ok(788));
-        assert_eq!(part_two(INPUT, 70), Ok(1106));
-        assert_eq!(part_two(INPUT, 80), Ok(1373));
-        assert_eq!(part_two(INPUT, 90), Ok(1844));
-        assert_eq!(part_two(INPUT, 100), Ok(2208));
+        assert_eq!(part_two(INPUT, 1).unwrap(), 15);
+        assert_eq!(part_two(INPUT, 2).unwrap(), 12);
+        assert_eq!(part_two(INPUT, 3).unwrap(), 25);
+        assert_eq!(part_two(INPUT, 4).unwrap(), 14);
+        assert_eq!(part_two(INPUT, 5).unwrap(), 23);
+        assert_eq!(part_two(INPUT, 6).unwrap(), 28);
+        assert_eq!(part_two(INPUT, 7).unwrap(), 41);
+        assert_eq!(part_two(INPUT, 8).unwrap(), 37);
+        assert_eq!(part_two(INPUT, 9).unwrap(), 49);
+        assert_eq!(part_two(INPUT, 10).unwrap(), 37);
+        assert_eq!(part_two(INPUT, 20).unwrap(), 132);
+        assert_eq!(part_two(INPUT, 30).unwrap(), 259);
+        assert_eq!(part_two(INPUT, 40).unwrap(), 406);
+        assert_eq!(part_two(INPUT, 50).unwrap(), 566);
+        assert_eq!(part_two(INPUT, 60).unwrap(), 788);
+        assert_eq!(part_two(INPUT, 70).unwrap(), 1106);
+        assert_eq!(part_two(INPUT, 80).unwrap(), 1373);
+        assert_eq!(part_two(INPUT, 90).unwrap(), 1844);
+        assert_eq!(part_two(INPUT, 100).unwrap(), 2208);
     }
 }
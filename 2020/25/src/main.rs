@@ -1,3 +1,5 @@
+use aoc::math::{discrete_log, mod_pow};
+
 fn main() {
     let input = include_str!("input.txt");
 
@@ -12,26 +14,12 @@ enum Error {
 
 const MAGIC_NUMBER: u64 = 20201227;
 
-fn loop_size_from_public_key(public_key: u64, subject_number: u64) -> u64 {
-    let mut value = 1;
-    let mut loop_size = 0;
-    loop {
-        loop_size += 1;
-        value *= subject_number;
-        value %= MAGIC_NUMBER;
-        if value == public_key {
-            return loop_size;
-        }
-    }
+fn loop_size_from_public_key(public_key: u64, subject_number: u64) -> Option<u64> {
+    discrete_log(subject_number, public_key, MAGIC_NUMBER)
 }
 
 fn encryption_key(public_key: u64, loop_size: u64) -> u64 {
-    let mut value = 1;
-    for _ in 0..loop_size {
-        value *= public_key;
-        value %= MAGIC_NUMBER;
-    }
-    value
+    mod_pow(public_key, loop_size, MAGIC_NUMBER)
 }
 
 fn part_one(input: &str) -> Result<u64, Error> {
@@ -46,8 +34,8 @@ fn part_one(input: &str) -> Result<u64, Error> {
         .ok_or(Error::BadInput)?
         .parse::<u64>()
         .map_err(|_| Error::BadInput)?;
-    let door_loop_size = loop_size_from_public_key(door_pub_key, 7);
-    let card_loop_size = loop_size_from_public_key(card_pub_key, 7);
+    let door_loop_size = loop_size_from_public_key(door_pub_key, 7).ok_or(Error::BadInput)?;
+    let card_loop_size = loop_size_from_public_key(card_pub_key, 7).ok_or(Error::BadInput)?;
     let enc_key_alt1 = encryption_key(door_pub_key, card_loop_size);
     let enc_key_alt2 = encryption_key(card_pub_key, door_loop_size);
     assert_eq!(enc_key_alt1, enc_key_alt2);
@@ -62,8 +50,8 @@ mod tests {
 
     #[test]
     fn test_loop_size_from_public_key() {
-        assert_eq!(loop_size_from_public_key(5764801, 7), 8);
-        assert_eq!(loop_size_from_public_key(17807724, 7), 11);
+        assert_eq!(loop_size_from_public_key(5764801, 7), Some(8));
+        assert_eq!(loop_size_from_public_key(17807724, 7), Some(11));
     }
 
     #[test]
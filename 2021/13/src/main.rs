@@ -1,13 +1,10 @@
 use std::{collections::HashSet, fmt::Display};
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     let input = include_str!("input.txt");
-
-    let answer = part_one(input).expect("no solution for part one");
-    println!("part 1: {}", answer);
-
-    let answer = part_two(input).expect("no solution for part two");
-    println!("part 2:\n{}", answer);
+    aoc::run!(part_one(input), 631)?;
+    aoc::run!(part_two(input), "EFLFJGRF")?;
+    Ok(())
 }
 
 #[derive(Debug, PartialEq)]
@@ -18,6 +15,14 @@ enum Error {
     BadFoldY(u32),
 }
 
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
+
 #[derive(Debug)]
 enum Instruction {
     FoldAlongX(u32),
@@ -159,19 +164,23 @@ fn parse_input(input: &str) -> Result<(Paper, Vec<Instruction>), Error> {
     Ok((paper, instructions))
 }
 
-fn part_one(input: &str) -> Result<usize, Error> {
+fn fold_all(input: &str) -> Result<Paper, Error> {
+    let (mut paper, instructions) = parse_input(input)?;
+    for instr in instructions {
+        paper.fold(&instr)?;
+    }
+    Ok(paper)
+}
+
+fn part_one(input: &str) -> anyhow::Result<usize> {
     let (mut paper, instructions) = parse_input(input)?;
     let instr = instructions.first().ok_or(Error::BadInput)?;
     paper.fold(instr)?;
     Ok(paper.count())
 }
 
-fn part_two(input: &str) -> Result<String, Error> {
-    let (mut paper, instructions) = parse_input(input)?;
-    for instr in instructions {
-        paper.fold(&instr)?;
-    }
-    Ok(paper.to_string())
+fn part_two(input: &str) -> anyhow::Result<String> {
+    aoc::ocr(&fold_all(input)?.to_string())
 }
 
 #[cfg(test)]
@@ -182,12 +191,12 @@ mod tests {
 
     #[test]
     fn test_part_one() {
-        assert_eq!(part_one(INPUT), Ok(17));
+        assert_eq!(part_one(INPUT).unwrap(), 17);
     }
 
     #[test]
-    fn test_part_two() {
-        let expected = include_str!("test-expected.txt").to_string();
-        assert_eq!(part_two(INPUT), Ok(expected));
+    fn test_fold_all() {
+        let expected = include_str!("test-expected.txt");
+        assert_eq!(fold_all(INPUT).unwrap().to_string(), expected);
     }
 }
@@ -1,3 +1,5 @@
+use aoc::bits::{BitError, BitReader};
+
 fn main() {
     let input = include_str!("input.txt");
 
@@ -11,80 +13,24 @@ fn main() {
 #[derive(Debug, PartialEq)]
 enum Error {
     BadArgument(String),
-    NoMoreBits,
+    Bits(BitError),
     Corrupt(&'static str),
 }
 
-#[derive(Debug, PartialEq, Clone)]
-enum Bit {
-    Zero,
-    One,
-}
-
-#[derive(Debug)]
-struct Scanner {
-    bits: Vec<Bit>,
-    position: usize,
-}
-
-impl Scanner {
-    fn read(&mut self, n: usize) -> Result<u64, Error> {
-        if n > u64::BITS as usize {
-            return Err(Error::BadArgument(format!("{}", n)));
-        }
-        let mut value = 0;
-        for i in 0..n {
-            value = match self.bits.get(self.position + i).ok_or(Error::NoMoreBits)? {
-                Bit::Zero => value << 1,
-                Bit::One => (value << 1) | 0x0001,
-            };
-        }
-        self.position += n;
-        Ok(value)
-    }
-}
-
-impl TryFrom<&str> for Scanner {
-    type Error = Error;
-
-    fn try_from(hex: &str) -> Result<Self, Self::Error> {
-        const B0: Bit = Bit::Zero;
-        const B1: Bit = Bit::One;
-
-        let mut bits = Vec::new();
-        for ch in hex.chars() {
-            match ch {
-                '0' => bits.append(&mut vec![B0, B0, B0, B0]),
-                '1' => bits.append(&mut vec![B0, B0, B0, B1]),
-                '2' => bits.append(&mut vec![B0, B0, B1, B0]),
-                '3' => bits.append(&mut vec![B0, B0, B1, B1]),
-                '4' => bits.append(&mut vec![B0, B1, B0, B0]),
-                '5' => bits.append(&mut vec![B0, B1, B0, B1]),
-                '6' => bits.append(&mut vec![B0, B1, B1, B0]),
-                '7' => bits.append(&mut vec![B0, B1, B1, B1]),
-                '8' => bits.append(&mut vec![B1, B0, B0, B0]),
-                '9' => bits.append(&mut vec![B1, B0, B0, B1]),
-                'A' => bits.append(&mut vec![B1, B0, B1, B0]),
-                'B' => bits.append(&mut vec![B1, B0, B1, B1]),
-                'C' => bits.append(&mut vec![B1, B1, B0, B0]),
-                'D' => bits.append(&mut vec![B1, B1, B0, B1]),
-                'E' => bits.append(&mut vec![B1, B1, B1, B0]),
-                'F' => bits.append(&mut vec![B1, B1, B1, B1]),
-                _ => return Err(Error::BadArgument(format!("{}", ch))),
-            }
-        }
-        Ok(Scanner { bits, position: 0 })
+impl From<BitError> for Error {
+    fn from(e: BitError) -> Self {
+        Error::Bits(e)
     }
 }
 
 #[derive(Debug)]
 struct Parser {
-    scanner: Scanner,
+    reader: BitReader,
 }
 
 impl Parser {
-    fn new(scanner: Scanner) -> Parser {
-        Parser { scanner }
+    fn new(reader: BitReader) -> Parser {
+        Parser { reader }
     }
 
     /*
@@ -100,37 +46,26 @@ impl Parser {
      * payload_operator := 0 L{15} package+ | 1 N{11} package+
      */
     fn parse_package(&mut self) -> Result<ASTNode, Error> {
-        let version = self.scanner.read(3)? as Version;
-        let type_: PackageType = self.scanner.read(3)?.try_into()?;
+        let version = self.reader.read(3)? as Version;
+        let type_: PackageType = self.reader.read(3)?.try_into()?;
 
         if type_ == PackageType::Constant {
-            let mut value = 0;
-            for _ in 0..(u64::BITS / 4) {
-                let repeat = self.scanner.read(1)? == 1;
-                value = (value << 4) | self.scanner.read(4)?;
-                if !repeat {
-                    return Ok(ASTNode::Constant(version, value));
-                }
-            }
-            return Err(Error::Corrupt("constant exceeds 64 bits"));
+            let value = self.reader.read_chunked(4)?;
+            return Ok(ASTNode::Constant(version, value));
         }
 
         let mut sub_packages = vec![];
-        match self.scanner.read(1)? {
-            0 => {
-                let len = self.scanner.read(15)? as usize;
-                let mut sub_parser = self.sub_parser(len);
-                while sub_parser.scanner.position < len {
-                    sub_packages.push(sub_parser.parse_package()?);
-                }
+        if self.reader.read_bool()? {
+            let n = self.reader.read(11)?;
+            for _ in 0..n {
+                sub_packages.push(self.parse_package()?);
             }
-            1 => {
-                let n = self.scanner.read(11)?;
-                for _ in 0..n {
-                    sub_packages.push(self.parse_package()?);
-                }
+        } else {
+            let len = self.reader.read(15)? as usize;
+            let mut sub_parser = Parser::new(self.reader.sub_stream(len)?);
+            while !sub_parser.reader.is_empty() {
+                sub_packages.push(sub_parser.parse_package()?);
             }
-            _ => unreachable!(),
         }
         if sub_packages.is_empty() {
             return Err(Error::Corrupt("no children"));
@@ -181,15 +116,6 @@ impl Parser {
         };
         Ok(node)
     }
-
-    fn sub_parser(&mut self, len: usize) -> Parser {
-        let sub_scanner = Scanner {
-            bits: self.scanner.bits[self.scanner.position..self.scanner.position + len].to_vec(),
-            position: 0,
-        };
-        self.scanner.position += len;
-        Parser::new(sub_scanner)
-    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -334,15 +260,13 @@ impl ASTNode {
 }
 
 fn part_one(input: &str) -> Result<u64, Error> {
-    let scanner: Scanner = input.trim().try_into()?;
-    let mut parser = Parser::new(scanner);
+    let mut parser = Parser::new(BitReader::from_hex(input)?);
     let pkg = parser.parse_package()?;
     pkg.visit_versions()
 }
 
 fn part_two(input: &str) -> Result<u64, Error> {
-    let scanner: Scanner = input.trim().try_into()?;
-    let mut parser = Parser::new(scanner);
+    let mut parser = Parser::new(BitReader::from_hex(input)?);
     let pkg = parser.parse_package()?;
     pkg.eval()
 }
@@ -351,109 +275,90 @@ fn part_two(input: &str) -> Result<u64, Error> {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_scanner() {
-        let scanner: Scanner = "0F".try_into().unwrap();
-        assert_eq!(scanner.position, 0);
-        assert_eq!(
-            scanner.bits,
-            [
-                Bit::Zero,
-                Bit::Zero,
-                Bit::Zero,
-                Bit::Zero,
-                Bit::One,
-                Bit::One,
-                Bit::One,
-                Bit::One
-            ]
-        );
-    }
-
     #[test]
     fn test_parse_package_literal_value() {
-        let scanner: Scanner = "D2FE28".try_into().unwrap();
-        let mut parser = Parser::new(scanner);
+        let reader = BitReader::from_hex("D2FE28").unwrap();
+        let mut parser = Parser::new(reader);
         let pkg = parser.parse_package().unwrap();
         assert_eq!(pkg.visit_versions(), Ok(6));
     }
 
     #[test]
     fn test_parse_constant_type_0() {
-        let scanner: Scanner = "38006F45291200".try_into().unwrap();
-        let mut parser = Parser::new(scanner);
+        let reader = BitReader::from_hex("38006F45291200").unwrap();
+        let mut parser = Parser::new(reader);
         let pkg = parser.parse_package().unwrap();
         assert_eq!(pkg.visit_versions(), Ok(1 + 6 + 2));
     }
 
     #[test]
     fn test_parse_constant_type_1() {
-        let scanner: Scanner = "EE00D40C823060".try_into().unwrap();
-        let mut parser = Parser::new(scanner);
+        let reader = BitReader::from_hex("EE00D40C823060").unwrap();
+        let mut parser = Parser::new(reader);
         let pkg = parser.parse_package().unwrap();
         assert_eq!(pkg.visit_versions(), Ok(7 + 2 + 4 + 1));
     }
 
     #[test]
     fn test_eval_sum() {
-        let scanner: Scanner = "C200B40A82".try_into().unwrap();
-        let mut parser = Parser::new(scanner);
+        let reader = BitReader::from_hex("C200B40A82").unwrap();
+        let mut parser = Parser::new(reader);
         let pkg = parser.parse_package().unwrap();
         assert_eq!(pkg.eval(), Ok(3));
     }
 
     #[test]
     fn test_eval_product() {
-        let scanner: Scanner = "04005AC33890".try_into().unwrap();
-        let mut parser = Parser::new(scanner);
+        let reader = BitReader::from_hex("04005AC33890").unwrap();
+        let mut parser = Parser::new(reader);
         let pkg = parser.parse_package().unwrap();
         assert_eq!(pkg.eval(), Ok(54));
     }
 
     #[test]
     fn test_eval_minimum() {
-        let scanner: Scanner = "880086C3E88112".try_into().unwrap();
-        let mut parser = Parser::new(scanner);
+        let reader = BitReader::from_hex("880086C3E88112").unwrap();
+        let mut parser = Parser::new(reader);
         let pkg = parser.parse_package().unwrap();
         assert_eq!(pkg.eval(), Ok(7));
     }
 
     #[test]
     fn test_eval_maximum() {
-        let scanner: Scanner = "CE00C43D881120".try_into().unwrap();
-        let mut parser = Parser::new(scanner);
+        let reader = BitReader::from_hex("CE00C43D881120").unwrap();
+        let mut parser = Parser::new(reader);
         let pkg = parser.parse_package().unwrap();
         assert_eq!(pkg.eval(), Ok(9));
     }
 
     #[test]
     fn test_eval_greater_than() {
-        let scanner: Scanner = "D8005AC2A8F0".try_into().unwrap();
-        let mut parser = Parser::new(scanner);
+        let reader = BitReader::from_hex("D8005AC2A8F0").unwrap();
+        let mut parser = Parser::new(reader);
         let pkg = parser.parse_package().unwrap();
         assert_eq!(pkg.eval(), Ok(1));
     }
 
     #[test]
     fn test_eval_less_than() {
-        let scanner: Scanner = "F600BC2D8F".try_into().unwrap();
-        let mut parser = Parser::new(scanner);
+        let reader = BitReader::from_hex("F600BC2D8F").unwrap();
+        let mut parser = Parser::new(reader);
         let pkg = parser.parse_package().unwrap();
         assert_eq!(pkg.eval(), Ok(0));
     }
 
     #[test]
     fn test_eval_equal() {
-        let scanner: Scanner = "9C005AC2F8F0".try_into().unwrap();
-        let mut parser = Parser::new(scanner);
+        let reader = BitReader::from_hex("9C005AC2F8F0").unwrap();
+        let mut parser = Parser::new(reader);
         let pkg = parser.parse_package().unwrap();
         assert_eq!(pkg.eval(), Ok(0));
     }
 
     #[test]
     fn test_eval() {
-        let scanner: Scanner = "9C0141080250320F1802104A08".try_into().unwrap();
-        let mut parser = Parser::new(scanner);
+        let reader = BitReader::from_hex("9C0141080250320F1802104A08").unwrap();
+        let mut parser = Parser::new(reader);
         let pkg = parser.parse_package().unwrap();
         assert_eq!(pkg.eval(), Ok(1));
     }
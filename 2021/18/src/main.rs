@@ -1,294 +1,110 @@
-fn main() {
-    let input = include_str!("input.txt");
-
-    let answer = part_one(input).expect("no solution for part one");
-    println!("part 1: {}", answer);
+use anyhow::{anyhow, Context, Result};
+use aoc::nested::Nested;
 
-    let answer = part_two(input).expect("no solution for part two");
-    println!("part 2: {}", answer);
+fn main() -> Result<()> {
+    let input = include_str!("input.txt");
+    aoc::run!(part_one(input), 4469)?;
+    aoc::run!(part_two(input), 4770)?;
+    Ok(())
 }
 
-#[derive(Debug, PartialEq)]
-enum Error {
-    BadInput,
-    EmptyStack,
-}
+type Number = Nested<u32>;
 
-#[derive(Debug)]
-enum Node {
-    Node(Box<Node>, Box<Node>),
-    Leaf(u32),
+fn leaf_value(number: &Number, path: &[usize]) -> u32 {
+    *number
+        .get(path)
+        .and_then(|n| n.as_leaf())
+        .expect("path to leaf")
 }
 
-impl Node {
-    // ---- split ----
-    fn split(&mut self) {
-        match *self {
-            Node::Node(_, _) => panic!(),
-            Node::Leaf(value) => {
-                let left = Box::new(Node::Leaf(value / 2));
-                let right = Box::new(Node::Leaf(value.div_ceil(2)));
-                *self = Node::Node(left, right);
-            }
-        }
-    }
-
-    pub fn try_split(&mut self) -> bool {
-        match self {
-            Node::Node(left, right) => left.try_split() || right.try_split(),
-            Node::Leaf(value) => {
-                if *value >= 10 {
-                    self.split();
-                    return true;
-                }
-                false
-            }
-        }
-    }
-
-    pub fn try_explode(&mut self) -> bool {
-        #[derive(Debug, PartialEq)]
-        enum State {
-            SearchingForPairToExplode,
-            SearchingForLeafToAddTo,
-            Done,
-        }
-        #[derive(Debug)]
-        struct Context<'a> {
-            depth: usize,
-            state: State,
-            left_of_exploding: Option<&'a Node>,
-            exploding: Option<&'a Node>,
-            right_of_exploding: Option<&'a Node>,
-        }
-        fn explode_inner<'a>(this: &'a Node, mut ctx: Context<'a>) -> Context<'a> {
-            if ctx.state == State::Done {
-                return ctx;
-            }
-            ctx.depth += 1;
-            match this {
-                Node::Node(left, right) => match ctx.state {
-                    State::SearchingForPairToExplode => {
-                        if ctx.depth == 5 {
-                            ctx.state = State::SearchingForLeafToAddTo;
-                            ctx.exploding = Some(this);
-                        } else {
-                            ctx = explode_inner(left, ctx);
-                            ctx = explode_inner(right, ctx);
-                        }
-                    }
-                    State::SearchingForLeafToAddTo => {
-                        ctx = explode_inner(left, ctx);
-                        ctx = explode_inner(right, ctx);
-                    }
-                    State::Done => unreachable!(),
-                },
-                Node::Leaf(_) => match ctx.state {
-                    State::SearchingForPairToExplode => {
-                        ctx.left_of_exploding = Some(this);
-                    }
-                    State::SearchingForLeafToAddTo => {
-                        ctx.right_of_exploding = Some(this);
-                        ctx.state = State::Done;
-                    }
-                    State::Done => unreachable!(),
-                },
-            }
-            ctx.depth -= 1;
-            ctx
-        }
-        let ctx = explode_inner(
-            self,
-            Context {
-                depth: 0,
-                state: State::SearchingForPairToExplode,
-                left_of_exploding: None,
-                exploding: None,
-                right_of_exploding: None,
-            },
-        );
-
-        #[allow(clippy::transmute_ptr_to_ref)]
-        if let Some(exploding) = ctx.exploding {
-            let (left, right) = match exploding {
-                Node::Node(left, right) => (left, right),
-                Node::Leaf(_) => unreachable!(),
-            };
-            let left = match **left {
-                Node::Node(_, _) => unreachable!(),
-                Node::Leaf(value) => value,
-            };
-            let right = match **right {
-                Node::Node(_, _) => unreachable!(),
-                Node::Leaf(value) => value,
-            };
-
-            if let Some(left_of_exploding) = ctx.left_of_exploding {
-                let ptr: *const Node = left_of_exploding;
-                let left_of_exploding =
-                    unsafe { std::mem::transmute::<*const Node, &mut Node>(ptr) };
-                if let Node::Leaf(value) = left_of_exploding {
-                    *value += left;
-                }
-            }
-
-            if let Some(right_of_exploding) = ctx.right_of_exploding {
-                let ptr: *const Node = right_of_exploding;
-                let right_of_exploding =
-                    unsafe { std::mem::transmute::<*const Node, &mut Node>(ptr) };
-                if let Node::Leaf(value) = right_of_exploding {
-                    *value += right;
-                }
-            }
-
-            let ptr: *const Node = exploding;
-            let exploding = unsafe { std::mem::transmute::<*const Node, &mut Node>(ptr) };
-            *exploding = Node::Leaf(0);
+fn try_split(number: &mut Number) -> bool {
+    for path in number.leaf_paths() {
+        let value = leaf_value(number, &path);
+        if value >= 10 {
+            *number.get_mut(&path).unwrap() = Nested::List(vec![
+                Nested::Leaf(value / 2),
+                Nested::Leaf(value.div_ceil(2)),
+            ]);
             return true;
         }
-        false
-    }
-
-    fn add(self, other: Box<Node>) -> Box<Node> {
-        Box::new(Node::Node(Box::new(self), other))
     }
+    false
+}
 
-    fn reduce(&mut self) {
-        loop {
-            if self.try_explode() {
-                continue;
-            }
-            if self.try_split() {
-                continue;
-            }
-            break;
+fn try_explode(number: &mut Number) -> bool {
+    let paths = number.leaf_paths();
+    // the leftmost pair nested inside four pairs; reduction never nests pairs deeper than that,
+    // so its left leaf is the first leaf at depth 5, and its right leaf follows it
+    let Some(i) = paths.iter().position(|path| path.len() > 4) else {
+        return false;
+    };
+    let left = leaf_value(number, &paths[i]);
+    let right = leaf_value(number, &paths[i + 1]);
+    if i > 0 {
+        if let Some(Nested::Leaf(value)) = number.get_mut(&paths[i - 1]) {
+            *value += left;
         }
     }
-
-    fn magnitude(&self) -> u32 {
-        match self {
-            Node::Node(left, right) => 3 * left.magnitude() + 2 * right.magnitude(),
-            Node::Leaf(value) => *value,
+    if let Some(path) = paths.get(i + 2) {
+        if let Some(Nested::Leaf(value)) = number.get_mut(path) {
+            *value += right;
         }
     }
+    let pair = &paths[i][..paths[i].len() - 1];
+    *number.get_mut(pair).unwrap() = Nested::Leaf(0);
+    true
 }
 
-impl TryFrom<&str> for Box<Node> {
-    type Error = Error;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        #[derive(Debug)]
-        enum Token {
-            Constant(u32),
-            OpeningBrace,
-        }
-
-        fn graph_from_tokens(tokens: &mut Vec<Token>) -> Result<Box<Node>, Error> {
-            match tokens.pop() {
-                None => Err(Error::EmptyStack),
-                Some(Token::Constant(value)) => Ok(Box::new(Node::Leaf(value))),
-                Some(Token::OpeningBrace) => {
-                    let right = graph_from_tokens(tokens)?;
-                    let left = graph_from_tokens(tokens)?;
-                    Ok(Box::new(Node::Node(left, right)))
-                }
-            }
-        }
+fn add(a: Number, b: Number) -> Number {
+    Nested::List(vec![a, b])
+}
 
-        let mut stack = vec![];
-        let mut output = vec![];
-        let mut chars: Vec<_> = value.trim().chars().rev().collect();
-        while let Some(ch) = chars.pop() {
-            match ch {
-                '[' => stack.push(Token::OpeningBrace),
-                #[allow(clippy::never_loop)]
-                ']' => loop {
-                    match stack.pop() {
-                        None => return Err(Error::BadInput),
-                        Some(Token::Constant(_)) => unreachable!(),
-                        Some(Token::OpeningBrace) => {
-                            output.push(Token::OpeningBrace);
-                            break;
-                        }
-                    }
-                },
-                ',' => {} // no-op
-                x if x.is_ascii_digit() => {
-                    let mut s = format!("{}", x);
-                    while let Some(next_ch) = chars.last() {
-                        if next_ch.is_ascii_digit() {
-                            s.push(chars.pop().unwrap());
-                        } else {
-                            break;
-                        }
-                    }
-                    let value: u32 = s.parse().unwrap();
-                    output.push(Token::Constant(value));
-                }
-                _ => return Err(Error::BadInput),
-            }
-        }
+fn reduce(number: &mut Number) {
+    while try_explode(number) || try_split(number) {}
+}
 
-        let graph = graph_from_tokens(&mut output)?;
-        if !output.is_empty() {
-            return Err(Error::BadInput);
-        }
-        Ok(graph)
+fn magnitude(number: &Number) -> u32 {
+    match number {
+        Nested::Leaf(value) => *value,
+        Nested::List(pair) if pair.len() == 2 => 3 * magnitude(&pair[0]) + 2 * magnitude(&pair[1]),
+        _ => panic!("not a snailfish number: {}", number),
     }
 }
 
-impl std::fmt::Display for Box<Node> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &**self {
-            Node::Node(left, right) => {
-                write!(f, "[{},{}]", left, right)
-            }
-            Node::Leaf(value) => {
-                write!(f, "{}", value)
-            }
-        }
-    }
+fn parse_input(input: &str) -> Result<Vec<Number>> {
+    input.lines().map(|line| line.parse()).collect()
 }
 
-#[allow(clippy::vec_box)]
-fn parse_input(input: &str) -> Result<Vec<Box<Node>>, Error> {
-    let mut nodes = vec![];
-    for line in input.lines() {
-        nodes.push(line.try_into()?);
-    }
-    Ok(nodes)
+fn sum(numbers: Vec<Number>) -> Option<Number> {
+    numbers.into_iter().reduce(|a, b| {
+        let mut c = add(a, b);
+        reduce(&mut c);
+        c
+    })
 }
 
-fn part_one(input: &str) -> Result<u32, Error> {
-    let mut nodes: Vec<_> = parse_input(input)?.into_iter().rev().collect();
-    let mut node = nodes.pop().ok_or(Error::BadInput)?;
-    while let Some(other) = nodes.pop() {
-        node = node.add(other);
-        node.reduce();
-    }
-    Ok(node.magnitude())
+fn part_one(input: &str) -> Result<u32> {
+    let number = sum(parse_input(input)?).context("no numbers")?;
+    Ok(magnitude(&number))
 }
 
-fn part_two(input: &str) -> Result<u32, Error> {
+fn part_two(input: &str) -> Result<u32> {
+    let numbers = parse_input(input)?;
     let mut magnitudes = vec![];
-    let lines: Vec<_> = input.lines().collect();
-    for i in 0..lines.len() {
-        for j in 0..lines.len() {
+    for i in 0..numbers.len() {
+        for j in 0..numbers.len() {
             if i == j {
                 continue;
             }
-
-            for pair in [(i, j), (j, i)] {
-                let a: Box<Node> = lines[pair.0].try_into()?;
-                let b: Box<Node> = lines[pair.1].try_into()?;
-                let mut c = a.add(b);
-                c.reduce();
-                magnitudes.push(c.magnitude());
-            }
+            let mut c = add(numbers[i].clone(), numbers[j].clone());
+            reduce(&mut c);
+            magnitudes.push(magnitude(&c));
         }
     }
-    let max = magnitudes.iter().max().ok_or(Error::BadInput)?;
-    Ok(*max)
+    magnitudes
+        .into_iter()
+        .max()
+        .ok_or_else(|| anyhow!("too few numbers"))
 }
 
 #[cfg(test)]
@@ -297,96 +113,91 @@ mod tests {
 
     const INPUT: &str = include_str!("test-input.txt");
 
+    fn number(s: &str) -> Number {
+        s.parse().unwrap()
+    }
+
     #[test]
-    fn test_node_try_from() {
-        macro_rules! assert_encode_decode {
-            ($str:expr) => {
-                let node: Box<Node> = $str.try_into().unwrap();
-                assert_eq!(format!("{}", node), $str);
-            };
+    fn test_parse() {
+        for s in [
+            "[1,2]",
+            "[[1,2],3]",
+            "[9,[8,7]]",
+            "[[1,9],[8,5]]",
+            "[[[[1,2],[3,4]],[[5,6],[7,8]]],9]",
+            "[[[9,[3,8]],[[0,9],6]],[[[3,7],[4,9]],3]]",
+            "[[[[1,3],[5,3]],[[1,3],[8,7]]],[[[4,9],[6,9]],[[8,2],[7,3]]]]",
+        ] {
+            assert_eq!(number(s).to_string(), s);
         }
-        assert_encode_decode!("[1,2]");
-        assert_encode_decode!("[[1,2],3]");
-        assert_encode_decode!("[9,[8,7]]");
-        assert_encode_decode!("[[1,9],[8,5]]");
-        assert_encode_decode!("[[[[1,2],[3,4]],[[5,6],[7,8]]],9]");
-        assert_encode_decode!("[[[9,[3,8]],[[0,9],6]],[[[3,7],[4,9]],3]]");
-        assert_encode_decode!("[[[[1,3],[5,3]],[[1,3],[8,7]]],[[[4,9],[6,9]],[[8,2],[7,3]]]]");
     }
 
     #[test]
     fn test_split() {
-        let mut node: Box<Node> = "[[[[0,7],4],[15,[0,13]]],[1,1]]".try_into().unwrap();
-        assert!(node.try_split());
-        assert_eq!(format!("{}", node), "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]");
+        let mut n = number("[[[[0,7],4],[15,[0,13]]],[1,1]]");
+        assert!(try_split(&mut n));
+        assert_eq!(n.to_string(), "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]");
     }
 
     #[test]
     fn test_explode() {
-        macro_rules! assert_explode {
-            ($before:expr, $after:expr) => {
-                let mut node: Box<Node> = $before.try_into().unwrap();
-                assert!(node.try_explode());
-                assert_eq!(format!("{}", node), $after);
-            };
+        for (before, after) in [
+            ("[[[[[9,8],1],2],3],4]", "[[[[0,9],2],3],4]"),
+            ("[7,[6,[5,[4,[3,2]]]]]", "[7,[6,[5,[7,0]]]]"),
+            ("[[6,[5,[4,[3,2]]]],1]", "[[6,[5,[7,0]]],3]"),
+            (
+                "[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]",
+                "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]",
+            ),
+            (
+                "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]",
+                "[[3,[2,[8,0]]],[9,[5,[7,0]]]]",
+            ),
+        ] {
+            let mut n = number(before);
+            assert!(try_explode(&mut n));
+            assert_eq!(n.to_string(), after);
         }
-        assert_explode!("[[[[[9,8],1],2],3],4]", "[[[[0,9],2],3],4]");
-        assert_explode!("[7,[6,[5,[4,[3,2]]]]]", "[7,[6,[5,[7,0]]]]");
-        assert_explode!("[[6,[5,[4,[3,2]]]],1]", "[[6,[5,[7,0]]],3]");
-        assert_explode!(
-            "[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]",
-            "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]"
-        );
-        assert_explode!(
-            "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]",
-            "[[3,[2,[8,0]]],[9,[5,[7,0]]]]"
-        );
     }
 
     #[test]
     fn test_add() {
-        let a: Box<Node> = "[[[[4,3],4],4],[7,[[8,4],9]]]".try_into().unwrap();
-        let b: Box<Node> = "[1,1]".try_into().unwrap();
+        let a = number("[[[[4,3],4],4],[7,[[8,4],9]]]");
+        let b = number("[1,1]");
         assert_eq!(
-            format!("{}", a.add(b)),
+            add(a, b).to_string(),
             "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]"
         );
     }
 
     #[test]
     fn test_reduce() {
-        let mut node: Box<Node> = "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]".try_into().unwrap();
-        node.reduce();
-        assert_eq!(format!("{}", node), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+        let mut n = number("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]");
+        reduce(&mut n);
+        assert_eq!(n.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
     }
 
     #[test]
     fn test_sum() {
-        let mut nodes: Vec<_> = parse_input(INPUT).unwrap().into_iter().rev().collect();
-        let mut node = nodes.pop().unwrap();
-        while let Some(other) = nodes.pop() {
-            node = node.add(other);
-            node.reduce();
-        }
+        let n = sum(parse_input(INPUT).unwrap()).unwrap();
         assert_eq!(
-            format!("{}", node),
+            n.to_string(),
             "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]"
         );
     }
 
     #[test]
     fn test_magnitude() {
-        let node: Box<Node> = "[[9,1],[1,9]]".try_into().unwrap();
-        assert_eq!(node.magnitude(), 129);
+        assert_eq!(magnitude(&number("[[9,1],[1,9]]")), 129);
     }
 
     #[test]
     fn test_part_one() {
-        assert_eq!(part_one(INPUT), Ok(4140));
+        assert_eq!(part_one(INPUT).unwrap(), 4140);
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(part_two(INPUT), Ok(3993));
+        assert_eq!(part_two(INPUT).unwrap(), 3993);
     }
 }
@@ -0,0 +1,496 @@
+use anyhow::{anyhow, bail, ensure, Result};
+
+fn main() -> Result<()> {
+    let input = include_str!("input.txt");
+    aoc::run!(part_one(input), 92793949489995)?;
+    aoc::run!(part_two(input), 51131616112781)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Register {
+    W,
+    X,
+    Y,
+    Z,
+}
+
+impl Register {
+    fn index(&self) -> usize {
+        match self {
+            Register::W => 0,
+            Register::X => 1,
+            Register::Y => 2,
+            Register::Z => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Register(Register),
+    Literal(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    Inp(Register),
+    Add(Register, Operand),
+    Mul(Register, Operand),
+    Div(Register, Operand),
+    Mod(Register, Operand),
+    Eql(Register, Operand),
+}
+
+fn parse_register(s: &str) -> Result<Register> {
+    match s {
+        "w" => Ok(Register::W),
+        "x" => Ok(Register::X),
+        "y" => Ok(Register::Y),
+        "z" => Ok(Register::Z),
+        _ => bail!("bad register '{s}'"),
+    }
+}
+
+fn parse_operand(s: &str) -> Result<Operand> {
+    if let Ok(register) = parse_register(s) {
+        return Ok(Operand::Register(register));
+    }
+    Ok(Operand::Literal(
+        s.parse().map_err(|_| anyhow!("bad operand '{s}'"))?,
+    ))
+}
+
+fn parse(input: &str) -> Result<Vec<Instruction>> {
+    let mut program = vec![];
+    for line in input.lines() {
+        let words: Vec<_> = line.split_whitespace().collect();
+        let instr = match words[..] {
+            ["inp", a] => Instruction::Inp(parse_register(a)?),
+            ["add", a, b] => Instruction::Add(parse_register(a)?, parse_operand(b)?),
+            ["mul", a, b] => Instruction::Mul(parse_register(a)?, parse_operand(b)?),
+            ["div", a, b] => Instruction::Div(parse_register(a)?, parse_operand(b)?),
+            ["mod", a, b] => Instruction::Mod(parse_register(a)?, parse_operand(b)?),
+            ["eql", a, b] => Instruction::Eql(parse_register(a)?, parse_operand(b)?),
+            _ => bail!("bad instruction '{line}'"),
+        };
+        program.push(instr);
+    }
+    Ok(program)
+}
+
+/// Run the program on concrete input and return the final values of the w, x, y and z registers.
+fn execute(program: &[Instruction], input: &[i64]) -> Result<[i64; 4]> {
+    let mut registers = [0; 4];
+    let mut input = input.iter();
+    for instr in program {
+        let value = |b: &Operand| match b {
+            Operand::Register(r) => registers[r.index()],
+            Operand::Literal(n) => *n,
+        };
+        match instr {
+            Instruction::Inp(a) => {
+                registers[a.index()] = *input.next().ok_or_else(|| anyhow!("out of input"))?;
+            }
+            Instruction::Add(a, b) => registers[a.index()] += value(b),
+            Instruction::Mul(a, b) => registers[a.index()] *= value(b),
+            Instruction::Div(a, b) => {
+                let b = value(b);
+                ensure!(b != 0, "division by zero");
+                registers[a.index()] /= b;
+            }
+            Instruction::Mod(a, b) => {
+                let b = value(b);
+                ensure!(registers[a.index()] >= 0 && b > 0, "bad modulo operands");
+                registers[a.index()] %= b;
+            }
+            Instruction::Eql(a, b) => {
+                registers[a.index()] = (registers[a.index()] == value(b)) as i64;
+            }
+        }
+    }
+    Ok(registers)
+}
+
+// The MONAD program treats z as a stack of base 26 "limbs". Each of the 14 input blocks either
+// pushes input[i] + c onto the stack, or pops the top of the stack and compares it to the input
+// digit, pushing a new value if the two differ. For z to end up as 0, every pop must match.
+//
+// Rather than hard-coding this structure, execute the program symbolically: register values are
+// kept as a sum of base 26 limbs, where each limb is a constant or input[i] + c. Whenever an
+// eql instruction compares two values that may or may not be equal, the execution forks in two:
+// one where the values are equal and one where they differ, each remembering the assumption as a
+// constraint on the input digits. The paths that end with z == 0 give the constraints the model
+// number must satisfy.
+const BASE: i64 = 26;
+
+/// input[digit] + offset, or only offset if digit is None.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Term {
+    digit: Option<usize>,
+    offset: i64,
+}
+
+impl Term {
+    fn constant(offset: i64) -> Self {
+        Term {
+            digit: None,
+            offset,
+        }
+    }
+
+    fn range(&self) -> (i64, i64) {
+        match self.digit {
+            Some(_) => (1 + self.offset, 9 + self.offset),
+            None => (self.offset, self.offset),
+        }
+    }
+
+    fn is_limb(&self) -> bool {
+        let (min, max) = self.range();
+        min >= 0 && max < BASE
+    }
+
+    fn possible_values(&self, assignment: &[Option<i64>]) -> Vec<i64> {
+        match self.digit {
+            Some(digit) => match assignment[digit] {
+                Some(value) => vec![value + self.offset],
+                None => (1..=9).map(|value| value + self.offset).collect(),
+            },
+            None => vec![self.offset],
+        }
+    }
+}
+
+/// The sum of limbs[k] * BASE^k. If there is more than one limb, each limb is in [0, BASE).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Value {
+    limbs: Vec<Term>,
+}
+
+impl Value {
+    fn constant(n: i64) -> Self {
+        Value::from(Term::constant(n))
+    }
+
+    fn as_constant(&self) -> Option<i64> {
+        match self.limbs[..] {
+            [Term {
+                digit: None,
+                offset,
+            }] => Some(offset),
+            _ => None,
+        }
+    }
+
+    fn as_term(&self) -> Option<Term> {
+        match self.limbs[..] {
+            [term] => Some(term),
+            _ => None,
+        }
+    }
+
+    fn is_base_representable(&self) -> bool {
+        self.limbs.len() > 1 || self.limbs[0].is_limb()
+    }
+}
+
+impl From<Term> for Value {
+    fn from(term: Term) -> Self {
+        Value { limbs: vec![term] }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Constraint {
+    lhs: Term,
+    rhs: Term,
+    equal: bool,
+}
+
+impl Constraint {
+    fn is_satisfiable(&self, assignment: &[Option<i64>]) -> bool {
+        if let (Some(a), Some(b)) = (self.lhs.digit, self.rhs.digit) {
+            if a == b && assignment[a].is_none() {
+                return (self.lhs.offset == self.rhs.offset) == self.equal;
+            }
+        }
+        let rhs = self.rhs.possible_values(assignment);
+        self.lhs
+            .possible_values(assignment)
+            .iter()
+            .any(|a| rhs.iter().any(|b| (a == b) == self.equal))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    registers: [Value; 4],
+    next_digit: usize,
+    constraints: Vec<Constraint>,
+}
+
+impl State {
+    fn new() -> Self {
+        State {
+            registers: [
+                Value::constant(0),
+                Value::constant(0),
+                Value::constant(0),
+                Value::constant(0),
+            ],
+            next_digit: 0,
+            constraints: vec![],
+        }
+    }
+
+    fn value(&self, operand: &Operand) -> Value {
+        match operand {
+            Operand::Register(r) => self.registers[r.index()].clone(),
+            Operand::Literal(n) => Value::constant(*n),
+        }
+    }
+
+    fn step(mut self, instr: &Instruction) -> Result<Vec<State>> {
+        let (dest, result) = match instr {
+            Instruction::Inp(a) => {
+                let term = Term {
+                    digit: Some(self.next_digit),
+                    offset: 0,
+                };
+                self.next_digit += 1;
+                (a, Value::from(term))
+            }
+            Instruction::Add(a, b) => (a, add(&self.registers[a.index()], &self.value(b))?),
+            Instruction::Mul(a, b) => (a, mul(&self.registers[a.index()], &self.value(b))?),
+            Instruction::Div(a, b) => (a, div(&self.registers[a.index()], &self.value(b))?),
+            Instruction::Mod(a, b) => (a, modulo(&self.registers[a.index()], &self.value(b))?),
+            Instruction::Eql(a, b) => {
+                let (lhs, rhs) = (&self.registers[a.index()], &self.value(b));
+                let (Some(lhs), Some(rhs)) = (lhs.as_term(), rhs.as_term()) else {
+                    bail!("unsupported eql: {lhs:?} == {rhs:?}");
+                };
+                let ((lhs_min, lhs_max), (rhs_min, rhs_max)) = (lhs.range(), rhs.range());
+                if lhs == rhs {
+                    (a, Value::constant(1))
+                } else if lhs_max < rhs_min || rhs_max < lhs_min || lhs.digit == rhs.digit {
+                    (a, Value::constant(0))
+                } else {
+                    let mut other = self.clone();
+                    self.registers[a.index()] = Value::constant(1);
+                    self.constraints.push(Constraint {
+                        lhs,
+                        rhs,
+                        equal: true,
+                    });
+                    other.registers[a.index()] = Value::constant(0);
+                    other.constraints.push(Constraint {
+                        lhs,
+                        rhs,
+                        equal: false,
+                    });
+                    return Ok(vec![self, other]);
+                }
+            }
+        };
+        self.registers[dest.index()] = result;
+        Ok(vec![self])
+    }
+}
+
+fn add(a: &Value, b: &Value) -> Result<Value> {
+    if b.as_constant() == Some(0) {
+        return Ok(a.clone());
+    }
+    if a.as_constant() == Some(0) {
+        return Ok(b.clone());
+    }
+    if let (Some(x), Some(y)) = (a.as_term(), b.as_term()) {
+        if x.digit.is_none() || y.digit.is_none() {
+            return Ok(Value::from(Term {
+                digit: x.digit.or(y.digit),
+                offset: x.offset + y.offset,
+            }));
+        }
+    }
+    if let Some(y) = b.as_term() {
+        if a.limbs.len() > 1 && a.limbs[0] == Term::constant(0) && y.is_limb() {
+            let mut value = a.clone();
+            value.limbs[0] = y;
+            return Ok(value);
+        }
+    }
+    bail!("unsupported add: {a:?} + {b:?}");
+}
+
+fn mul(a: &Value, b: &Value) -> Result<Value> {
+    match (a.as_constant(), b.as_constant()) {
+        (Some(0), _) | (_, Some(0)) => Ok(Value::constant(0)),
+        (_, Some(1)) => Ok(a.clone()),
+        (Some(1), _) => Ok(b.clone()),
+        (Some(x), Some(y)) => Ok(Value::constant(x * y)),
+        (_, Some(BASE)) if a.is_base_representable() => {
+            let mut value = a.clone();
+            value.limbs.insert(0, Term::constant(0));
+            Ok(value)
+        }
+        _ => bail!("unsupported mul: {a:?} * {b:?}"),
+    }
+}
+
+fn div(a: &Value, b: &Value) -> Result<Value> {
+    match (a.as_constant(), b.as_constant()) {
+        (_, Some(0)) => bail!("division by zero"),
+        (_, Some(1)) => Ok(a.clone()),
+        (Some(x), Some(y)) => Ok(Value::constant(x / y)),
+        (_, Some(BASE)) if a.limbs.len() > 1 => Ok(Value {
+            limbs: a.limbs[1..].to_vec(),
+        }),
+        (_, Some(BASE)) if a.is_base_representable() => Ok(Value::constant(0)),
+        _ => bail!("unsupported div: {a:?} / {b:?}"),
+    }
+}
+
+fn modulo(a: &Value, b: &Value) -> Result<Value> {
+    match (a.as_constant(), b.as_constant()) {
+        (Some(x), Some(y)) if x >= 0 && y > 0 => Ok(Value::constant(x % y)),
+        (_, Some(BASE)) if a.is_base_representable() => Ok(Value::from(a.limbs[0])),
+        _ => bail!("unsupported mod: {a:?} % {b:?}"),
+    }
+}
+
+/// Symbolically execute the program. Returns the number of input digits, and for every
+/// execution path that ends with z == 0, the constraints on the input digits along that path.
+fn analyse(program: &[Instruction]) -> Result<(usize, Vec<Vec<Constraint>>)> {
+    let mut states = vec![State::new()];
+    for instr in program {
+        let mut next_states = vec![];
+        for state in states {
+            next_states.extend(state.step(instr)?);
+        }
+        states = next_states;
+    }
+    let num_digits = states.first().map(|state| state.next_digit).unwrap_or(0);
+    Ok((
+        num_digits,
+        states
+            .into_iter()
+            .filter(|state| state.registers[Register::Z.index()].as_constant() == Some(0))
+            .map(|state| state.constraints)
+            .collect(),
+    ))
+}
+
+/// Assign the digits from left to right, trying the digits in the given order, and return the
+/// first assignment that satisfies all constraints.
+fn assign_digits(
+    assignment: &mut Vec<Option<i64>>,
+    constraints: &[Constraint],
+    order: &[i64],
+) -> bool {
+    let Some(index) = assignment.iter().position(|digit| digit.is_none()) else {
+        return true;
+    };
+    for digit in order {
+        assignment[index] = Some(*digit);
+        if constraints.iter().all(|c| c.is_satisfiable(assignment))
+            && assign_digits(assignment, constraints, order)
+        {
+            return true;
+        }
+    }
+    assignment[index] = None;
+    false
+}
+
+fn find_model_number(input: &str, largest: bool) -> Result<u64> {
+    let order: Vec<i64> = if largest {
+        (1..=9).rev().collect()
+    } else {
+        (1..=9).collect()
+    };
+    let program = parse(input)?;
+    let (num_digits, paths) = analyse(&program)?;
+    let mut candidates = vec![];
+    for constraints in paths {
+        let mut assignment = vec![None; num_digits];
+        if assign_digits(&mut assignment, &constraints, &order) {
+            let digits: Vec<i64> = assignment.into_iter().flatten().collect();
+            ensure!(
+                execute(&program, &digits)?[Register::Z.index()] == 0,
+                "{digits:?} does not pass MONAD verification"
+            );
+            candidates.push(digits.iter().fold(0, |acc, d| acc * 10 + *d as u64));
+        }
+    }
+    let best = if largest {
+        candidates.into_iter().max()
+    } else {
+        candidates.into_iter().min()
+    };
+    best.ok_or_else(|| anyhow!("no valid model number"))
+}
+
+fn part_one(input: &str) -> Result<u64> {
+    find_model_number(input, true)
+}
+
+fn part_two(input: &str) -> Result<u64> {
+    find_model_number(input, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("test-input.txt");
+
+    #[test]
+    fn test_execute() {
+        let negate = parse("inp x\nmul x -1").unwrap();
+        assert_eq!(execute(&negate, &[7]).unwrap(), [0, -7, 0, 0]);
+
+        let three_times = parse("inp z\ninp x\nmul z 3\neql z x").unwrap();
+        assert_eq!(execute(&three_times, &[2, 6]).unwrap()[3], 1);
+        assert_eq!(execute(&three_times, &[2, 7]).unwrap()[3], 0);
+
+        let binary = parse(
+            "inp w\nadd z w\nmod z 2\ndiv w 2\nadd y w\nmod y 2\ndiv w 2\nadd x w\nmod x 2\ndiv w 2\nmod w 2",
+        )
+        .unwrap();
+        assert_eq!(execute(&binary, &[13]).unwrap(), [1, 1, 0, 1]);
+
+        assert!(execute(&negate, &[]).is_err());
+    }
+
+    #[test]
+    fn test_analyse() {
+        // push input[0] + 2, then pop and compare with input[1]
+        let (num_digits, paths) = analyse(&parse(INPUT).unwrap()).unwrap();
+        assert_eq!(num_digits, 2);
+        assert_eq!(
+            paths,
+            vec![vec![Constraint {
+                lhs: Term {
+                    digit: Some(0),
+                    offset: 2,
+                },
+                rhs: Term {
+                    digit: Some(1),
+                    offset: 0,
+                },
+                equal: true,
+            }]]
+        );
+    }
+
+    #[test]
+    fn test_part_one() {
+        assert_eq!(part_one(INPUT).unwrap(), 79);
+    }
+
+    #[test]
+    fn test_part_two() {
+        assert_eq!(part_two(INPUT).unwrap(), 13);
+    }
+}
@@ -1,29 +1,51 @@
 use anyhow::{bail, Context, Result};
+use aoc::vm::{Flow, Instruction, Machine};
 
 fn main() -> Result<()> {
     let input = include_str!("input.txt");
     aoc::run!(part_one(input), 15220)?;
-    println!("{}", part_two(input)?); // "RFZEKBFA"
+    aoc::run!(part_two(input), "RFZEKBFA")?;
     Ok(())
 }
 
-fn parse(input: &str) -> Result<Vec<i32>> {
-    let mut values: Vec<i32> = vec![1];
-    for line in input.lines() {
-        match &line[..4] {
-            "noop" => {
-                values.push(*values.last().unwrap());
-            }
-            "addx" => {
-                let (_, term) = line.split_once(' ').context("bad input")?;
-                let term: i32 = term.parse().context("failed to convert to i32")?;
-                let last = *values.last().unwrap();
-                values.push(last);
-                values.push(last + term);
-            }
+#[derive(Debug, Clone)]
+enum Op {
+    Noop,
+    Addx(i32),
+}
+
+impl Instruction for Op {
+    type State = i32;
+
+    fn parse(line: &str) -> Result<Self> {
+        match line.split_once(' ') {
+            None if line == "noop" => Ok(Op::Noop),
+            Some(("addx", term)) => Ok(Op::Addx(term.parse().context("failed to convert to i32")?)),
             _ => bail!("unexpected input '{}'", line),
         }
     }
+
+    fn execute(&self, x: &mut i32) -> Result<Flow> {
+        if let Op::Addx(term) = self {
+            *x += term;
+        }
+        Ok(Flow::Next)
+    }
+
+    fn cycles(&self) -> usize {
+        match self {
+            Op::Noop => 1,
+            Op::Addx(_) => 2,
+        }
+    }
+}
+
+// Returns the value of the X register during each cycle, followed by its final value
+fn parse(input: &str) -> Result<Vec<i32>> {
+    let mut machine: Machine<Op> = Machine::parse(input, 1)?;
+    let mut values: Vec<i32> = vec![];
+    machine.run_traced(|_, x| values.push(*x))?;
+    values.push(machine.state);
     Ok(values)
 }
 
@@ -36,7 +58,8 @@ fn part_one(input: &str) -> Result<i32> {
     Ok(sum)
 }
 
-fn part_two(input: &str) -> Result<String> {
+// Returns the CRT output, drawn with '#' and '.'
+fn render(input: &str) -> Result<String> {
     let values = parse(input)?;
     let mut output = String::new();
     for (i, value) in values.iter().enumerate().take(240) {
@@ -52,6 +75,10 @@ fn part_two(input: &str) -> Result<String> {
     Ok(output)
 }
 
+fn part_two(input: &str) -> Result<String> {
+    aoc::ocr(&render(input)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -75,8 +102,8 @@ mod tests {
     }
 
     #[test]
-    fn test_part_two() {
+    fn test_render() {
         let expected = "##..##..##..##..##..##..##..##..##..##..\n###...###...###...###...###...###...###.\n####....####....####....####....####....\n#####.....#####.....#####.....#####.....\n######......######......######......####\n#######.......#######.......#######.....";
-        assert_eq!(part_two(INPUT).unwrap(), expected);
+        assert_eq!(render(INPUT).unwrap(), expected);
     }
 }
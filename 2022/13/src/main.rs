@@ -1,4 +1,5 @@
-use anyhow::{ensure, Context, Result};
+use anyhow::{Context, Result};
+use aoc::nested::Nested;
 use std::cmp::Ordering;
 
 fn main() -> Result<()> {
@@ -8,158 +9,7 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-struct InputIterator<'a> {
-    string: &'a str,
-    index: usize,
-}
-
-impl<'a> InputIterator<'a> {
-    fn new(string: &'a str) -> Self {
-        InputIterator { string, index: 0 }
-    }
-}
-
-/// Split by any of the delimiters [],
-/// Keep delimiter if any of []
-/// Discard delimiter if ,
-impl<'a> Iterator for InputIterator<'a> {
-    type Item = &'a str;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.index >= self.string.len() {
-            return None;
-        }
-        let start = self.index;
-        let mut end = self.index;
-        for ch in self.string.chars().skip(start) {
-            match ch {
-                '[' => {
-                    self.index += 1;
-                    end += 1;
-                    break;
-                }
-                ',' => {
-                    self.index += 1;
-                    break;
-                }
-                ']' => {
-                    if start != end {
-                        break;
-                    }
-                    self.index += 1;
-                    end += 1;
-                }
-                _ => {
-                    self.index += 1;
-                    end += 1;
-                }
-            };
-        }
-        Some(&self.string[start..end])
-    }
-}
-
-#[derive(Debug, PartialEq, Clone)]
-enum Element {
-    Constant(i32),
-    List(Vec<Element>),
-}
-
-impl TryFrom<&str> for Element {
-    type Error = anyhow::Error;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let mut current: Vec<Element> = vec![];
-        let mut stack: Vec<Element> = vec![];
-        for substring in InputIterator::new(value) {
-            match substring {
-                "[" => {
-                    stack.push(Element::List(current));
-                    current = vec![];
-                }
-                "]" => {
-                    let tmp = stack.pop().context("unbalanced [] braces: unexpected ]")?;
-                    if let Element::List(mut v) = tmp {
-                        v.push(Element::List(current));
-                        current = v;
-                    } else {
-                        panic!("cannot happen");
-                    }
-                }
-                _ => {
-                    let value: i32 = substring
-                        .parse()
-                        .with_context(|| format!("failed to convert '{}' to i32", substring))?;
-                    current.push(Element::Constant(value));
-                }
-            }
-        }
-        ensure!(stack.is_empty(), "unbalanced [] braces: missing ]");
-        ensure!(current.len() == 1, "failed to parse '{}'", value);
-        Ok(current.pop().unwrap())
-    }
-}
-
-#[derive(Debug, PartialEq)]
-enum ElementOrdering {
-    Correct,
-    Incorrect,
-    Undecided,
-}
-
-impl From<ElementOrdering> for Ordering {
-    fn from(eo: ElementOrdering) -> Self {
-        match eo {
-            ElementOrdering::Correct => Ordering::Less,
-            ElementOrdering::Incorrect => Ordering::Greater,
-            ElementOrdering::Undecided => Ordering::Equal,
-        }
-    }
-}
-
-impl From<Ordering> for ElementOrdering {
-    fn from(o: Ordering) -> Self {
-        match o {
-            Ordering::Less => ElementOrdering::Correct,
-            Ordering::Equal => ElementOrdering::Undecided,
-            Ordering::Greater => ElementOrdering::Incorrect,
-        }
-    }
-}
-
-fn order_of(left: &Element, right: &Element) -> ElementOrdering {
-    if let (&Element::Constant(l), &Element::Constant(r)) = (&left, &right) {
-        l.cmp(r).into()
-    } else if let (&Element::List(l), &Element::List(r)) = (&left, &right) {
-        for i in 0..l.len() {
-            if i >= r.len() {
-                return ElementOrdering::Incorrect;
-            }
-            match order_of(&l[i], &r[i]) {
-                ElementOrdering::Correct => {
-                    return ElementOrdering::Correct;
-                }
-                ElementOrdering::Incorrect => {
-                    return ElementOrdering::Incorrect;
-                }
-                ElementOrdering::Undecided => {
-                    // keep going through the elements
-                }
-            }
-        }
-        if l.len() == r.len() {
-            ElementOrdering::Undecided
-        } else {
-            ElementOrdering::Correct
-        }
-    } else if let (&Element::Constant(l), &Element::List(_)) = (&left, &right) {
-        order_of(&Element::List(vec![Element::Constant(*l)]), right)
-    } else if let (&Element::List(_), &Element::Constant(r)) = (&left, &right) {
-        order_of(left, &Element::List(vec![Element::Constant(*r)]))
-    } else {
-        panic!("cannot happen");
-    }
-}
+type Packet = Nested<i32>;
 
 fn part_one(input: &str) -> Result<usize> {
     let mut sum = 0;
@@ -167,9 +17,9 @@ fn part_one(input: &str) -> Result<usize> {
         let i = i + 1;
         let chunk = chunk.trim();
         let (left, right) = chunk.split_once('\n').context("bad input")?;
-        let left: Element = left.try_into()?;
-        let right: Element = right.try_into()?;
-        if order_of(&left, &right) == ElementOrdering::Correct {
+        let left: Packet = left.parse()?;
+        let right: Packet = right.parse()?;
+        if left.compare(&right) == Ordering::Less {
             sum += i;
         }
     }
@@ -177,18 +27,24 @@ fn part_one(input: &str) -> Result<usize> {
 }
 
 fn part_two(input: &str) -> Result<usize> {
-    let mut packets: Vec<Element> = vec![];
-    let extra1: Element = "[[2]]".try_into()?;
-    let extra2: Element = "[[6]]".try_into()?;
+    let mut packets: Vec<Packet> = vec![];
     for line in input.split('\n').filter(|line| !line.is_empty()) {
-        packets.push(line.try_into()?);
+        packets.push(line.parse()?);
     }
-    packets.push(extra1.clone());
-    packets.push(extra2.clone());
-    packets.sort_by(|a, b| order_of(a, b).into());
-    let a = packets.iter().position(|e| e == &extra1).unwrap() + 1;
-    let b = packets.iter().position(|e| e == &extra2).unwrap() + 1;
-    Ok(a * b)
+    // the 1-based index of each divider in the sorted packets: the dividers themselves are in
+    // order, so divider i comes after i other dividers and every packet that sorts before it
+    let dividers: [Packet; 2] = ["[[2]]".parse()?, "[[6]]".parse()?];
+    Ok(dividers
+        .iter()
+        .enumerate()
+        .map(|(i, divider)| {
+            let before = packets
+                .iter()
+                .filter(|packet| packet.compare(divider) == Ordering::Less)
+                .count();
+            before + i + 1
+        })
+        .product())
 }
 
 #[cfg(test)]
@@ -198,34 +54,18 @@ mod tests {
     const INPUT: &str = include_str!("test-input.txt");
 
     #[test]
-    fn test_input_iterator() {
-        let iter = InputIterator::new("[1,2,[[30]],40,50]");
-        let actual: Vec<&str> = iter.collect();
-        let expected: Vec<&str> = vec!["[", "1", "2", "[", "[", "30", "]", "]", "40", "50", "]"];
-        dbg!(&actual);
-        dbg!(&expected);
-        assert_eq!(actual, expected);
-    }
-
-    #[test]
-    fn test_element() {
-        let element: Element = "[]".try_into().unwrap();
-        assert_eq!(element, Element::List(vec![]));
-
-        let element: Element = "[1,2]".try_into().unwrap();
-        assert_eq!(
-            element,
-            Element::List(vec![Element::Constant(1), Element::Constant(2)])
-        );
+    fn test_packet() {
+        let packet: Packet = "[]".parse().unwrap();
+        assert_eq!(packet, Nested::List(vec![]));
 
-        let element: Element = "[[1,2],[3],4,[5]]".try_into().unwrap();
+        let packet: Packet = "[[1,2],[3],4,[5]]".parse().unwrap();
         assert_eq!(
-            element,
-            Element::List(vec![
-                Element::List(vec![Element::Constant(1), Element::Constant(2)]),
-                Element::List(vec![Element::Constant(3)]),
-                Element::Constant(4),
-                Element::List(vec![Element::Constant(5)]),
+            packet,
+            Nested::List(vec![
+                Nested::List(vec![Nested::Leaf(1), Nested::Leaf(2)]),
+                Nested::List(vec![Nested::Leaf(3)]),
+                Nested::Leaf(4),
+                Nested::List(vec![Nested::Leaf(5)]),
             ])
         );
     }
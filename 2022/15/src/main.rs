@@ -1,7 +1,7 @@
 use std::collections::{HashMap, HashSet};
 
 use anyhow::{bail, Context, Result};
-use regex::{Captures, Regex};
+use aoc::parse::{integer_array, parse_lines};
 
 fn main() -> Result<()> {
     let input = include_str!("input.txt");
@@ -100,23 +100,10 @@ fn manhattan_distance(a: &XY, b: &XY) -> u32 {
 }
 
 fn parse(input: &str) -> Result<Space> {
-    fn to_i32(caps: &Captures, index: usize) -> Result<i32> {
-        caps.get(index)
-            .unwrap()
-            .as_str()
-            .parse()
-            .context("failed to convert to i32")
-    }
-
-    let regex =
-        Regex::new(r"Sensor at x=(-?\d+), y=(-?\d+): closest beacon is at x=(-?\d+), y=(-?\d+)")
-            .unwrap();
     let mut objects = HashSet::new();
     let mut ranges: HashMap<i32, Vec<_>> = HashMap::new();
-    for line in input.lines() {
-        let caps = regex.captures(line).context("line does not match regex")?;
-        let sensor = (to_i32(&caps, 1)?, to_i32(&caps, 2)?);
-        let beacon = (to_i32(&caps, 3)?, to_i32(&caps, 4)?);
+    for [sx, sy, bx, by] in parse_lines(input, integer_array)? {
+        let (sensor, beacon) = ((sx, sy), (bx, by));
         objects.insert(sensor);
         objects.insert(beacon);
 
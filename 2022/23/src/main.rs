@@ -190,6 +190,7 @@ fn part_one(input: &str) -> Result<usize> {
     let mut grid = Grid::try_from(input)?;
     for _ in 0..10 {
         grid = grid.next();
+        aoc::visualise::frame(|| format!("{:?}", grid));
     }
     grid.score()
 }
@@ -199,6 +200,7 @@ fn part_two(input: &str) -> Result<usize> {
     let mut previous = grid.cells.clone();
     for i in 1.. {
         grid = grid.next();
+        aoc::visualise::frame(|| format!("{:?}", grid));
         if previous == grid.cells {
             return Ok(i);
         }
@@ -1,5 +1,5 @@
-use anyhow::{bail, ensure, Result};
-use num::integer::lcm;
+use anyhow::{bail, ensure, Context, Result};
+use aoc::math::lcm;
 use std::collections::BTreeMap;
 
 fn main() -> Result<()> {
@@ -88,7 +88,7 @@ fn part_two(input: &str) -> Result<usize> {
     for start in start_state.iter() {
         for end in end_state.iter() {
             if let Ok(x) = solve(input, start, end) {
-                steps = lcm(steps, x);
+                steps = lcm(steps, x).context("overflow")?;
             }
         }
     }
@@ -1,6 +1,5 @@
-use std::collections::HashMap;
-
 use anyhow::{anyhow, Result};
+use aoc::Memo;
 
 fn main() -> Result<()> {
     let input = include_str!("input.txt");
@@ -9,7 +8,7 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum State {
     On,
     Off,
@@ -46,37 +45,24 @@ fn parse(input: &str) -> Result<Vec<(Vec<State>, Vec<usize>)>> {
     Ok(out)
 }
 
-fn to_hash_key(states: &[State], group_sizes: &[usize]) -> String {
-    let states = states
-        .iter()
-        .map(|state| match state {
-            State::On => '#',
-            State::Off => '.',
-            State::Unknown => '?',
-        })
-        .collect::<String>();
-    let group_sizes = group_sizes
-        .iter()
-        .map(|size| size.to_string())
-        .collect::<Vec<_>>()
-        .join(",");
-    format!("{} {}", states, group_sizes)
-}
-
 fn possible_arrangements(
     states: &[State],
     group_sizes: &[usize],
-    cache: &mut HashMap<String, usize>,
+    memo: &mut Memo<(Vec<State>, Vec<usize>), usize>,
 ) -> usize {
     if group_sizes.is_empty() {
         let x = if states.contains(&State::On) { 0 } else { 1 };
         return x;
     }
-    let key = to_hash_key(states, group_sizes);
-    if let Some(value) = cache.get(&key) {
-        return *value;
-    }
+    let key = (states.to_vec(), group_sizes.to_vec());
+    memo.get_or_insert_with(key, |memo| count_arrangements(states, group_sizes, memo))
+}
 
+fn count_arrangements(
+    states: &[State],
+    group_sizes: &[usize],
+    memo: &mut Memo<(Vec<State>, Vec<usize>), usize>,
+) -> usize {
     let size = group_sizes[0];
     assert!(size > 0);
     if size > states.len() {
@@ -106,17 +92,16 @@ fn possible_arrangements(
         };
         let sub_states = &states[end..];
         let sub_group_sizes = &group_sizes[1..];
-        sum += possible_arrangements(sub_states, sub_group_sizes, cache);
+        sum += possible_arrangements(sub_states, sub_group_sizes, memo);
     }
-    cache.insert(key, sum);
     sum
 }
 
 fn part_one(input: &str) -> Result<usize> {
     let mut sum = 0;
-    let mut cache = HashMap::new();
+    let mut memo = Memo::default();
     for (template, expected) in parse(input)?.into_iter() {
-        sum += possible_arrangements(&template, &expected, &mut cache);
+        sum += possible_arrangements(&template, &expected, &mut memo);
     }
     Ok(sum)
 }
@@ -145,8 +130,8 @@ mod tests {
                 let tmp = parse($input).unwrap();
                 assert_eq!(tmp.len(), 1);
                 let (states, groups) = &tmp[0];
-                let mut cache = HashMap::new();
-                assert_eq!(possible_arrangements(states, groups, &mut cache), $expected);
+                let mut memo = Memo::default();
+                assert_eq!(possible_arrangements(states, groups, &mut memo), $expected);
             };
         }
 
@@ -1,8 +1,9 @@
 use anyhow::{bail, ensure, Result};
+use std::collections::{HashSet, VecDeque};
 
 fn main() -> Result<()> {
     let input = include_str!("input.txt");
-    aoc::run!(part_one(input), 40745)?;
+    aoc::run!(aoc::variants!(input, part_one, part_one_flood_fill), 40745)?;
     aoc::run!(part_two(input), 90_111_113_594_927)?;
     Ok(())
 }
@@ -15,7 +16,7 @@ enum Op {
     L(usize),
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 struct XY {
     x: i64,
     y: i64,
@@ -117,10 +118,56 @@ fn solve(ops: Vec<Op>) -> Result<usize> {
     Ok(area as usize + perimiter / 2 + 1)
 }
 
+// Dig out the trench one cube at a time, and flood fill the outside: only feasible for the
+// small distances in part one, but a good check of the shoelace formula.
+fn flood_fill(ops: Vec<Op>) -> Result<usize> {
+    let mut xy: XY = (0, 0).into();
+    let mut trench = HashSet::from([xy]);
+    for op in ops {
+        let ((dx, dy), steps) = match op {
+            Op::U(steps) => ((0, -1), steps),
+            Op::R(steps) => ((1, 0), steps),
+            Op::D(steps) => ((0, 1), steps),
+            Op::L(steps) => ((-1, 0), steps),
+        };
+        for _ in 0..steps {
+            xy = (xy.x + dx, xy.y + dy).into();
+            trench.insert(xy);
+        }
+    }
+
+    // one cube of margin, so the outside is connected
+    let min_x = trench.iter().map(|xy| xy.x).min().unwrap() - 1;
+    let max_x = trench.iter().map(|xy| xy.x).max().unwrap() + 1;
+    let min_y = trench.iter().map(|xy| xy.y).min().unwrap() - 1;
+    let max_y = trench.iter().map(|xy| xy.y).max().unwrap() + 1;
+    let start: XY = (min_x, min_y).into();
+    let mut outside = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(xy) = queue.pop_front() {
+        for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let next: XY = (xy.x + dx, xy.y + dy).into();
+            if (min_x..=max_x).contains(&next.x)
+                && (min_y..=max_y).contains(&next.y)
+                && !trench.contains(&next)
+                && outside.insert(next)
+            {
+                queue.push_back(next);
+            }
+        }
+    }
+    let total = (max_x - min_x + 1) * (max_y - min_y + 1);
+    Ok(total as usize - outside.len())
+}
+
 fn part_one(input: &str) -> Result<usize> {
     solve(parse_part_one(input)?)
 }
 
+fn part_one_flood_fill(input: &str) -> Result<usize> {
+    flood_fill(parse_part_one(input)?)
+}
+
 fn part_two(input: &str) -> Result<usize> {
     solve(parse_part_two(input)?)
 }
@@ -133,7 +180,10 @@ mod tests {
 
     #[test]
     fn test_part_one() {
-        assert_eq!(part_one(INPUT).unwrap(), 62);
+        assert_eq!(
+            aoc::variants!(INPUT, part_one, part_one_flood_fill).unwrap(),
+            62
+        );
     }
 
     #[test]
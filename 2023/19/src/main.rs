@@ -1,8 +1,10 @@
-use anyhow::{anyhow, Result};
-use regex::Regex;
+use anyhow::{bail, Result};
+use aoc::parse::{parse_lines, parse_sections};
+use aoc::FromRegex;
 use std::{
     collections::BTreeMap,
     ops::{Index, IndexMut, RangeInclusive},
+    str::FromStr,
 };
 
 fn main() -> Result<()> {
@@ -12,7 +14,8 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-#[derive(Debug)]
+#[derive(Debug, FromRegex)]
+#[regex(r"\{x=(\d+),m=(\d+),a=(\d+),s=(\d+)\}")]
 struct Part {
     x: usize,
     m: usize,
@@ -71,95 +74,92 @@ enum Field {
     S,
 }
 
+impl FromStr for Field {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "x" => Ok(Field::X),
+            "m" => Ok(Field::M),
+            "a" => Ok(Field::A),
+            "s" => Ok(Field::S),
+            _ => bail!("unknown field '{s}'"),
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Op {
     LessThan,
     GreaterThan,
 }
 
-#[derive(Debug)]
+impl FromStr for Op {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "<" => Ok(Op::LessThan),
+            ">" => Ok(Op::GreaterThan),
+            _ => bail!("unknown op '{s}'"),
+        }
+    }
+}
+
+#[derive(Debug, FromRegex)]
 enum Rule {
+    #[regex(r"^(?<field>[xmas])(?<op><|>)(?<value>\d+):(?<label>[a-zA-Z]+)$")]
     Rule {
         label: String,
         field: Field,
         op: Op,
         value: usize,
     },
-    Default {
-        label: String,
-    },
+    #[regex(r"^(?<label>[a-zA-Z]+)$")]
+    Default { label: String },
 }
 
-#[derive(Debug)]
+// Conditional rules, and a default rule last.
+fn parse_rules(s: &str) -> Result<Vec<Rule>> {
+    let rules = s
+        .split(',')
+        .map(str::parse)
+        .collect::<Result<Vec<Rule>>>()?;
+    let Some((last, conditionals)) = rules.split_last() else {
+        bail!("no rules");
+    };
+    if !matches!(last, Rule::Default { .. }) {
+        bail!("last rule is not a default rule");
+    }
+    if conditionals
+        .iter()
+        .any(|rule| matches!(rule, Rule::Default { .. }))
+    {
+        bail!("default rule before the last rule");
+    }
+    Ok(rules)
+}
+
+#[derive(Debug, FromRegex)]
+#[regex(r"(?<label>[a-z]+)\{(?<rules>.*)\}")]
 struct Workflow {
+    label: String,
+    #[parse_with(parse_rules)]
     rules: Vec<Rule>,
 }
 
 fn parse(input: &str) -> Result<(BTreeMap<String, Workflow>, Vec<Part>)> {
-    let re_workflow = Regex::new(r"([a-z]+)\{(.*)\}").unwrap();
-    let re_rule = Regex::new(r"([xmas])(<|>)(\d+):(.*)").unwrap();
-    let re_part = Regex::new(r"\{x=(\d+),m=(\d+),a=(\d+),s=(\d+)\}").unwrap();
-
-    let (input_rules, input_parts) = input
-        .split_once("\n\n")
-        .ok_or_else(|| anyhow!("missing blank line"))?;
-
-    let mut workflows = BTreeMap::new();
-    for line in input_rules.lines() {
-        let (_, [label, rules_input]) = re_workflow
-            .captures(line)
-            .map(|caps| caps.extract())
-            .ok_or_else(|| anyhow!("regex found no matches in \"{line}\""))?;
-        let mut rules_input = rules_input.split(',').collect::<Vec<_>>();
-        let default = rules_input
-            .pop()
-            .ok_or_else(|| anyhow!("empty conditional"))?;
-        let mut rules = vec![];
-        for rule_input in rules_input {
-            let (field, op, value, label) = aoc::parse!(
-                &re_rule,
-                rule_input,
-                |s| match s {
-                    "x" => Ok(Field::X),
-                    "m" => Ok(Field::M),
-                    "a" => Ok(Field::A),
-                    "s" => Ok(Field::S),
-                    _ => Err(anyhow!("unexpected pattern")),
-                },
-                |s| match s {
-                    "<" => Ok(Op::LessThan),
-                    ">" => Ok(Op::GreaterThan),
-                    _ => Err(anyhow!("unexpected pattern")),
-                },
-                |s| s.parse::<usize>(),
-                |s| Ok::<String, &str>(s.to_string())
-            )?;
-            rules.push(Rule::Rule {
-                label,
-                field,
-                op,
-                value,
-            });
-        }
-        rules.push(Rule::Default {
-            label: default.to_string(),
-        });
-        workflows.insert(label.to_string(), Workflow { rules });
-    }
-
-    let mut parts = vec![];
-    for line in input_parts.lines() {
-        let (x, m, a, s) = aoc::parse4(
-            &re_part,
-            line,
-            |s| s.parse::<usize>(),
-            |s| s.parse::<usize>(),
-            |s| s.parse::<usize>(),
-            |s| s.parse::<usize>(),
-        )?;
-        parts.push(Part { x, m, a, s });
-    }
-
+    let (workflows, parts) = parse_sections(
+        input,
+        (
+            |s| parse_lines(s, str::parse::<Workflow>),
+            |s| parse_lines(s, str::parse::<Part>),
+        ),
+    )?;
+    let workflows = workflows
+        .into_iter()
+        .map(|workflow| (workflow.label.clone(), workflow))
+        .collect();
     Ok((workflows, parts))
 }
 
@@ -285,4 +285,13 @@ mod tests {
     fn test_part_two() {
         assert_eq!(part_two(INPUT).unwrap(), 167409079868000);
     }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!(parse_rules("a<2006:qkq,m>2090:A,rfg").unwrap().len(), 3);
+        assert!(parse_rules("x<abc:foo,A").is_err());
+        assert!(parse_rules("A,m>2090:A").is_err());
+        assert!(parse_rules("A,R").is_err());
+        assert!(parse_rules("").is_err());
+    }
 }
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use regex::Regex;
+use aoc::FromRegex;
 
 fn main() -> Result<()> {
     let input = include_str!("input.txt");
@@ -8,11 +8,14 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+#[derive(FromRegex)]
+#[regex(r"(\d+)\s+(\d+)")]
+struct Pair(usize, usize);
+
 fn parse(input: &str) -> Result<(Vec<usize>, Vec<usize>)> {
-    let re = Regex::new(r"(\d+)\s+(\d+)").unwrap();
     let mut out = (vec![], vec![]);
     for line in input.lines() {
-        let (a, b) = aoc::parse!(&re, line, |s| s.parse::<usize>(), |s| s.parse::<usize>())?;
+        let Pair(a, b) = line.parse()?;
         out.0.push(a);
         out.1.push(b);
     }
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use aoc::parse::{list, parse_lines};
 use itertools::Itertools;
 
 fn main() -> Result<()> {
@@ -9,15 +10,7 @@ fn main() -> Result<()> {
 }
 
 fn parse(input: &str) -> Result<Vec<Vec<i32>>> {
-    let mut out = vec![];
-    for line in input.lines() {
-        out.push(
-            line.split_whitespace()
-                .map(|w| w.parse::<i32>())
-                .collect::<Result<_, _>>()?,
-        );
-    }
-    Ok(out)
+    parse_lines(input, |line| list(line, " "))
 }
 
 fn is_safe(numbers: &[i32]) -> bool {
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use regex::Regex;
+use aoc::parse::find_all;
 
 fn main() -> Result<()> {
     let input = include_str!("input.txt");
@@ -8,26 +8,26 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+#[derive(Debug, PartialEq, aoc::FromRegex)]
+enum Instruction {
+    #[regex(r"mul\((\d+),(\d+)\)")]
+    Mul(usize, usize),
+    #[regex(r"do\(\)")]
+    Do,
+    #[regex(r"don't\(\)")]
+    Dont,
+}
+
 fn parse(input: &str, support_toggle_instr: bool) -> Result<Vec<(usize, usize)>> {
-    let re = if support_toggle_instr {
-        Regex::new(r#"do\(\)|don't\(\)|mul\((\d+),(\d+)\)"#).unwrap()
-    } else {
-        Regex::new(r#"mul\((\d+),(\d+)\)"#).unwrap()
-    };
     let mut enabled = true;
     let mut out = vec![];
-    for caps in re.captures_iter(input) {
-        if caps[0].starts_with("mul(") {
-            if enabled {
-                let a = caps[1].parse::<usize>()?;
-                let b = caps[2].parse::<usize>()?;
-                out.push((a, b));
-            }
-        } else if caps[0] == *"don't()" {
-            enabled = false;
-        } else if caps[0] == *"do()" {
-            enabled = true;
-        };
+    for instr in find_all(input)? {
+        match instr {
+            Instruction::Mul(a, b) if enabled => out.push((a, b)),
+            Instruction::Mul(..) => {}
+            Instruction::Do => enabled = true,
+            Instruction::Dont => enabled = !support_toggle_instr,
+        }
     }
     Ok(out)
 }
@@ -1,4 +1,5 @@
 use anyhow::{anyhow, ensure, Result};
+use aoc::parse::parse_sections;
 use std::collections::{BTreeMap, BTreeSet};
 
 fn main() -> Result<()> {
@@ -11,9 +12,7 @@ fn main() -> Result<()> {
 type Rules = BTreeMap<usize, BTreeSet<usize>>;
 
 fn parse(input: &str) -> Result<(Rules, Vec<Vec<usize>>)> {
-    let (first, second) = input
-        .split_once("\n\n")
-        .ok_or_else(|| anyhow!("missing blank line"))?;
+    let (first, second) = parse_sections(input, (Ok, Ok))?;
 
     let mut rules = Rules::new();
     for line in first.lines() {
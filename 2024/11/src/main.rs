@@ -1,6 +1,5 @@
-use std::collections::HashMap;
-
 use anyhow::Result;
+use aoc::Memo;
 
 fn main() -> Result<()> {
     let input = include_str!("input.txt");
@@ -33,37 +32,33 @@ fn maybe_split(n: usize) -> Option<(usize, usize)> {
     Some((n / denominator, n % denominator))
 }
 
-fn process(numbers: &[usize], depth: usize, cache: &mut HashMap<(usize, usize), usize>) -> usize {
+fn process(numbers: &[usize], depth: usize, memo: &mut Memo<(usize, usize), usize>) -> usize {
     if depth == 0 {
         return numbers.len();
     }
     let mut total_count = 0;
     for n in numbers {
-        if let Some(count) = cache.get(&(*n, depth)) {
-            total_count += count;
-        } else {
-            let count = if *n == 0 {
-                process(&[1], depth - 1, cache)
+        total_count += memo.get_or_insert_with((*n, depth), |memo| {
+            if *n == 0 {
+                process(&[1], depth - 1, memo)
             } else if let Some((a, b)) = maybe_split(*n) {
-                process(&[a], depth - 1, cache) + process(&[b], depth - 1, cache)
+                process(&[a], depth - 1, memo) + process(&[b], depth - 1, memo)
             } else {
-                process(&[n * 2024], depth - 1, cache)
-            };
-            cache.insert((*n, depth), count);
-            total_count += count;
-        }
+                process(&[n * 2024], depth - 1, memo)
+            }
+        });
     }
     total_count
 }
 
 fn part_one(input: &str) -> Result<usize> {
     let numbers = parse(input)?;
-    Ok(process(&numbers, 25, &mut HashMap::new()))
+    Ok(process(&numbers, 25, &mut Memo::default()))
 }
 
 fn part_two(input: &str) -> Result<usize> {
     let numbers = parse(input)?;
-    Ok(process(&numbers, 75, &mut HashMap::new()))
+    Ok(process(&numbers, 75, &mut Memo::default()))
 }
 
 #[cfg(test)]
@@ -84,11 +79,11 @@ mod tests {
 
     #[test]
     fn test_process() {
-        assert_eq!(process(&[0], 0, &mut HashMap::new()), [0].len());
-        assert_eq!(process(&[0], 1, &mut HashMap::new()), [1].len());
-        assert_eq!(process(&[125], 1, &mut HashMap::new()), [253000].len());
-        assert_eq!(process(&[17], 1, &mut HashMap::new()), [1, 7].len());
-        assert_eq!(process(&[125], 2, &mut HashMap::new()), [253, 0].len());
+        assert_eq!(process(&[0], 0, &mut Memo::default()), [0].len());
+        assert_eq!(process(&[0], 1, &mut Memo::default()), [1].len());
+        assert_eq!(process(&[125], 1, &mut Memo::default()), [253000].len());
+        assert_eq!(process(&[17], 1, &mut Memo::default()), [1, 7].len());
+        assert_eq!(process(&[125], 2, &mut Memo::default()), [253, 0].len());
     }
 
     #[test]
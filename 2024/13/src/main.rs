@@ -1,6 +1,6 @@
 use anyhow::Result;
-use aoc::XY;
-use regex::Regex;
+use aoc::linalg::Matrix;
+use aoc::{FromRegex, XY};
 
 fn main() -> Result<()> {
     let input = include_str!("input.txt");
@@ -20,23 +20,14 @@ struct Machine {
     target: XY,
 }
 
+#[derive(FromRegex)]
+#[regex(r"Button A: X\+(\d+), Y\+(\d+)\nButton B: X\+(\d+), Y\+(\d+)\nPrize: X=(\d+), Y=(\d+)")]
+struct MachineSpec(i32, i32, i32, i32, i32, i32);
+
 fn parse(input: &str) -> Result<Vec<Machine>> {
-    let re = Regex::new(
-        r"Button A: X\+(\d+), Y\+(\d+)\nButton B: X\+(\d+), Y\+(\d+)\nPrize: X=(\d+), Y=(\d+)",
-    )
-    .unwrap();
     let mut machines = vec![];
     for chunk in input.split("\n\n") {
-        let (ax, ay, bx, by, targetx, targety): (i32, i32, i32, i32, i32, i32) = aoc::parse!(
-            &re,
-            chunk,
-            |s| s.parse(),
-            |s| s.parse(),
-            |s| s.parse(),
-            |s| s.parse(),
-            |s| s.parse(),
-            |s| s.parse()
-        )?;
+        let MachineSpec(ax, ay, bx, by, targetx, targety) = chunk.parse()?;
         machines.push(Machine {
             button_a: Button {
                 delta: (ax, ay).into(),
@@ -65,8 +56,6 @@ fn parse(input: &str) -> Result<Vec<Machine>> {
 //   bx, by is the delta the claw moves every time button B is pushed
 //   tx, ty is the target coordinates
 //
-// See https://en.wikipedia.org/wiki/Cramer%27s_rule
-//
 // Returns (A, B) if there is a solution where A and B are both integers
 fn solve_equation(
     ax: i128,
@@ -76,13 +65,10 @@ fn solve_equation(
     tx: i128,
     ty: i128,
 ) -> Option<(usize, usize)> {
-    let a = (tx * by - bx * ty) / (ax * by - bx * ay);
-    let b = (ax * ty - tx * ay) / (ax * by - bx * ay);
-
-    if a * ax + b * bx == tx && a * ay + b * by == ty {
-        Some((a as usize, b as usize))
-    } else {
-        None
+    let m = Matrix::from_rows([[ax, bx], [ay, by]]);
+    match m.solve(&[tx.into(), ty.into()]).integral()?[..] {
+        [a, b] => Some((a as usize, b as usize)),
+        _ => None,
     }
 }
 
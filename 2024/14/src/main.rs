@@ -1,7 +1,7 @@
 use anyhow::Result;
-use aoc::XY;
-use regex::Regex;
-use std::{collections::HashMap, fs::File, io::BufWriter};
+use aoc::render::{Recorder, Renderer};
+use aoc::{BoundingBox, FromRegex, XY};
+use std::collections::HashMap;
 
 fn main() -> Result<()> {
     let input = include_str!("input.txt");
@@ -15,24 +15,18 @@ struct Robot {
     velocity: XY,
 }
 
+#[derive(FromRegex)]
+#[regex(r"p=(-?\d+),(-?\d+) v=(-?\d+),(-?\d+)")]
+struct RobotLine(i32, i32, i32, i32);
+
 fn parse(input: &str) -> Result<Vec<Robot>> {
-    let re = Regex::new(r"p=(-?\d+),(-?\d+) v=(-?\d+),(-?\d+)").unwrap();
-    let mut robots = vec![];
-    for line in input.lines() {
-        let (px, py, vx, vy): (i32, i32, i32, i32) = aoc::parse!(
-            &re,
-            line,
-            |s| s.parse(),
-            |s| s.parse(),
-            |s| s.parse(),
-            |s| s.parse()
-        )?;
-        robots.push(Robot {
+    aoc::parse::parse_lines(input, |line| {
+        let RobotLine(px, py, vx, vy) = line.parse()?;
+        Ok(Robot {
             position: (px, py).into(),
             velocity: (vx, vy).into(),
-        });
-    }
-    Ok(robots)
+        })
+    })
 }
 
 fn simulate(robots: &[Robot], width: i32, height: i32, steps: usize) -> HashMap<XY, usize> {
@@ -69,21 +63,14 @@ fn part_two(input: &str, width: i32, height: i32, generate_images: bool) -> Resu
     // the first N steps and manually inspecting them.
     if generate_images {
         let robots = parse(input)?;
+        let renderer = Renderer::default().with_bounds(BoundingBox::new(
+            (0, 0).into(),
+            (width - 1, height - 1).into(),
+        ));
+        let mut recorder = Recorder::directory(".")?;
         for iteration in 0..=9999 {
             let grid = simulate(&robots, width, height, iteration);
-            let file = File::create(format!("{:04}.png", iteration))?;
-            let w = BufWriter::new(file);
-            let mut encoder = png::Encoder::new(w, width as u32, height as u32);
-            encoder.set_color(png::ColorType::Grayscale);
-            encoder.set_depth(png::BitDepth::Eight);
-            let mut writer = encoder.write_header()?;
-            let mut data = Vec::with_capacity(width as usize * height as usize);
-            for i in 0..(width * height) {
-                let xy: XY = (i % width, i / width).into();
-                let color = if grid.contains_key(&xy) { 255 } else { 0 };
-                data.push(color);
-            }
-            writer.write_image_data(&data)?;
+            recorder.record(&renderer.points(grid.into_keys()))?;
         }
     }
     Ok(6752)
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use anyhow::{anyhow, bail, Result};
+use aoc::parse::parse_sections;
 use aoc::{Direction, XY};
 
 fn main() -> Result<()> {
@@ -20,15 +21,28 @@ enum Type {
     Wall,
 }
 
+impl Type {
+    fn to_char(self) -> char {
+        match self {
+            Type::Empty => '.',
+            Type::Robot => '@',
+            Type::SmallBox => 'O',
+            Type::BoxWest => '[',
+            Type::BoxEast => ']',
+            Type::Wall => '#',
+        }
+    }
+}
+
 type Grid = HashMap<XY, Type>;
 
 fn parse(input: &str, expand: bool) -> Result<(Grid, Vec<Direction>)> {
-    let (first, second) = input
-        .split_once("\n\n")
-        .ok_or_else(|| anyhow!("missing blank line"))?;
+    parse_sections(input, (|s| parse_warehouse(s, expand), parse_directions))
+}
 
+fn parse_warehouse(input: &str, expand: bool) -> Result<Grid> {
     let mut grid = Grid::new();
-    for (y, line) in first.lines().enumerate() {
+    for (y, line) in input.lines().enumerate() {
         let mut x = 0;
         for ch in line.chars() {
             let type_ = match ch {
@@ -56,9 +70,12 @@ fn parse(input: &str, expand: bool) -> Result<(Grid, Vec<Direction>)> {
             }
         }
     }
+    Ok(grid)
+}
 
+fn parse_directions(input: &str) -> Result<Vec<Direction>> {
     let mut directions = vec![];
-    for ch in second.chars().filter(|ch| !ch.is_whitespace()) {
+    for ch in input.chars().filter(|ch| !ch.is_whitespace()) {
         let dir = match ch {
             '^' => Direction::North,
             '>' => Direction::East,
@@ -68,8 +85,7 @@ fn parse(input: &str, expand: bool) -> Result<(Grid, Vec<Direction>)> {
         };
         directions.push(dir);
     }
-
-    Ok((grid, directions))
+    Ok(directions)
 }
 
 fn try_move(grid: &Grid, from: XY, dir: Direction, shifts: &mut Vec<(XY, XY)>) -> bool {
@@ -168,6 +184,7 @@ fn part_x(input: &str, expand: bool) -> Result<usize> {
             }
             xy = xy.forward(dir);
         }
+        aoc::visualise::frame_cells(|| grid.iter().map(|(xy, ty)| (*xy, ty.to_char())));
     }
 
     Ok(score(&grid))
@@ -1,7 +1,8 @@
-use anyhow::{anyhow, bail, ensure, Result};
+use anyhow::{anyhow, bail, Result};
+use aoc::parse::{grid_markers, parse_grid_as};
 use aoc::{Direction, Graph, XY};
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     sync::{Arc, Mutex},
     thread,
 };
@@ -15,37 +16,32 @@ fn main() -> Result<()> {
 
 type NodeId = (XY, Direction);
 
-fn parse(input: &str) -> Result<(Graph<NodeId>, NodeId, NodeId, HashSet<XY>)> {
-    // get set of XY
-    let mut spaces = HashSet::new();
-    let mut start: Option<XY> = None;
-    let mut end: Option<XY> = None;
-    let _ = aoc::parse_grid(input, |xy, ch| match ch {
-        '#' => Ok(()),
-        '.' => {
-            spaces.insert(xy);
-            Ok(())
-        }
-        'S' => {
-            ensure!(start.is_none(), "duplicate start positions");
-            start = Some(xy);
-            spaces.insert(xy);
-            Ok(())
-        }
-        'E' => {
-            ensure!(end.is_none(), "duplicate end positions");
-            end = Some(xy);
-            spaces.insert(xy);
-            Ok(())
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Wall,
+    Space,
+}
+
+impl TryFrom<char> for Tile {
+    type Error = anyhow::Error;
+
+    fn try_from(ch: char) -> Result<Self> {
+        match ch {
+            '#' => Ok(Tile::Wall),
+            '.' | 'S' | 'E' => Ok(Tile::Space),
+            _ => bail!("unexpected char"),
         }
-        _ => bail!("unexpected char '{ch}'"),
-    })?;
-    let Some(start) = start else {
-        bail!("missing start position");
-    };
-    let Some(end) = end else {
-        bail!("missing end position");
-    };
+    }
+}
+
+fn parse(input: &str) -> Result<(Graph<NodeId>, NodeId, NodeId, HashSet<XY>)> {
+    let (tiles, _): (HashMap<XY, Tile>, _) = parse_grid_as(input)?;
+    let spaces: HashSet<XY> = tiles
+        .into_iter()
+        .filter(|(_, tile)| *tile == Tile::Space)
+        .map(|(xy, _)| xy)
+        .collect();
+    let [start, end] = grid_markers(input, ['S', 'E'])?;
 
     // create graph nodes
     let mut graph = Graph::default();
@@ -0,0 +1,139 @@
+use anyhow::{bail, ensure, Context, Result};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A puzzle, identified by year and day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Day {
+    pub year: u16,
+    pub day: u8,
+}
+
+impl Day {
+    pub fn new(year: u16, day: u8) -> Result<Self> {
+        ensure!((2015..=2099).contains(&year), "{}: bad year", year);
+        ensure!((1..=25).contains(&day), "{}: bad day", day);
+        Ok(Day { year, day })
+    }
+
+    /// Parse `<year> <day>` from the command line; the day may be zero-padded.
+    pub fn from_args(year: &str, day: &str) -> Result<Self> {
+        let year = year
+            .parse()
+            .with_context(|| format!("{}: bad year", year))?;
+        let day = day.parse().with_context(|| format!("{}: bad day", day))?;
+        Day::new(year, day)
+    }
+
+    /// The puzzle released most recently, by the clock in the puzzle time zone (UTC-5).
+    pub fn today() -> Result<Self> {
+        let (year, month, day) = civil_from_days((now() - 5 * 3600).div_euclid(86400));
+        if month != 12 || day > 25 {
+            bail!("no puzzle today ({year}-{month:02}-{day:02}), pass <year> <day>");
+        }
+        Day::new(year as u16, day as u8)
+    }
+
+    /// When the puzzle unlocks, in seconds since the Unix epoch: midnight UTC-5.
+    pub fn unlocks_at(&self) -> i64 {
+        (days_from_civil(self.year.into(), 12, self.day.into()) * 86400) + 5 * 3600
+    }
+
+    /// Whether the puzzle has unlocked.
+    pub fn is_unlocked(&self) -> bool {
+        now() >= self.unlocks_at()
+    }
+
+    /// The package directory, relative to the workspace root: `2024/07`.
+    pub fn dir(&self, root: &Path) -> PathBuf {
+        root.join(self.year.to_string())
+            .join(format!("{:02}", self.day))
+    }
+
+    /// The package name: `aoc-2024-07`.
+    pub fn package(&self) -> String {
+        format!("aoc-{}-{:02}", self.year, self.day)
+    }
+}
+
+impl Display for Day {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{:02}", self.year, self.day)
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+// (year, month, day) to days since 1970-01-01, and back, from Howard Hinnant's date algorithms.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_args() {
+        assert_eq!(
+            Day::from_args("2024", "07").unwrap(),
+            Day::new(2024, 7).unwrap()
+        );
+        assert_eq!(Day::from_args("2024", "7").unwrap().to_string(), "2024/07");
+        assert!(Day::from_args("2014", "1").is_err());
+        assert!(Day::from_args("2024", "26").is_err());
+        assert!(Day::from_args("2024", "x").is_err());
+    }
+
+    #[test]
+    fn test_paths() {
+        let day = Day::new(2024, 7).unwrap();
+        assert_eq!(day.dir(Path::new("/w")), Path::new("/w/2024/07"));
+        assert_eq!(day.package(), "aoc-2024-07");
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19723), (2024, 1, 1));
+        assert_eq!(civil_from_days(20063), (2024, 12, 6));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        for days in [-1, 0, 19723, 20063] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
+
+    #[test]
+    fn test_unlocks_at() {
+        // 2024-12-06T05:00:00Z
+        assert_eq!(Day::new(2024, 6).unwrap().unlocks_at(), 1733461200);
+        assert!(Day::new(2015, 1).unwrap().is_unlocked());
+        assert!(!Day::new(2099, 1).unwrap().is_unlocked());
+    }
+}
@@ -0,0 +1,451 @@
+use anyhow::{bail, ensure, Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::day::{now, Day};
+
+const DEFAULT_URL: &str = "https://adventofcode.com";
+const USER_AGENT: &str = "github.com/amhk/advent-of-code by marten.kongstad@gmail.com";
+
+/// An HTTP response: the status code and the body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Where puzzle inputs and pages come from, and where answers go. `path` is relative to the
+/// site root, e.g. `/2024/day/7/input`.
+pub trait Fetcher {
+    fn get(&self, path: &str) -> Result<Response>;
+
+    /// Send `form` as an URL encoded form.
+    fn post(&self, path: &str, form: &[(&str, &str)]) -> Result<Response>;
+}
+
+impl<F: Fetcher + ?Sized> Fetcher for Box<F> {
+    fn get(&self, path: &str) -> Result<Response> {
+        (**self).get(path)
+    }
+
+    fn post(&self, path: &str, form: &[(&str, &str)]) -> Result<Response> {
+        (**self).post(path, form)
+    }
+}
+
+/// The fetcher selected by `AOC_URL`: a [`DirFetcher`] for a `file://` URL, otherwise an
+/// [`HttpFetcher`].
+pub fn from_env() -> Box<dyn Fetcher> {
+    match std::env::var("AOC_URL") {
+        Ok(url) if url.starts_with("file://") => {
+            Box::new(DirFetcher::new(url.trim_start_matches("file://")))
+        }
+        _ => Box::new(HttpFetcher::from_env()),
+    }
+}
+
+/// Whether [`from_env`] can be expected to work: the site needs a session cookie.
+pub fn is_configured() -> bool {
+    let is_dir = std::env::var("AOC_URL").is_ok_and(|url| url.starts_with("file://"));
+    is_dir || HttpFetcher::from_env().has_session()
+}
+
+/// The real site, or whatever `AOC_URL` points at, reached with `curl`.
+pub struct HttpFetcher {
+    base_url: String,
+    session: Option<String>,
+}
+
+impl HttpFetcher {
+    /// Use `AOC_URL` (default: the Advent of Code site) and the session cookie in `AOC_SESSION`.
+    pub fn from_env() -> Self {
+        HttpFetcher {
+            base_url: std::env::var("AOC_URL").unwrap_or_else(|_| DEFAULT_URL.to_string()),
+            session: std::env::var("AOC_SESSION").ok().filter(|s| !s.is_empty()),
+        }
+    }
+
+    fn has_session(&self) -> bool {
+        self.session.is_some()
+    }
+
+    fn curl(&self, path: &str, form: &[(&str, &str)]) -> Result<Response> {
+        let url = format!("{}{}", self.base_url.trim_end_matches('/'), path);
+        let mut command = Command::new("curl");
+        command
+            .args(["--silent", "--show-error", "--user-agent", USER_AGENT])
+            .args(["--write-out", "\n%{http_code}"]);
+        // the session cookie goes in a config file on stdin: command lines are visible to all
+        if let Some(session) = &self.session {
+            ensure!(
+                session.chars().all(|ch| ch.is_ascii_alphanumeric()),
+                "AOC_SESSION: unexpected characters in session cookie"
+            );
+            command.args(["--config", "-"]);
+        }
+        for (key, value) in form {
+            command.args(["--data-urlencode", &format!("{}={}", key, value)]);
+        }
+        let mut child = command
+            .arg(&url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("failed to run curl")?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        if let Some(session) = &self.session {
+            writeln!(stdin, "cookie = \"session={}\"", session)
+                .context("failed to pass the session cookie to curl")?;
+        }
+        drop(stdin);
+        let output = child.wait_with_output().context("failed to run curl")?;
+        ensure!(
+            output.status.success(),
+            "{}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        let stdout = String::from_utf8(output.stdout).context("response is not UTF-8")?;
+        let (body, status) = stdout.rsplit_once('\n').context("no status from curl")?;
+        Ok(Response {
+            status: status.trim().parse().context("bad status from curl")?,
+            body: body.to_string(),
+        })
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn get(&self, path: &str) -> Result<Response> {
+        self.curl(path, &[])
+    }
+
+    fn post(&self, path: &str, form: &[(&str, &str)]) -> Result<Response> {
+        ensure!(!form.is_empty(), "{}: empty form", path);
+        self.curl(path, form)
+    }
+}
+
+/// A stand-in for the site that serves files from a directory, like a static web server: a
+/// path names a file, or a directory with an `index.html`. Anything else is a 404. A POST is
+/// answered like a GET of the same path; the form is ignored.
+pub struct DirFetcher {
+    root: PathBuf,
+}
+
+impl DirFetcher {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        DirFetcher { root: root.into() }
+    }
+}
+
+impl Fetcher for DirFetcher {
+    fn get(&self, path: &str) -> Result<Response> {
+        let mut file = self.root.join(path.trim_start_matches('/'));
+        if file.is_dir() {
+            file = file.join("index.html");
+        }
+        Ok(match fs::read_to_string(&file) {
+            Ok(body) => Response { status: 200, body },
+            Err(_) => Response {
+                status: 404,
+                body: "404 Not Found".to_string(),
+            },
+        })
+    }
+
+    fn post(&self, path: &str, _form: &[(&str, &str)]) -> Result<Response> {
+        self.get(path)
+    }
+}
+
+// Turn a bad response into an error that says what probably went wrong.
+fn check(path: &str, response: Response) -> Result<String> {
+    let Response { status, body } = response;
+    if body.contains("Please log in") || body.contains("please identify yourself") {
+        bail!(
+            "{}: not logged in; set AOC_SESSION to your session cookie",
+            path
+        );
+    }
+    match status {
+        200 => Ok(body),
+        404 => bail!("{}: not found; is the puzzle unlocked?", path),
+        429 => bail!("{}: too many requests; try again later", path),
+        _ => bail!("{}: HTTP {}: {}", path, status, body.trim()),
+    }
+}
+
+/// A file-backed cache in front of a [`Fetcher`]. Requests that reach the fetcher are spaced at
+/// least `interval` apart, also across runs.
+pub struct Cache<F> {
+    dir: PathBuf,
+    fetcher: F,
+    interval: Duration,
+}
+
+impl<F: Fetcher> Cache<F> {
+    pub fn new(dir: impl Into<PathBuf>, fetcher: F, interval: Duration) -> Self {
+        Cache {
+            dir: dir.into(),
+            fetcher,
+            interval,
+        }
+    }
+
+    fn day_dir(&self, day: &Day) -> PathBuf {
+        day.dir(&self.dir)
+    }
+
+    /// The puzzle input.
+    pub fn input(&self, day: &Day) -> Result<String> {
+        let path = format!("/{}/day/{}/input", day.year, day.day);
+        self.cached(day, "input.txt", &path, false, |input| {
+            ensure!(!input.trim().is_empty(), "{}: empty puzzle input", path);
+            ensure!(
+                !is_html(input),
+                "{}: got a web page, not a puzzle input",
+                path
+            );
+            Ok(())
+        })
+    }
+
+    /// The puzzle page. `refresh` fetches it again, e.g. to see part two after solving part one.
+    pub fn puzzle(&self, day: &Day, refresh: bool) -> Result<String> {
+        let path = format!("/{}/day/{}", day.year, day.day);
+        self.cached(day, "puzzle.html", &path, refresh, |page| {
+            ensure!(page.contains("<article"), "{}: not a puzzle page", path);
+            Ok(())
+        })
+    }
+
+    // Only responses that pass `validate` are cached, and a cached file that does not pass is
+    // fetched again.
+    fn cached<V>(
+        &self,
+        day: &Day,
+        name: &str,
+        path: &str,
+        refresh: bool,
+        validate: V,
+    ) -> Result<String>
+    where
+        V: Fn(&str) -> Result<()>,
+    {
+        let file = self.day_dir(day).join(name);
+        if !refresh {
+            if let Ok(contents) = fs::read_to_string(&file) {
+                if validate(&contents).is_ok() {
+                    return Ok(contents);
+                }
+            }
+        }
+        ensure!(
+            day.is_unlocked(),
+            "{}: puzzle {} has not unlocked yet",
+            path,
+            day
+        );
+        self.throttle()?;
+        let body = check(path, self.fetcher.get(path)?)?;
+        validate(&body)?;
+        fs::create_dir_all(self.day_dir(day))?;
+        fs::write(&file, &body).with_context(|| format!("{}: failed to write", file.display()))?;
+        Ok(body)
+    }
+
+    /// Send `form` to `path`, bypassing the cache.
+    pub fn post(&self, path: &str, form: &[(&str, &str)]) -> Result<String> {
+        self.throttle()?;
+        check(path, self.fetcher.post(path, form)?)
+    }
+
+    /// Wait until `interval` has passed since the last request, and record this one.
+    pub fn throttle(&self) -> Result<()> {
+        let stamp = self.dir.join("last-request");
+        let last: Option<i64> = fs::read_to_string(&stamp)
+            .ok()
+            .and_then(|s| s.trim().parse().ok());
+        if let Some(last) = last {
+            let elapsed = Duration::from_secs(now().saturating_sub(last).max(0) as u64);
+            if elapsed < self.interval {
+                std::thread::sleep(self.interval - elapsed);
+            }
+        }
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&stamp, now().to_string())?;
+        Ok(())
+    }
+}
+
+// Whether `s` is a web page. Puzzle inputs may start with `<` too, so look for more than that.
+fn is_html(s: &str) -> bool {
+    let start = s.trim_start().to_ascii_lowercase();
+    start.starts_with("<!doctype") || start.starts_with("<html")
+}
+
+/// The contents of every `<pre><code>` block in a puzzle page, with markup removed: the
+/// examples.
+pub fn examples(html: &str) -> Vec<String> {
+    let mut out = vec![];
+    let mut rest = html;
+    while let Some(start) = rest.find("<pre><code>") {
+        rest = &rest[start + "<pre><code>".len()..];
+        let Some(end) = rest.find("</code></pre>") else {
+            break;
+        };
+        out.push(unescape(&strip_tags(&rest[..end])));
+        rest = &rest[end..];
+    }
+    out
+}
+
+/// `s` without HTML tags.
+pub fn strip_tags(s: &str) -> String {
+    let mut out = String::new();
+    let mut in_tag = false;
+    for ch in s.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(ch),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Write `contents` to `path` unless the file already has something in it. Returns whether the
+/// file was written.
+pub fn write_if_empty(path: &Path, contents: &str) -> Result<bool> {
+    let current = fs::read_to_string(path).unwrap_or_default();
+    if !current.trim().is_empty() {
+        return Ok(false);
+    }
+    fs::write(path, contents).with_context(|| format!("{}: failed to write", path.display()))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempDir;
+
+    const PAGE: &str = "<html><body><main><article><h2>--- Day 1 ---</h2>\
+        <p>For example:</p><pre><code>1   <em>3</em>\n2 &lt; 4 &amp;&amp; x\n</code></pre>\
+        <p>Then <code>x</code>:</p><pre><code>a-&gt;b\n</code></pre></article></main></body></html>";
+
+    fn site(tmp: &TempDir) -> DirFetcher {
+        let root = tmp.0.join("site");
+        fs::create_dir_all(root.join("2015/day/1")).unwrap();
+        fs::write(root.join("2015/day/1/input"), "(()(\n").unwrap();
+        fs::write(root.join("2015/day/1/index.html"), PAGE).unwrap();
+        fs::create_dir_all(root.join("2015/day/2")).unwrap();
+        fs::write(
+            root.join("2015/day/2/input"),
+            "Puzzle inputs differ by user.  Please log in to get your puzzle input.\n",
+        )
+        .unwrap();
+        DirFetcher::new(root)
+    }
+
+    #[test]
+    fn test_examples() {
+        assert_eq!(
+            examples(PAGE),
+            vec!["1   3\n2 < 4 && x\n".to_string(), "a->b\n".to_string()]
+        );
+        assert!(examples("<p>no examples</p>").is_empty());
+    }
+
+    #[test]
+    fn test_dir_fetcher() {
+        let tmp = TempDir::new("dir-fetcher");
+        let site = site(&tmp);
+        assert_eq!(site.get("/2015/day/1/input").unwrap().body, "(()(\n");
+        assert_eq!(site.get("/2015/day/1").unwrap().body, PAGE);
+        assert_eq!(site.get("/2015/day/3/input").unwrap().status, 404);
+    }
+
+    #[test]
+    fn test_cache() {
+        let tmp = TempDir::new("cache");
+        let cache = Cache::new(tmp.0.join("cache"), site(&tmp), Duration::ZERO);
+        let day = Day::new(2015, 1).unwrap();
+        assert_eq!(cache.input(&day).unwrap(), "(()(\n");
+        assert!(cache.puzzle(&day, false).unwrap().contains("Day 1"));
+
+        // served from the cache once fetched
+        fs::remove_dir_all(tmp.0.join("site")).unwrap();
+        assert_eq!(cache.input(&day).unwrap(), "(()(\n");
+        assert!(cache.puzzle(&day, false).is_ok());
+        assert!(cache.puzzle(&day, true).is_err());
+        assert!(tmp.0.join("cache/last-request").exists());
+    }
+
+    #[test]
+    fn test_cache_errors() {
+        let tmp = TempDir::new("cache-errors");
+        let cache = Cache::new(tmp.0.join("cache"), site(&tmp), Duration::ZERO);
+
+        let err = cache.input(&Day::new(2015, 2).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("not logged in"));
+        let err = cache.input(&Day::new(2015, 3).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("not found"));
+        let err = cache.input(&Day::new(2099, 1).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("has not unlocked yet"));
+
+        // failures are not cached
+        assert!(!tmp.0.join("cache/2015/02/input.txt").exists());
+
+        // neither are pages that are not what was asked for
+        let site = tmp.0.join("site/2015/day/4");
+        fs::create_dir_all(&site).unwrap();
+        fs::write(
+            site.join("input"),
+            "<!DOCTYPE html>\n<html><body>Down for maintenance</body></html>\n",
+        )
+        .unwrap();
+        let day = Day::new(2015, 4).unwrap();
+        let err = cache.input(&day).unwrap_err();
+        assert!(err.to_string().contains("got a web page"));
+        assert!(!tmp.0.join("cache/2015/04/input.txt").exists());
+        fs::write(site.join("input"), "").unwrap();
+        let err = cache.input(&day).unwrap_err();
+        assert!(err.to_string().contains("empty puzzle input"));
+        assert!(!tmp.0.join("cache/2015/04/input.txt").exists());
+
+        // and a bad file in the cache is fetched again
+        fs::write(site.join("input"), "<{([\n").unwrap();
+        fs::create_dir_all(tmp.0.join("cache/2015/04")).unwrap();
+        fs::write(tmp.0.join("cache/2015/04/input.txt"), "<html>").unwrap();
+        assert_eq!(cache.input(&day).unwrap(), "<{([\n");
+        assert_eq!(
+            fs::read_to_string(tmp.0.join("cache/2015/04/input.txt")).unwrap(),
+            "<{([\n"
+        );
+    }
+
+    #[test]
+    fn test_write_if_empty() {
+        let tmp = TempDir::new("write-if-empty");
+        let path = tmp.0.join("test-input.txt");
+        assert!(write_if_empty(&path, "a\n").unwrap());
+        assert!(!write_if_empty(&path, "b\n").unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a\n");
+        fs::write(&path, "\n").unwrap();
+        assert!(write_if_empty(&path, "c\n").unwrap());
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::{bail, ensure, Context, Result};
+use aoc::journal::Verdict;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+mod day;
+mod fetch;
+mod new_day;
+mod submit;
+
+use day::Day;
+use fetch::{Cache, Fetcher};
+use new_day::Template;
+
+const USAGE: &str = "\
+usage: aoc-cli <command> [options]
+
+commands:
+    new-day [--template plain|grid|graph] [<year> <day>]
+        create the package for a puzzle, and fetch its input if AOC_SESSION is set; defaults
+        to today's puzzle and the plain template
+
+    fetch [--example <n>] [--refresh] [<year> <day>]
+        download the input and the puzzle page, and use the nth example (default: the first)
+        as test input; existing input files are not overwritten
+
+    submit <part> <answer> [<year> <day>]
+        submit an answer and record the verdict in the puzzle's journal.txt; answers the
+        journal rules out are not sent, and an accepted answer becomes the expected value
+        in main.rs
+
+environment:
+    AOC_SESSION    session cookie from a logged in browser
+    AOC_URL        site to talk to, default https://adventofcode.com; a file:// URL serves
+                   files from a directory instead";
+
+// Requests that reach the site are spaced at least this far apart.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(5);
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("aoc-cli is inside the workspace")
+        .to_path_buf()
+}
+
+// Command line arguments: positional arguments, the values of `--name value` options, and
+// `--name` flags.
+struct Args<'a> {
+    positional: Vec<&'a str>,
+    options: Vec<(&'a str, &'a str)>,
+    flags: Vec<&'a str>,
+}
+
+impl<'a> Args<'a> {
+    fn parse(args: &'a [String], option_names: &[&str], flag_names: &[&str]) -> Result<Self> {
+        let mut positional = vec![];
+        let mut options = vec![];
+        let mut flags = vec![];
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if flag_names.contains(&name) => flags.push(name),
+                Some(name) if option_names.contains(&name) => {
+                    let value = iter
+                        .next()
+                        .with_context(|| format!("--{}: missing value", name))?;
+                    options.push((name, value.as_str()));
+                }
+                Some(name) => bail!("--{}: unknown option\n\n{}", name, USAGE),
+                None => positional.push(arg.as_str()),
+            }
+        }
+        Ok(Args {
+            positional,
+            options,
+            flags,
+        })
+    }
+
+    // The value of the last `--name` option.
+    fn option(&self, name: &str) -> Option<&'a str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| *value)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.contains(&name)
+    }
+}
+
+fn day_from_args(positional: &[&str]) -> Result<Day> {
+    match positional {
+        [] => Day::today(),
+        [year, day] => Day::from_args(year, day),
+        _ => bail!("expected <year> <day>\n\n{}", USAGE),
+    }
+}
+
+fn cache() -> Cache<Box<dyn Fetcher>> {
+    Cache::new(
+        workspace_root().join(".cache"),
+        fetch::from_env(),
+        REQUEST_INTERVAL,
+    )
+}
+
+fn new_day(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["template"], &[])?;
+    let day = day_from_args(&args.positional)?;
+    let template = match args.option("template") {
+        Some(name) => name.parse()?,
+        None => Template::Plain,
+    };
+    new_day::scaffold(&workspace_root(), &day, template)?;
+    println!("created {}", day);
+    if !fetch::is_configured() {
+        println!("AOC_SESSION not set, not fetching the input; run `aoc-cli fetch` later");
+        return Ok(());
+    }
+    fetch_day(&cache(), &day, 1, false)
+}
+
+fn fetch(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["example"], &["refresh"])?;
+    let day = day_from_args(&args.positional)?;
+    let example = match args.option("example") {
+        Some(n) => n.parse().context("--example: expected a number")?,
+        None => 1,
+    };
+    fetch_day(&cache(), &day, example, args.flag("refresh"))
+}
+
+fn submit(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &[], &[])?;
+    let (part, answer, day) = match args.positional[..] {
+        [part, answer, ref rest @ ..] => (part, answer, day_from_args(rest)?),
+        _ => bail!("expected <part> <answer>\n\n{}", USAGE),
+    };
+    let part = part
+        .parse()
+        .with_context(|| format!("{}: bad part", part))?;
+    let root = workspace_root();
+    let verdict = submit::submit(&cache(), &root, &day, part, answer)?;
+    println!("{} part {}: {}: {}", day, part, answer, verdict);
+    if verdict != Verdict::Correct {
+        return Ok(());
+    }
+    let main_rs = day.dir(&root).join("src/main.rs");
+    if submit::promote(&main_rs, part, answer)? {
+        // best effort: the edit is correct even if not pretty
+        let _ = Command::new("rustfmt")
+            .args(["--edition", "2021"])
+            .arg(&main_rs)
+            .status();
+        println!("{}/src/main.rs: part {} now expects {}", day, part, answer);
+    }
+    if part == 1 {
+        println!("run `aoc-cli fetch --refresh` to get the examples for part two");
+    }
+    Ok(())
+}
+
+fn fetch_day(cache: &Cache<impl Fetcher>, day: &Day, example: usize, refresh: bool) -> Result<()> {
+    let src = day.dir(&workspace_root()).join("src");
+    ensure!(
+        src.is_dir(),
+        "{}: no such puzzle, run `aoc-cli new-day` first",
+        day
+    );
+
+    let input = cache.input(day)?;
+    report(
+        fetch::write_if_empty(&src.join("input.txt"), &input)?,
+        day,
+        "input.txt",
+    );
+
+    let examples = fetch::examples(&cache.puzzle(day, refresh)?);
+    for (i, text) in examples.iter().enumerate() {
+        let first_line = text.lines().next().unwrap_or_default();
+        println!("example {}: {}", i + 1, first_line);
+    }
+    let text = example
+        .checked_sub(1)
+        .and_then(|i| examples.get(i))
+        .with_context(|| format!("no example {}, found {}", example, examples.len()))?;
+    report(
+        fetch::write_if_empty(&src.join("test-input.txt"), text)?,
+        day,
+        "test-input.txt",
+    );
+    Ok(())
+}
+
+fn report(written: bool, day: &Day, name: &str) {
+    if written {
+        println!("wrote {}/src/{}", day, name);
+    } else {
+        println!("kept existing {}/src/{}", day, name);
+    }
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("new-day") => new_day(&args[1..]),
+        Some("fetch") => fetch(&args[1..]),
+        Some("submit") => submit(&args[1..]),
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => bail!("{}", USAGE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// A directory under the system temp dir, removed when dropped.
+    pub struct TempDir(pub PathBuf);
+
+    impl TempDir {
+        pub fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("aoc-cli-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+}
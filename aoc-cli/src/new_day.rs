@@ -0,0 +1,234 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::day::Day;
+
+/// The starting point for `src/main.rs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    Plain,
+    Grid,
+    Graph,
+}
+
+impl Template {
+    fn main_rs(&self) -> &'static str {
+        match self {
+            Template::Plain => include_str!("../templates/plain.rs"),
+            Template::Grid => include_str!("../templates/grid.rs"),
+            Template::Graph => include_str!("../templates/graph.rs"),
+        }
+    }
+}
+
+impl FromStr for Template {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "plain" => Ok(Template::Plain),
+            "grid" => Ok(Template::Grid),
+            "graph" => Ok(Template::Graph),
+            _ => bail!("{}: unknown template, expected plain, grid or graph", s),
+        }
+    }
+}
+
+fn cargo_toml(day: &Day) -> String {
+    format!(
+        r#"[package]
+name = "{}"
+version.workspace = true
+authors.workspace = true
+edition.workspace = true
+
+[dependencies]
+anyhow.workspace = true
+aoc.workspace = true
+"#,
+        day.package()
+    )
+}
+
+/// Create the package for `day` in the workspace at `root`, and add it to the workspace members
+/// unless a member pattern already covers it. Input files that already exist are kept, but an
+/// existing `Cargo.toml` or `main.rs` is an error: that is someone's work.
+pub fn scaffold(root: &Path, day: &Day, template: Template) -> Result<PathBuf> {
+    let dir = day.dir(root);
+    let src = dir.join("src");
+    for path in [dir.join("Cargo.toml"), src.join("main.rs")] {
+        if path.exists() {
+            bail!("{}: already exists, refusing to overwrite", path.display());
+        }
+    }
+
+    fs::create_dir_all(&src).with_context(|| format!("{}: failed to create", src.display()))?;
+    write(&dir.join("Cargo.toml"), &cargo_toml(day))?;
+    write(&src.join("main.rs"), template.main_rs())?;
+    for name in ["input.txt", "test-input.txt"] {
+        if !src.join(name).exists() {
+            write(&src.join(name), "")?;
+        }
+    }
+    register(root, day)?;
+    Ok(dir)
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    fs::write(path, contents).with_context(|| format!("{}: failed to write", path.display()))
+}
+
+// Add the day to the workspace members in the root Cargo.toml, unless already covered.
+fn register(root: &Path, day: &Day) -> Result<()> {
+    let path = root.join("Cargo.toml");
+    let manifest =
+        fs::read_to_string(&path).with_context(|| format!("{}: failed to read", path.display()))?;
+    let start = manifest
+        .find("members = [")
+        .context("no workspace members in Cargo.toml")?;
+    let end = start
+        + manifest[start..]
+            .find("\n]")
+            .context("unterminated workspace members in Cargo.toml")?;
+    let member = day.to_string();
+    let covered = manifest[start..end]
+        .split('"')
+        .skip(1)
+        .step_by(2)
+        .any(|pattern| glob_match(pattern, &member));
+    if covered {
+        return Ok(());
+    }
+    let updated = format!(
+        "{}\n    \"{}\",{}",
+        &manifest[..end],
+        member,
+        &manifest[end..]
+    );
+    write(&path, &updated)
+}
+
+// Match the subset of glob syntax used in workspace members: `*`, `?` and `[...]` classes with
+// ranges.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (Some(p), rest) = split_first(pattern) else {
+        return text.is_empty();
+    };
+    match p {
+        '*' => (0..=text.len())
+            .filter(|&i| text.is_char_boundary(i) && !text[..i].contains('/'))
+            .any(|i| glob_match(rest, &text[i..])),
+        '?' => match split_first(text) {
+            (Some(ch), text) => ch != '/' && glob_match(rest, text),
+            (None, _) => false,
+        },
+        '[' => {
+            let Some(close) = rest.find(']') else {
+                return false;
+            };
+            let class: Vec<char> = rest[..close].chars().collect();
+            let Some(ch) = text.chars().next() else {
+                return false;
+            };
+            let mut matched = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    matched |= (class[i]..=class[i + 2]).contains(&ch);
+                    i += 3;
+                } else {
+                    matched |= class[i] == ch;
+                    i += 1;
+                }
+            }
+            matched && glob_match(&rest[close + 1..], &text[ch.len_utf8()..])
+        }
+        _ => text.starts_with(p) && glob_match(rest, &text[p.len_utf8()..]),
+    }
+}
+
+fn split_first(s: &str) -> (Option<char>, &str) {
+    let mut chars = s.chars();
+    (chars.next(), chars.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempDir;
+
+    const MANIFEST: &str =
+        "[workspace]\nmembers = [\n    \"20[0-9][0-9]/[0-9][0-9]\",\n    \"aoc\",\n]\n";
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("20[0-9][0-9]/[0-9][0-9]", "2024/07"));
+        assert!(!glob_match("20[0-9][0-9]/[0-9][0-9]", "2024/7"));
+        assert!(!glob_match("20[0-9][0-9]/[0-9][0-9]", "aoc"));
+        assert!(glob_match("2024/*", "2024/07"));
+        assert!(!glob_match("*", "2024/07"));
+        assert!(glob_match("20??/0?", "2024/07"));
+        assert!(glob_match("aoc", "aoc"));
+    }
+
+    #[test]
+    fn test_scaffold() {
+        let tmp = TempDir::new("scaffold");
+        fs::write(tmp.0.join("Cargo.toml"), MANIFEST).unwrap();
+        let day = Day::new(2024, 7).unwrap();
+
+        let dir = scaffold(&tmp.0, &day, Template::Grid).unwrap();
+        assert_eq!(dir, tmp.0.join("2024/07"));
+        let cargo = fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+        assert!(cargo.contains("name = \"aoc-2024-07\""));
+        assert!(cargo.contains("aoc.workspace = true"));
+        let main = fs::read_to_string(dir.join("src/main.rs")).unwrap();
+        assert!(main.contains("parse_grid_as"));
+        assert!(dir.join("src/input.txt").exists());
+        assert!(dir.join("src/test-input.txt").exists());
+
+        // covered by the existing pattern
+        assert_eq!(
+            fs::read_to_string(tmp.0.join("Cargo.toml")).unwrap(),
+            MANIFEST
+        );
+
+        // never overwrite
+        let err = scaffold(&tmp.0, &day, Template::Plain).unwrap_err();
+        assert!(err.to_string().contains("refusing to overwrite"));
+        assert!(fs::read_to_string(dir.join("src/main.rs"))
+            .unwrap()
+            .contains("parse_grid_as"));
+    }
+
+    #[test]
+    fn test_scaffold_keeps_input_and_registers() {
+        let tmp = TempDir::new("register");
+        fs::write(
+            tmp.0.join("Cargo.toml"),
+            "[workspace]\nmembers = [\n    \"aoc\",\n]\n",
+        )
+        .unwrap();
+        let day = Day::new(2015, 1).unwrap();
+        fs::create_dir_all(day.dir(&tmp.0).join("src")).unwrap();
+        fs::write(day.dir(&tmp.0).join("src/input.txt"), "(()").unwrap();
+
+        scaffold(&tmp.0, &day, Template::Plain).unwrap();
+        assert_eq!(
+            fs::read_to_string(day.dir(&tmp.0).join("src/input.txt")).unwrap(),
+            "(()"
+        );
+        assert_eq!(
+            fs::read_to_string(tmp.0.join("Cargo.toml")).unwrap(),
+            "[workspace]\nmembers = [\n    \"aoc\",\n    \"2015/01\",\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_template() {
+        assert_eq!("graph".parse::<Template>().unwrap(), Template::Graph);
+        assert!("tree".parse::<Template>().is_err());
+    }
+}
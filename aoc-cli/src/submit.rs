@@ -0,0 +1,416 @@
+use anyhow::{bail, ensure, Context, Result};
+use aoc::journal::{part_of, Check, Journal, Verdict};
+use std::fs;
+use std::path::Path;
+
+use crate::day::Day;
+use crate::fetch::{strip_tags, Cache, Fetcher};
+
+/// How the site answered a submission.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Verdict(Verdict),
+    /// The previous answer was too recent; the site's message says how long to wait.
+    TooSoon(String),
+    /// The part is already solved, or not unlocked yet.
+    WrongLevel,
+}
+
+/// Make sense of the page the site returns after a submission.
+pub fn outcome(html: &str) -> Result<Outcome> {
+    let text = article_text(html);
+    if text.contains("That's the right answer") {
+        Ok(Outcome::Verdict(Verdict::Correct))
+    } else if text.contains("That's not the right answer") {
+        Ok(Outcome::Verdict(if text.contains("too high") {
+            Verdict::TooHigh
+        } else if text.contains("too low") {
+            Verdict::TooLow
+        } else {
+            Verdict::Wrong
+        }))
+    } else if text.contains("You gave an answer too recently") {
+        Ok(Outcome::TooSoon(text))
+    } else if text.contains("You don't seem to be solving the right level") {
+        Ok(Outcome::WrongLevel)
+    } else {
+        bail!("unexpected response: {}", text)
+    }
+}
+
+// The text of the page's `<article>`, or of the whole page if there is none, on one line.
+fn article_text(html: &str) -> String {
+    let article = match (html.find("<article"), html.find("</article>")) {
+        (Some(start), Some(end)) if start < end => &html[start..end],
+        _ => html,
+    };
+    strip_tags(article)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Submit `answer` to `part` of `day`, and record the verdict in the puzzle's journal. Answers
+/// the journal already rules out are not sent; an answer the journal says is correct is not
+/// sent again.
+pub fn submit(
+    cache: &Cache<impl Fetcher>,
+    root: &Path,
+    day: &Day,
+    part: u32,
+    answer: &str,
+) -> Result<Verdict> {
+    ensure!(
+        part == 1 || part == 2,
+        "{}: bad part, expected 1 or 2",
+        part
+    );
+    ensure!(
+        !answer.is_empty() && !answer.contains(char::is_whitespace),
+        "{:?}: bad answer",
+        answer
+    );
+    let dir = day.dir(root);
+    ensure!(
+        dir.is_dir(),
+        "{}: no such puzzle, run `aoc-cli new-day` first",
+        day
+    );
+    let path = dir.join("journal.txt");
+    let mut journal = Journal::load(&path)?;
+    match journal.check(part, answer) {
+        Check::Correct => return Ok(Verdict::Correct),
+        Check::Wrong(reason) => bail!("refusing to submit {}: {}", answer, reason),
+        Check::Unknown => {}
+    }
+
+    let url = format!("/{}/day/{}/answer", day.year, day.day);
+    let level = part.to_string();
+    let response = cache.post(&url, &[("level", &level), ("answer", answer)])?;
+    let verdict = match outcome(&response)? {
+        Outcome::Verdict(verdict) => verdict,
+        Outcome::TooSoon(message) => bail!("{}", message),
+        Outcome::WrongLevel => bail!("{} part {}: already solved, or not unlocked yet", day, part),
+    };
+    journal.record(part, answer, verdict);
+    journal.save(&path)?;
+    Ok(verdict)
+}
+
+/// Make `answer` the expected value of the `run!` for `part` in `main_rs`, the one that calls
+/// `part_one` or `part_two` (the same rule `aoc::run!` uses to check the journal). Returns false,
+/// and leaves the file alone, if that `run!` already has an expectation.
+pub fn promote(main_rs: &Path, part: u32, answer: &str) -> Result<bool> {
+    let source = fs::read_to_string(main_rs)
+        .with_context(|| format!("{}: failed to read", main_rs.display()))?;
+    let code = blank_comments_and_strings(&source);
+    let mut calls = vec![];
+    for (i, m) in code.match_indices("run!(") {
+        let start = i + m.len();
+        let (comma, close) = top_level_comma_and_close(&code[start..])
+            .with_context(|| format!("{}: unterminated run!", main_rs.display()))?;
+        if part_of(&code[start..start + comma.unwrap_or(close)]) == Some(part) {
+            calls.push((start, comma, close));
+        }
+    }
+    let [(start, comma, close)] = calls[..] else {
+        bail!(
+            "{}: expected one run! for part {}, found {}",
+            main_rs.display(),
+            part,
+            calls.len()
+        );
+    };
+    let args = source[start..start + close].trim_end();
+    if let Some(comma) = comma {
+        if !source[start + comma + 1..start + close].trim().is_empty() {
+            return Ok(false);
+        }
+    }
+    let expr = args.trim_end_matches(',').trim_end();
+    let literal = match answer.parse::<i128>() {
+        Ok(_) => answer.to_string(),
+        Err(_) => format!("{:?}", answer),
+    };
+    let updated = format!(
+        "{}{}, {}{}",
+        &source[..start],
+        expr,
+        literal,
+        &source[start + close..]
+    );
+    fs::write(main_rs, updated)
+        .with_context(|| format!("{}: failed to write", main_rs.display()))?;
+    Ok(true)
+}
+
+// `source` with comments and the contents of string and char literals replaced by spaces, so
+// that byte offsets into it are offsets into `source` too.
+fn blank_comments_and_strings(source: &str) -> String {
+    enum State {
+        Code,
+        String { escaped: bool },
+        LineComment,
+        BlockComment,
+    }
+    let mut state = State::Code;
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let blank = match state {
+            State::Code => match (ch, chars.peek()) {
+                ('"', _) => {
+                    state = State::String { escaped: false };
+                    false
+                }
+                ('\'', _) => {
+                    // a lifetime or label is left alone
+                    if let Some(len) = char_literal_len(chars.clone()) {
+                        out.push(ch);
+                        for ch in chars.by_ref().take(len - 1) {
+                            out.extend(std::iter::repeat_n(' ', ch.len_utf8()));
+                        }
+                        out.extend(chars.next());
+                        continue;
+                    }
+                    false
+                }
+                ('/', Some('/')) => {
+                    state = State::LineComment;
+                    true
+                }
+                ('/', Some('*')) => {
+                    state = State::BlockComment;
+                    chars.next();
+                    out.push_str("  ");
+                    continue;
+                }
+                _ => false,
+            },
+            State::String { escaped } => {
+                match ch {
+                    _ if escaped => state = State::String { escaped: false },
+                    '\\' => state = State::String { escaped: true },
+                    '"' => state = State::Code,
+                    _ => {}
+                }
+                !matches!(state, State::Code)
+            }
+            State::LineComment => {
+                if ch == '\n' {
+                    state = State::Code;
+                }
+                ch != '\n'
+            }
+            State::BlockComment => {
+                if ch == '*' && chars.peek() == Some(&'/') {
+                    state = State::Code;
+                    chars.next();
+                    out.push_str("  ");
+                    continue;
+                }
+                ch != '\n'
+            }
+        };
+        if blank {
+            out.extend(std::iter::repeat_n(' ', ch.len_utf8()));
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+// The number of characters after the opening quote of a char literal, up to and including the
+// closing quote, or None if the quote starts a lifetime or a label.
+fn char_literal_len(mut rest: impl Iterator<Item = char>) -> Option<usize> {
+    match rest.next()? {
+        '\\' => {
+            rest.next()?;
+            rest.position(|ch| ch == '\'').map(|i| i + 3)
+        }
+        _ => (rest.next()? == '\'').then_some(2),
+    }
+}
+
+// The offsets of the first comma outside nested brackets, if any, and of the closing
+// parenthesis, in the arguments of a macro call. `args` must come from
+// blank_comments_and_strings, so that brackets and commas in literals and comments are gone.
+fn top_level_comma_and_close(args: &str) -> Option<(Option<usize>, usize)> {
+    let mut depth = 0;
+    let mut comma = None;
+    for (i, ch) in args.char_indices() {
+        match ch {
+            '(' | '[' | '{' => depth += 1,
+            ')' if depth == 0 => return Some((comma, i)),
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 && comma.is_none() => comma = Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetch::DirFetcher;
+    use crate::tests::TempDir;
+    use std::time::Duration;
+
+    fn page(text: &str) -> String {
+        format!(
+            "<html><body><main><article><p>{}</p></article></main></body></html>",
+            text
+        )
+    }
+
+    #[test]
+    fn test_outcome() {
+        assert_eq!(
+            outcome(&page(
+                "That's the right answer! You are <em>one gold star</em> closer."
+            ))
+            .unwrap(),
+            Outcome::Verdict(Verdict::Correct)
+        );
+        assert_eq!(
+            outcome(&page(
+                "That's not the right answer; your answer is too high."
+            ))
+            .unwrap(),
+            Outcome::Verdict(Verdict::TooHigh)
+        );
+        assert_eq!(
+            outcome(&page(
+                "That's not the right answer; your answer is too low."
+            ))
+            .unwrap(),
+            Outcome::Verdict(Verdict::TooLow)
+        );
+        assert_eq!(
+            outcome(&page("That's not the right answer. If you're stuck, ...")).unwrap(),
+            Outcome::Verdict(Verdict::Wrong)
+        );
+        assert_eq!(
+            outcome(&page(
+                "You gave an answer too recently. You have 42s left to wait."
+            ))
+            .unwrap(),
+            Outcome::TooSoon(
+                "You gave an answer too recently. You have 42s left to wait.".to_string()
+            )
+        );
+        assert_eq!(
+            outcome(&page("You don't seem to be solving the right level.")).unwrap(),
+            Outcome::WrongLevel
+        );
+        assert!(outcome("<html>maintenance</html>").is_err());
+    }
+
+    #[test]
+    fn test_submit() {
+        let tmp = TempDir::new("submit");
+        let site = tmp.0.join("site/2015/day/1");
+        fs::create_dir_all(&site).unwrap();
+        let root = tmp.0.join("root");
+        let day = Day::new(2015, 1).unwrap();
+        fs::create_dir_all(day.dir(&root)).unwrap();
+        let cache = Cache::new(
+            tmp.0.join("cache"),
+            DirFetcher::new(tmp.0.join("site")),
+            Duration::ZERO,
+        );
+        let respond = |text: &str| fs::write(site.join("answer"), page(text)).unwrap();
+        let journal = || Journal::load(&day.dir(&root).join("journal.txt")).unwrap();
+
+        respond("That's not the right answer; your answer is too high.");
+        assert_eq!(
+            submit(&cache, &root, &day, 1, "300").unwrap(),
+            Verdict::TooHigh
+        );
+        respond("That's not the right answer; your answer is too low.");
+        assert_eq!(
+            submit(&cache, &root, &day, 1, "100").unwrap(),
+            Verdict::TooLow
+        );
+        assert_eq!(journal().bounds(1), (Some(100), Some(300)));
+
+        // ruled out by the journal, not sent
+        fs::remove_file(site.join("answer")).unwrap();
+        for answer in ["300", "100", "400", "50"] {
+            let err = submit(&cache, &root, &day, 1, answer).unwrap_err();
+            assert!(err.to_string().contains("refusing to submit"));
+        }
+
+        // not recorded
+        respond("You gave an answer too recently. You have 42s left to wait.");
+        assert!(submit(&cache, &root, &day, 1, "200").is_err());
+        assert_eq!(journal().entries().len(), 2);
+
+        respond("That's the right answer!");
+        assert_eq!(
+            submit(&cache, &root, &day, 1, "200").unwrap(),
+            Verdict::Correct
+        );
+        assert_eq!(journal().correct(1), Some("200"));
+        fs::remove_file(site.join("answer")).unwrap();
+        assert_eq!(
+            submit(&cache, &root, &day, 1, "200").unwrap(),
+            Verdict::Correct
+        );
+        assert!(submit(&cache, &root, &day, 1, "201").is_err());
+        assert!(submit(&cache, &root, &day, 3, "1").is_err());
+    }
+
+    #[test]
+    fn test_blank_comments_and_strings() {
+        let source = "f(\"a//\\\"\") // g()\n/* h() */ i()";
+        let code = blank_comments_and_strings(source);
+        assert_eq!(code, "f(\"     \")       \n          i()");
+        assert_eq!(code.len(), source.len());
+
+        let source = "f('\"', '(', '\\'', '\\u{e9}', 'é') + g::<'a>(x)";
+        let code = blank_comments_and_strings(source);
+        assert_eq!(code, "f(' ', ' ', '  ', '      ', '  ') + g::<'a>(x)");
+        assert_eq!(code.len(), source.len());
+    }
+
+    #[test]
+    fn test_promote() {
+        let tmp = TempDir::new("promote");
+        let path = tmp.0.join("main.rs");
+        fs::write(
+            &path,
+            "fn main() -> Result<()> {
+    let input = include_str!(\"input.txt\");
+    // aoc::run!(part_two(input))?;
+    aoc::run!(part_one(input, (1, \")\")))?;
+    aoc::run!(part_two(
+        input, /* part_one */
+    ))?;
+    Ok(())
+}
+",
+        )
+        .unwrap();
+
+        assert!(promote(&path, 2, "ABC").unwrap());
+        assert!(promote(&path, 1, "-42").unwrap());
+        assert!(!promote(&path, 1, "43").unwrap());
+        assert!(promote(&path, 3, "1").is_err());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "fn main() -> Result<()> {
+    let input = include_str!(\"input.txt\");
+    // aoc::run!(part_two(input))?;
+    aoc::run!(part_one(input, (1, \")\")), -42)?;
+    aoc::run!(part_two(
+        input, /* part_one */
+    ), \"ABC\")?;
+    Ok(())
+}
+"
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::{Context, Result};
+use aoc::Graph;
+
+fn main() -> Result<()> {
+    let input = include_str!("input.txt");
+    aoc::run!(part_one(input))?;
+    aoc::run!(part_two(input))?;
+    Ok(())
+}
+
+type NodeId = String;
+
+fn parse(input: &str) -> Result<Graph<NodeId>> {
+    let mut graph = Graph::default();
+    for line in input.lines() {
+        let (from, to) = line.split_once('-').context("missing '-'")?;
+        for (a, b) in [(from, to), (to, from)] {
+            if graph.get_node_mut(&a.to_string()).is_none() {
+                graph.add_node(a.to_string());
+            }
+            graph
+                .get_node_mut(&a.to_string())
+                .expect("node just added")
+                .add_edge(b.to_string(), 1);
+        }
+    }
+    Ok(graph)
+}
+
+fn part_one(input: &str) -> Result<usize> {
+    let _ = parse(input)?;
+    todo!();
+}
+
+fn part_two(_input: &str) -> Result<usize> {
+    todo!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("test-input.txt");
+
+    #[test]
+    fn test_part_one() {
+        assert_eq!(part_one(INPUT).unwrap(), 0);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_part_two() {
+        assert_eq!(part_two(INPUT).unwrap(), 0);
+    }
+}
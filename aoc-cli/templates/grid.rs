@@ -0,0 +1,74 @@
+use anyhow::{bail, Result};
+use aoc::parse::{grid_markers, parse_grid_as};
+use aoc::{BoundingBox, XY};
+use std::collections::HashMap;
+
+fn main() -> Result<()> {
+    let input = include_str!("input.txt");
+    aoc::run!(part_one(input))?;
+    aoc::run!(part_two(input))?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Wall,
+    Open,
+}
+
+impl TryFrom<char> for Tile {
+    type Error = anyhow::Error;
+
+    fn try_from(ch: char) -> Result<Self> {
+        match ch {
+            '#' => Ok(Tile::Wall),
+            '.' | 'S' | 'E' => Ok(Tile::Open),
+            _ => bail!("unexpected char"),
+        }
+    }
+}
+
+struct Grid {
+    tiles: HashMap<XY, Tile>,
+    bounds: BoundingBox,
+    start: XY,
+    end: XY,
+}
+
+fn parse(input: &str) -> Result<Grid> {
+    let (tiles, bounds) = parse_grid_as(input)?;
+    let [start, end] = grid_markers(input, ['S', 'E'])?;
+    Ok(Grid {
+        tiles,
+        bounds,
+        start,
+        end,
+    })
+}
+
+fn part_one(input: &str) -> Result<usize> {
+    let _ = parse(input)?;
+    todo!();
+}
+
+fn part_two(_input: &str) -> Result<usize> {
+    todo!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("test-input.txt");
+
+    #[test]
+    fn test_part_one() {
+        assert_eq!(part_one(INPUT).unwrap(), 0);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_part_two() {
+        assert_eq!(part_two(INPUT).unwrap(), 0);
+    }
+}
@@ -0,0 +1,39 @@
+use anyhow::Result;
+
+fn main() -> Result<()> {
+    let input = include_str!("input.txt");
+    aoc::run!(part_one(input))?;
+    aoc::run!(part_two(input))?;
+    Ok(())
+}
+
+fn parse(input: &str) -> Result<()> {
+    todo!();
+}
+
+fn part_one(input: &str) -> Result<usize> {
+    let _ = parse(input)?;
+    todo!();
+}
+
+fn part_two(_input: &str) -> Result<usize> {
+    todo!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("test-input.txt");
+
+    #[test]
+    fn test_part_one() {
+        assert_eq!(part_one(INPUT).unwrap(), 0);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_part_two() {
+        assert_eq!(part_two(INPUT).unwrap(), 0);
+    }
+}
@@ -0,0 +1,260 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use regex::Regex;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Expr, Fields, Ident, LitStr};
+
+/// Derive `FromStr` from a regular expression.
+///
+/// A struct declares its regex with `#[regex(r"...")]`; an enum declares one per variant, and the
+/// first variant whose regex matches is used. Each capture group is converted to the field with
+/// the same index, or, if the regex has named groups, to the field with the same name. Fields are
+/// converted with `FromStr`, or with the function or closure given in `#[parse_with(...)]`.
+///
+/// ```ignore
+/// #[derive(aoc::FromRegex)]
+/// #[regex(r"^(\w+) is (\d+) years old$")]
+/// struct Person {
+///     name: String,
+///     age: u32,
+/// }
+/// ```
+///
+/// The derive also implements `aoc::parse::FindRegex`, so `aoc::parse::find_all` can pick every
+/// match out of a longer text.
+///
+/// A failed conversion is reported as an `aoc::parse::ParseError` pointing at the group, with
+/// the message `group 2: "x": invalid digit found in string`.
+#[proc_macro_derive(FromRegex, attributes(regex, parse_with))]
+pub fn derive_from_regex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (parse_body, find_body) = match &input.data {
+        Data::Struct(data) => {
+            let regex = regex_attr(&input.attrs, input.span())?;
+            let shape = expand_fields(&regex, &data.fields, quote!(Self))?;
+            let parse = parse_shape(&shape);
+            let find = find_shape(&shape);
+            // a single regex, which searches from start anyway
+            let find = quote! {
+                let _ = state;
+                #find
+            };
+            (
+                quote! {
+                    #parse
+                    Err(::aoc::__private::anyhow::anyhow!(
+                        "regex not found in \"{}\"",
+                        s
+                    ))
+                },
+                find,
+            )
+        }
+        Data::Enum(data) => {
+            let mut shapes = vec![];
+            for variant in data.variants.iter() {
+                let regex = regex_attr(&variant.attrs, variant.span())?;
+                let ident = &variant.ident;
+                shapes.push(expand_fields(
+                    &regex,
+                    &variant.fields,
+                    quote!(Self::#ident),
+                )?);
+            }
+            let parses = shapes.iter().map(parse_shape);
+            let indices: Vec<usize> = (0..shapes.len()).collect();
+            let nexts = shapes.iter().enumerate().map(|(index, shape)| {
+                let regex = &shape.regex;
+                quote! {
+                    {
+                        #regex
+                        state.next_match(#index, regex, s, start)
+                    }
+                }
+            });
+            let finds = shapes.iter().map(find_shape);
+            let message = format!("no variant of {} matches \"{{}}\"", name);
+            (
+                quote! {
+                    #(#parses)*
+                    Err(::aoc::__private::anyhow::anyhow!(#message, s))
+                },
+                // the leftmost match wins; on a tie, the variant declared first. Each variant's next
+                // match is remembered in state, so only the regex that matched searches again.
+                quote! {
+                    let mut best: ::std::option::Option<(usize, ::std::ops::Range<usize>)> = None;
+                    #(
+                        if let Some(found) = #nexts {
+                            match &best {
+                                Some((_, range)) if range.start <= found.start => {}
+                                _ => best = Some((#indices, found)),
+                            }
+                        }
+                    )*
+                    let (index, range) = best?;
+                    let start = range.start;
+                    match index {
+                        #(#indices => #finds,)*
+                        _ => unreachable!(),
+                    }
+                },
+            )
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                input.span(),
+                "FromRegex cannot be derived for unions",
+            ))
+        }
+    };
+    Ok(quote! {
+        impl #impl_generics ::std::str::FromStr for #name #ty_generics #where_clause {
+            type Err = ::aoc::__private::anyhow::Error;
+
+            fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                #parse_body
+            }
+        }
+
+        impl #impl_generics ::aoc::parse::FindRegex for #name #ty_generics #where_clause {
+            fn find_at(
+                s: &str,
+                start: usize,
+                state: &mut ::aoc::parse::FindState,
+            ) -> ::std::option::Option<(
+                ::std::ops::Range<usize>,
+                ::aoc::__private::anyhow::Result<Self>,
+            )> {
+                #find_body
+            }
+        }
+    })
+}
+
+fn regex_attr(attrs: &[Attribute], span: proc_macro2::Span) -> syn::Result<LitStr> {
+    let mut found = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("regex")) {
+        if found.is_some() {
+            return Err(syn::Error::new(attr.span(), "duplicate #[regex] attribute"));
+        }
+        found = Some(attr.parse_args::<LitStr>()?);
+    }
+    found.ok_or_else(|| syn::Error::new(span, "missing #[regex(r\"...\")] attribute"))
+}
+
+fn parse_with_attr(attrs: &[Attribute]) -> syn::Result<Option<Expr>> {
+    attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("parse_with"))
+        .map(|attr| attr.parse_args::<Expr>())
+        .transpose()
+}
+
+// The compiled regex, and the expression that builds constructor { fields } from its captures.
+struct Shape {
+    regex: TokenStream2,
+    construct: TokenStream2,
+}
+
+fn expand_fields(lit: &LitStr, fields: &Fields, constructor: TokenStream2) -> syn::Result<Shape> {
+    let regex = Regex::new(&lit.value())
+        .map_err(|err| syn::Error::new(lit.span(), format!("invalid regex: {}", err)))?;
+    let group_count = regex.captures_len() - 1;
+    let group_names: Vec<Option<&str>> = regex.capture_names().skip(1).collect();
+    let has_named_groups = group_names.iter().any(|name| name.is_some());
+
+    let mut conversions = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let group = match (&field.ident, has_named_groups) {
+            (Some(ident), true) => {
+                let wanted = ident.to_string();
+                let wanted = wanted.strip_prefix("r#").unwrap_or(&wanted);
+                group_names
+                    .iter()
+                    .position(|name| *name == Some(wanted))
+                    .map(|index| index + 1)
+                    .ok_or_else(|| {
+                        syn::Error::new(
+                            field.span(),
+                            format!("regex has no group named '{}'", wanted),
+                        )
+                    })?
+            }
+            _ => i + 1,
+        };
+        let ty = &field.ty;
+        let convert = match parse_with_attr(&field.attrs)? {
+            Some(expr) => quote_spanned!(expr.span()=> #expr),
+            None => quote!(<#ty as ::std::str::FromStr>::from_str),
+        };
+        let value = quote! {
+            ::aoc::__private::convert_group(s, &caps, #group, #convert)?
+        };
+        conversions.push(match &field.ident {
+            Some(ident) => quote!(#ident: #value),
+            None => value,
+        });
+    }
+    if !has_named_groups && !fields.is_empty() && fields.len() != group_count {
+        return Err(syn::Error::new(
+            lit.span(),
+            format!(
+                "regex has {} capture groups, but there are {} fields",
+                group_count,
+                fields.len()
+            ),
+        ));
+    }
+
+    let construct = match fields {
+        Fields::Named(_) => quote!(#constructor { #(#conversions),* }),
+        Fields::Unnamed(_) => quote!(#constructor ( #(#conversions),* )),
+        Fields::Unit => quote!(#constructor),
+    };
+    let static_name = Ident::new("REGEX", lit.span());
+    let regex = quote! {
+        static #static_name: ::std::sync::OnceLock<::aoc::__private::Regex> =
+            ::std::sync::OnceLock::new();
+        let regex = #static_name.get_or_init(|| {
+            ::aoc::__private::Regex::new(#lit).expect("regex checked at compile time")
+        });
+    };
+    Ok(Shape { regex, construct })
+}
+
+// Code that returns Ok(value) from from_str if the regex matches s, and falls through otherwise.
+fn parse_shape(shape: &Shape) -> TokenStream2 {
+    let Shape { regex, construct } = shape;
+    quote! {
+        {
+            #regex
+            if let Some(caps) = regex.captures(s) {
+                return Ok(#construct);
+            }
+        }
+    }
+}
+
+// An expression for the first match at or after start in s, and its conversion.
+fn find_shape(shape: &Shape) -> TokenStream2 {
+    let Shape { regex, construct } = shape;
+    quote! {
+        {
+            #regex
+            regex.captures_at(s, start).map(|caps| {
+                let range = caps.get(0).expect("group 0 always participates").range();
+                (range, ::aoc::__private::attempt(|| Ok(#construct)))
+            })
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::fmt::Display;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitError {
+    /// Not a hexadecimal digit.
+    BadHexDigit(char),
+    /// More than 64 bits requested in a single read.
+    TooWide(u32),
+    /// Tried to read past the end of the stream.
+    EndOfStream {
+        position: usize,
+        wanted: usize,
+        available: usize,
+    },
+    /// A chunked value did not fit in 64 bits.
+    Overflow { position: usize },
+}
+
+impl Display for BitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BitError::BadHexDigit(ch) => write!(f, "bad hex digit '{}'", ch),
+            BitError::TooWide(n) => write!(f, "cannot read {} bits into a u64", n),
+            BitError::EndOfStream {
+                position,
+                wanted,
+                available,
+            } => write!(
+                f,
+                "bit {}: wanted {} bits, only {} left",
+                position, wanted, available
+            ),
+            BitError::Overflow { position } => {
+                write!(f, "bit {}: chunked value exceeds 64 bits", position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BitError {}
+
+/// Reads a stream of bits, most significant bit of each byte first.
+#[derive(Debug, Clone)]
+pub struct BitReader {
+    data: Rc<[u8]>,
+    start: usize,
+    end: usize,
+    position: usize,
+}
+
+impl BitReader {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        BitReader {
+            data: bytes.into(),
+            start: 0,
+            end: bytes.len() * 8,
+            position: 0,
+        }
+    }
+
+    /// Create a reader from hexadecimal digits, each digit being 4 bits. Surrounding whitespace is
+    /// ignored.
+    pub fn from_hex(hex: &str) -> Result<Self, BitError> {
+        let digits = hex
+            .trim()
+            .chars()
+            .map(|ch| ch.to_digit(16).ok_or(BitError::BadHexDigit(ch)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let bytes: Vec<u8> = digits
+            .chunks(2)
+            .map(|pair| (pair[0] << 4 | pair.get(1).unwrap_or(&0)) as u8)
+            .collect();
+        Ok(BitReader {
+            data: bytes.into(),
+            start: 0,
+            end: digits.len() * 4,
+            position: 0,
+        })
+    }
+
+    /// Number of bits read so far. For a sub-stream, this is relative to the start of the
+    /// sub-stream.
+    pub fn position(&self) -> usize {
+        self.position - self.start
+    }
+
+    /// Number of bits left to read.
+    pub fn remaining(&self) -> usize {
+        self.end - self.position
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    fn ensure_available(&self, wanted: usize) -> Result<(), BitError> {
+        if wanted > self.remaining() {
+            return Err(BitError::EndOfStream {
+                position: self.position(),
+                wanted,
+                available: self.remaining(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Read an n bit unsigned value.
+    pub fn read(&mut self, n: u32) -> Result<u64, BitError> {
+        if n > u64::BITS {
+            return Err(BitError::TooWide(n));
+        }
+        self.ensure_available(n as usize)?;
+        let mut value = 0;
+        for _ in 0..n {
+            let byte = self.data[self.position / 8];
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u64;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, BitError> {
+        Ok(self.read(1)? == 1)
+    }
+
+    /// Read a value stored as groups of 1 + chunk_bits bits, where the first bit of each group is
+    /// 1 if more groups follow, and the rest of the bits are the next chunk of the value, most
+    /// significant chunk first.
+    pub fn read_chunked(&mut self, chunk_bits: u32) -> Result<u64, BitError> {
+        let start = self.position();
+        let mut value: u64 = 0;
+        loop {
+            let more = self.read_bool()?;
+            let chunk = self.read(chunk_bits)?;
+            if chunk_bits > 0 && value.leading_zeros() < chunk_bits {
+                return Err(BitError::Overflow { position: start });
+            }
+            value = value.checked_shl(chunk_bits).unwrap_or(0) | chunk;
+            if !more {
+                return Ok(value);
+            }
+        }
+    }
+
+    pub fn skip(&mut self, n: usize) -> Result<(), BitError> {
+        self.ensure_available(n)?;
+        self.position += n;
+        Ok(())
+    }
+
+    /// Split off the next len bits as a separate reader, and skip past them in this reader.
+    pub fn sub_stream(&mut self, len: usize) -> Result<BitReader, BitError> {
+        self.ensure_available(len)?;
+        let sub = BitReader {
+            data: self.data.clone(),
+            start: self.position,
+            end: self.position + len,
+            position: self.position,
+        };
+        self.position += len;
+        Ok(sub)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read() {
+        let mut r = BitReader::from_hex("D2FE28").unwrap();
+        assert_eq!(r.remaining(), 24);
+        assert_eq!(r.read(3), Ok(6));
+        assert_eq!(r.read(3), Ok(4));
+        assert_eq!(r.position(), 6);
+        assert_eq!(r.read(0), Ok(0));
+        assert_eq!(r.read(18), Ok(0b101111111000101000));
+        assert!(r.is_empty());
+        assert_eq!(
+            r.read(1),
+            Err(BitError::EndOfStream {
+                position: 24,
+                wanted: 1,
+                available: 0
+            })
+        );
+
+        let mut r = BitReader::from_bytes(&[0xff; 9]);
+        assert_eq!(r.read(65), Err(BitError::TooWide(65)));
+        assert_eq!(r.read(64), Ok(u64::MAX));
+        assert_eq!(r.read_bool(), Ok(true));
+    }
+
+    #[test]
+    fn test_from_hex() {
+        let mut r = BitReader::from_hex(" F0F\n").unwrap();
+        assert_eq!(r.remaining(), 12);
+        assert_eq!(r.read(12), Ok(0xf0f));
+        assert_eq!(
+            BitReader::from_hex("12x").err(),
+            Some(BitError::BadHexDigit('x'))
+        );
+    }
+
+    #[test]
+    fn test_read_chunked() {
+        let mut r = BitReader::from_hex("D2FE28").unwrap();
+        r.skip(6).unwrap();
+        assert_eq!(r.read_chunked(4), Ok(2021));
+        assert_eq!(r.remaining(), 3);
+
+        // 17 groups of 4 bits do not fit in a u64
+        let mut r = BitReader::from_bytes(&[0xff; 11]);
+        assert_eq!(r.read_chunked(4), Err(BitError::Overflow { position: 0 }));
+    }
+
+    #[test]
+    fn test_sub_stream() {
+        let mut r = BitReader::from_hex("38006F45291200").unwrap();
+        r.skip(7).unwrap();
+        let len = r.read(15).unwrap() as usize;
+        assert_eq!(len, 27);
+        let mut sub = r.sub_stream(len).unwrap();
+        assert_eq!(sub.position(), 0);
+        assert_eq!(sub.remaining(), 27);
+        sub.skip(6).unwrap();
+        assert_eq!(sub.read_chunked(4), Ok(10));
+        sub.skip(6).unwrap();
+        assert_eq!(sub.read_chunked(4), Ok(20));
+        assert!(sub.is_empty());
+        assert_eq!(r.position(), 49);
+        assert!(r.sub_stream(8).is_err());
+    }
+}
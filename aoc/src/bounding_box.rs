@@ -23,6 +23,36 @@ impl BoundingBox {
         }
     }
 
+    /// The smallest BoundingBox that contains all points, or None if there are no points.
+    pub fn from_points(points: impl IntoIterator<Item = XY>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let (top_left, bottom_right) = points.fold((first, first), |(min, max), xy| {
+            (
+                XY {
+                    x: min.x.min(xy.x),
+                    y: min.y.min(xy.y),
+                },
+                XY {
+                    x: max.x.max(xy.x),
+                    y: max.y.max(xy.y),
+                },
+            )
+        });
+        Some(BoundingBox {
+            top_left,
+            bottom_right,
+        })
+    }
+
+    pub fn top_left(&self) -> XY {
+        self.top_left
+    }
+
+    pub fn bottom_right(&self) -> XY {
+        self.bottom_right
+    }
+
     /// Check if an XY coordinate is within the bounding box (including on the border of the
     /// bounding box).
     pub fn contains(&self, xy: &XY) -> bool {
@@ -44,6 +74,13 @@ mod tests {
         assert_eq!(a.top_left, b.top_left);
     }
 
+    #[test]
+    fn test_from_points() {
+        let bb = BoundingBox::from_points([(2, 1).into(), (0, 3).into(), (1, -1).into()]);
+        assert_eq!(bb, Some(BoundingBox::new((0, -1).into(), (2, 3).into())));
+        assert_eq!(BoundingBox::from_points([]), None);
+    }
+
     #[test]
     fn test_contains() {
         // ....
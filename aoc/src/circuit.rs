@@ -0,0 +1,413 @@
+use anyhow::{anyhow, ensure, Result};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+/// A gate input: either a constant or the value of another wire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input<V> {
+    Value(V),
+    Wire(String),
+}
+
+impl<V: FromStr> Input<V> {
+    /// A constant if `s` parses as a value, otherwise a wire name.
+    pub fn parse(s: &str) -> Self {
+        match s.parse() {
+            Ok(value) => Input::Value(value),
+            Err(_) => Input::Wire(s.to_string()),
+        }
+    }
+}
+
+type Op<V> = Box<dyn Fn(&[V]) -> V>;
+
+struct Gate<V> {
+    inputs: Vec<Input<V>>,
+    op: Op<V>,
+}
+
+/// A network of named wires, each driven by a gate that computes its value from other wires.
+/// Wires are evaluated lazily, on demand, and their values are cached until the circuit changes.
+pub struct Circuit<V> {
+    gates: FxHashMap<String, Gate<V>>,
+    values: RefCell<FxHashMap<String, V>>,
+}
+
+impl<V> Default for Circuit<V> {
+    fn default() -> Self {
+        Circuit {
+            gates: FxHashMap::default(),
+            values: RefCell::new(FxHashMap::default()),
+        }
+    }
+}
+
+impl<V: Clone + 'static> Circuit<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drive `wire` by a gate. Any previous gate driving the wire is replaced.
+    pub fn add_gate(
+        &mut self,
+        wire: &str,
+        inputs: Vec<Input<V>>,
+        op: impl Fn(&[V]) -> V + 'static,
+    ) {
+        let gate = Gate {
+            inputs,
+            op: Box::new(op),
+        };
+        self.gates.insert(wire.to_string(), gate);
+        self.values.borrow_mut().clear();
+    }
+
+    /// Override `wire` with a constant value.
+    pub fn set(&mut self, wire: &str, value: V) {
+        self.add_gate(wire, vec![], move |_| value.clone());
+    }
+
+    pub fn value(&self, wire: &str) -> Result<V> {
+        self.value_inner(wire, &mut vec![])
+    }
+
+    fn value_inner<'a>(&'a self, wire: &'a str, visiting: &mut Vec<&'a str>) -> Result<V> {
+        if let Some(value) = self.values.borrow().get(wire) {
+            return Ok(value.clone());
+        }
+        let gate = self
+            .gates
+            .get(wire)
+            .ok_or_else(|| anyhow!("wire '{}' is not connected", wire))?;
+        ensure!(
+            !visiting.contains(&wire),
+            "wire '{}' depends on itself",
+            wire
+        );
+        visiting.push(wire);
+        let mut args = Vec::with_capacity(gate.inputs.len());
+        for input in gate.inputs.iter() {
+            args.push(match input {
+                Input::Value(value) => value.clone(),
+                Input::Wire(other) => self.value_inner(other, visiting)?,
+            });
+        }
+        visiting.pop();
+        let value = (gate.op)(&args);
+        self.values
+            .borrow_mut()
+            .insert(wire.to_string(), value.clone());
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pulse {
+    Low,
+    High,
+}
+
+/// How a module reacts to an incoming pulse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Forwards every pulse.
+    Broadcast,
+    /// Ignores high pulses. A low pulse toggles it on or off; it then sends high if it turned on,
+    /// and low if it turned off.
+    FlipFlop,
+    /// Remembers the last pulse from each input, and sends low if all of them were high,
+    /// otherwise high.
+    Conjunction,
+}
+
+#[derive(Debug, Clone)]
+struct Module {
+    kind: Kind,
+    outputs: Vec<usize>,
+    inputs: Vec<usize>,
+    // false until added, for modules only seen as outputs so far
+    defined: bool,
+    // FlipFlop state
+    on: bool,
+    // Conjunction state, one entry per input
+    memory: Vec<Pulse>,
+}
+
+/// A pulse delivered from one module to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signal<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub pulse: Pulse,
+}
+
+/// Modules that pass pulses to each other. Pulses are processed in the order they are sent.
+#[derive(Debug, Clone, Default)]
+pub struct PulseNetwork {
+    names: Vec<String>,
+    ids: FxHashMap<String, usize>,
+    modules: Vec<Module>,
+}
+
+impl PulseNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse lines like `broadcaster -> a, b`, `%a -> b` (flip-flop) and `&b -> c`
+    /// (conjunction).
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut network = PulseNetwork::new();
+        for line in input.lines() {
+            let (name, outputs) = line
+                .split_once(" -> ")
+                .ok_or_else(|| anyhow!("missing arrow: \"{}\"", line))?;
+            let (kind, name) = if let Some(name) = name.strip_prefix('%') {
+                (Kind::FlipFlop, name)
+            } else if let Some(name) = name.strip_prefix('&') {
+                (Kind::Conjunction, name)
+            } else {
+                (Kind::Broadcast, name)
+            };
+            network.add_module(name, kind, outputs.split(", "))?;
+        }
+        Ok(network)
+    }
+
+    fn id(&mut self, name: &str) -> usize {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.modules.push(Module {
+            kind: Kind::Broadcast,
+            outputs: vec![],
+            inputs: vec![],
+            defined: false,
+            on: false,
+            memory: vec![],
+        });
+        id
+    }
+
+    /// Add a module. Outputs that have not been added yet are created as broadcast modules
+    /// without outputs, until they are added themselves.
+    pub fn add_module<'a>(
+        &mut self,
+        name: &str,
+        kind: Kind,
+        outputs: impl IntoIterator<Item = &'a str>,
+    ) -> Result<()> {
+        let id = self.id(name);
+        ensure!(!self.modules[id].defined, "module '{}' added twice", name);
+        self.modules[id].defined = true;
+        self.modules[id].kind = kind;
+        for output in outputs {
+            let output = self.id(output);
+            self.modules[id].outputs.push(output);
+            self.modules[output].inputs.push(id);
+            self.modules[output].memory.push(Pulse::Low);
+        }
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Result<usize> {
+        self.ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow!("unknown module '{}'", name))
+    }
+
+    /// Names of the modules that send pulses to `name`.
+    pub fn inputs(&self, name: &str) -> Result<Vec<String>> {
+        let id = self.get(name)?;
+        Ok(self.modules[id]
+            .inputs
+            .iter()
+            .map(|i| self.names[*i].clone())
+            .collect())
+    }
+
+    /// Turn off all flip-flops and make all conjunctions forget their inputs.
+    pub fn reset(&mut self) {
+        for module in self.modules.iter_mut() {
+            module.on = false;
+            module.memory.fill(Pulse::Low);
+        }
+    }
+
+    /// Send a pulse to a module, and keep processing the pulses that causes until the network is
+    /// quiet. The sender does not have to be a module. Every delivered pulse, including the
+    /// first, is passed to `observe`.
+    pub fn send(
+        &mut self,
+        from: &str,
+        to: &str,
+        pulse: Pulse,
+        mut observe: impl FnMut(Signal),
+    ) -> Result<()> {
+        let to = self.get(to)?;
+        observe(Signal {
+            from,
+            to: &self.names[to],
+            pulse,
+        });
+        let mut queue = VecDeque::new();
+        if let Some(pulse) = self.receive(None, to, pulse) {
+            queue.push_back((to, pulse));
+        }
+        while let Some((src, pulse)) = queue.pop_front() {
+            for i in 0..self.modules[src].outputs.len() {
+                let dest = self.modules[src].outputs[i];
+                observe(Signal {
+                    from: &self.names[src],
+                    to: &self.names[dest],
+                    pulse,
+                });
+                if let Some(pulse) = self.receive(Some(src), dest, pulse) {
+                    queue.push_back((dest, pulse));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Update a module's state, and return the pulse it sends in response, if any
+    fn receive(&mut self, src: Option<usize>, dest: usize, pulse: Pulse) -> Option<Pulse> {
+        let module = &mut self.modules[dest];
+        match module.kind {
+            Kind::Broadcast => Some(pulse),
+            Kind::FlipFlop => {
+                if pulse == Pulse::High {
+                    return None;
+                }
+                module.on = !module.on;
+                Some(if module.on { Pulse::High } else { Pulse::Low })
+            }
+            Kind::Conjunction => {
+                // a pulse from outside the network is not remembered
+                if let Some(i) = src.and_then(|src| module.inputs.iter().position(|i| *i == src)) {
+                    module.memory[i] = pulse;
+                }
+                if module.memory.iter().all(|p| *p == Pulse::High) {
+                    Some(Pulse::Low)
+                } else {
+                    Some(Pulse::High)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_circuit() -> Circuit<u16> {
+        let mut c = Circuit::<u16>::new();
+        c.add_gate("x", vec![Input::parse("123")], |v| v[0]);
+        c.add_gate("y", vec![Input::parse("456")], |v| v[0]);
+        c.add_gate("d", vec![Input::parse("x"), Input::parse("y")], |v| {
+            v[0] & v[1]
+        });
+        c.add_gate("e", vec![Input::parse("x"), Input::parse("y")], |v| {
+            v[0] | v[1]
+        });
+        c.add_gate("f", vec![Input::parse("x"), Input::parse("2")], |v| {
+            v[0] << v[1]
+        });
+        c.add_gate("h", vec![Input::parse("x")], |v| !v[0]);
+        c
+    }
+
+    #[test]
+    fn test_circuit() {
+        let mut c = example_circuit();
+        assert_eq!(c.value("d").unwrap(), 72);
+        assert_eq!(c.value("e").unwrap(), 507);
+        assert_eq!(c.value("f").unwrap(), 492);
+        assert_eq!(c.value("h").unwrap(), 65412);
+        assert!(c.value("z").is_err());
+
+        c.set("x", 1);
+        assert_eq!(c.value("d").unwrap(), 0);
+        assert_eq!(c.value("f").unwrap(), 4);
+    }
+
+    #[test]
+    fn test_circuit_loop() {
+        let mut c = example_circuit();
+        c.add_gate("x", vec![Input::parse("d")], |v| v[0]);
+        assert!(c.value("d").is_err());
+    }
+
+    fn count_pulses(network: &mut PulseNetwork, presses: usize) -> (usize, usize) {
+        let (mut low, mut high) = (0, 0);
+        for _ in 0..presses {
+            network
+                .send("button", "broadcaster", Pulse::Low, |signal| {
+                    match signal.pulse {
+                        Pulse::Low => low += 1,
+                        Pulse::High => high += 1,
+                    }
+                })
+                .unwrap();
+        }
+        (low, high)
+    }
+
+    #[test]
+    fn test_pulse_network() {
+        let mut network =
+            PulseNetwork::parse("broadcaster -> a, b, c\n%a -> b\n%b -> c\n%c -> inv\n&inv -> a")
+                .unwrap();
+        assert_eq!(count_pulses(&mut network, 1), (8, 4));
+        assert_eq!(count_pulses(&mut network, 999), (7992, 3996));
+        assert_eq!(network.inputs("a").unwrap(), vec!["broadcaster", "inv"]);
+        assert!(network.send("button", "nope", Pulse::Low, |_| {}).is_err());
+
+        // a module without outputs is still only added once
+        let mut network = PulseNetwork::new();
+        network.add_module("a", Kind::FlipFlop, ["sink"]).unwrap();
+        network.add_module("sink", Kind::Conjunction, []).unwrap();
+        let err = network
+            .add_module("sink", Kind::FlipFlop, ["a"])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "module 'sink' added twice");
+    }
+
+    #[test]
+    fn test_pulse_network_state() {
+        let mut network = PulseNetwork::parse(
+            "broadcaster -> a\n%a -> inv, con\n&inv -> b\n%b -> con\n&con -> output",
+        )
+        .unwrap();
+        let mut output = vec![];
+        for _ in 0..4 {
+            network
+                .send("button", "broadcaster", Pulse::Low, |signal| {
+                    if signal.to == "output" {
+                        output.push(signal.pulse);
+                    }
+                })
+                .unwrap();
+        }
+        use Pulse::*;
+        assert_eq!(output, vec![High, Low, High, Low, High, High]);
+
+        network.reset();
+        output.clear();
+        network
+            .send("button", "broadcaster", Pulse::Low, |signal| {
+                if signal.to == "output" {
+                    output.push(signal.pulse);
+                }
+            })
+            .unwrap();
+        assert_eq!(output, vec![High, Low]);
+    }
+}
@@ -0,0 +1,512 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::parse::ParseError;
+
+/// Why a parser did not match.
+///
+/// When several alternatives fail, the failure that got furthest into the input is kept, so
+/// errors point at the character that could not be parsed rather than at the start of the
+/// alternatives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    // bytes of input left at the failure: the failure that got furthest has the least left
+    remaining: usize,
+    expected: Vec<String>,
+    // set for errors that alternatives must not backtrack over, such as a failed conversion
+    fatal: Option<String>,
+}
+
+impl Failure {
+    /// `input` does not start with `what`.
+    pub fn expected(input: &str, what: impl Display) -> Self {
+        Failure {
+            remaining: input.len(),
+            expected: vec![what.to_string()],
+            fatal: None,
+        }
+    }
+
+    /// `input` starts with the right thing, but it is wrong. Not recovered from by [`alt`],
+    /// [`optional`] or repetition.
+    pub fn fatal(input: &str, message: impl Display) -> Self {
+        Failure {
+            remaining: input.len(),
+            expected: vec![],
+            fatal: Some(message.to_string()),
+        }
+    }
+
+    fn is_fatal(&self) -> bool {
+        self.fatal.is_some()
+    }
+
+    fn merge(mut self, other: Failure) -> Failure {
+        if other.remaining < self.remaining {
+            return other;
+        }
+        if other.remaining == self.remaining {
+            for what in other.expected {
+                if !self.expected.contains(&what) {
+                    self.expected.push(what);
+                }
+            }
+        }
+        self
+    }
+
+    /// Convert to a [`ParseError`] located in `input`, the text given to the outermost parser.
+    pub fn into_error(self, input: &str) -> anyhow::Error {
+        let offset = input.len().saturating_sub(self.remaining);
+        let message = match self.fatal {
+            Some(message) => message,
+            None => {
+                let expected = match self.expected.as_slice() {
+                    [what] => what.clone(),
+                    all => format!("one of {}", all.join(", ")),
+                };
+                let found = match input[offset..].chars().next() {
+                    Some(ch) => format!("{:?}", ch),
+                    None => "end of input".to_string(),
+                };
+                format!("expected {}, found {}", expected, found)
+            }
+        };
+        anyhow::Error::new(ParseError::at(input, offset, message))
+    }
+}
+
+/// The parsed value and the rest of the input, or why the parser did not match.
+pub type PResult<'a, T> = Result<(T, &'a str), Failure>;
+
+/// Anything that parses a `T` from the start of a `&str`. Implemented for every
+/// `Fn(&str) -> PResult<T>`, so recursive grammars can be written as plain functions:
+///
+/// ```ignore
+/// fn list(input: &str) -> PResult<'_, Vec<u32>> {
+///     delimited(literal("["), separated(integer(), literal(",")), literal("]")).parse(input)
+/// }
+/// ```
+pub trait Parser<'a, T> {
+    fn parse(&self, input: &'a str) -> PResult<'a, T>;
+}
+
+impl<'a, T, F> Parser<'a, T> for F
+where
+    F: Fn(&'a str) -> PResult<'a, T>,
+{
+    fn parse(&self, input: &'a str) -> PResult<'a, T> {
+        self(input)
+    }
+}
+
+/// Run `parser` on all of `input`; only trailing whitespace may be left over.
+pub fn parse_all<'a, T>(input: &'a str, parser: impl Parser<'a, T>) -> anyhow::Result<T> {
+    let (value, rest) = parser.parse(input).map_err(|f| f.into_error(input))?;
+    let rest = rest.trim_start();
+    if !rest.is_empty() {
+        return Err(Failure::expected(rest, "end of input").into_error(input));
+    }
+    Ok(value)
+}
+
+/// Exactly `lit`.
+pub fn literal<'a>(lit: &'static str) -> impl Parser<'a, &'a str> {
+    move |input: &'a str| match input.strip_prefix(lit) {
+        Some(rest) => Ok((&input[..lit.len()], rest)),
+        None => Err(Failure::expected(input, format!("{:?}", lit))),
+    }
+}
+
+/// One character that is in `chars`.
+pub fn one_of<'a>(chars: &'static str) -> impl Parser<'a, char> {
+    move |input: &'a str| match input.chars().next() {
+        Some(ch) if chars.contains(ch) => Ok((ch, &input[ch.len_utf8()..])),
+        _ => Err(Failure::expected(input, format!("one of {:?}", chars))),
+    }
+}
+
+/// The longest non-empty prefix whose characters all satisfy `pred`, described as `what` in
+/// errors.
+pub fn take_while1<'a, F>(what: &'static str, pred: F) -> impl Parser<'a, &'a str>
+where
+    F: Fn(char) -> bool,
+{
+    move |input: &'a str| {
+        let end = input.find(|ch| !pred(ch)).unwrap_or(input.len());
+        if end == 0 {
+            return Err(Failure::expected(input, what));
+        }
+        Ok((&input[..end], &input[end..]))
+    }
+}
+
+/// Zero or more whitespace characters, including newlines.
+pub fn whitespace<'a>() -> impl Parser<'a, &'a str> {
+    move |input: &'a str| {
+        let rest = input.trim_start();
+        Ok((&input[..input.len() - rest.len()], rest))
+    }
+}
+
+/// An integer with an optional sign, converted with `FromStr`. A number that does not fit in
+/// `T` is a fatal error.
+pub fn integer<'a, T>() -> impl Parser<'a, T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    move |input: &'a str| {
+        let sign = usize::from(input.starts_with(['-', '+']));
+        let digits = input[sign..]
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(input.len() - sign);
+        if digits == 0 {
+            return Err(Failure::expected(input, "integer"));
+        }
+        let (text, rest) = input.split_at(sign + digits);
+        match text.parse() {
+            Ok(value) => Ok((value, rest)),
+            Err(err) => Err(Failure::fatal(input, format!("{:?}: {}", text, err))),
+        }
+    }
+}
+
+/// A letter or underscore followed by letters, digits and underscores.
+pub fn identifier<'a>() -> impl Parser<'a, &'a str> {
+    move |input: &'a str| {
+        let mut chars = input.chars();
+        match chars.next() {
+            Some(ch) if ch.is_alphabetic() || ch == '_' => {}
+            _ => return Err(Failure::expected(input, "identifier")),
+        }
+        let end = input
+            .find(|ch: char| !ch.is_alphanumeric() && ch != '_')
+            .unwrap_or(input.len());
+        Ok((&input[..end], &input[end..]))
+    }
+}
+
+/// `parser`, with any whitespace around it skipped.
+pub fn spaced<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, T> {
+    move |input: &'a str| {
+        let (value, rest) = parser.parse(input.trim_start())?;
+        Ok((value, rest.trim_start()))
+    }
+}
+
+/// Convert the output of `parser` with `f`.
+pub fn map<'a, T, U, F>(parser: impl Parser<'a, T>, f: F) -> impl Parser<'a, U>
+where
+    F: Fn(T) -> U,
+{
+    move |input: &'a str| {
+        let (value, rest) = parser.parse(input)?;
+        Ok((f(value), rest))
+    }
+}
+
+/// Convert the output of `parser` with `f`. An error from `f` is fatal, and located at the start
+/// of what `parser` matched.
+pub fn try_map<'a, T, U, F>(parser: impl Parser<'a, T>, f: F) -> impl Parser<'a, U>
+where
+    F: Fn(T) -> anyhow::Result<U>,
+{
+    move |input: &'a str| {
+        let (value, rest) = parser.parse(input)?;
+        match f(value) {
+            Ok(value) => Ok((value, rest)),
+            Err(err) => Err(Failure::fatal(input, format!("{:#}", err))),
+        }
+    }
+}
+
+/// `parser`, replacing its output with `value`.
+pub fn value<'a, T, U>(parser: impl Parser<'a, T>, value: U) -> impl Parser<'a, U>
+where
+    U: Clone,
+{
+    map(parser, move |_| value.clone())
+}
+
+/// `parser` if it matches, otherwise `None` and no input consumed.
+pub fn optional<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Option<T>> {
+    move |input: &'a str| match parser.parse(input) {
+        Ok((value, rest)) => Ok((Some(value), rest)),
+        Err(failure) if failure.is_fatal() => Err(failure),
+        Err(_) => Ok((None, input)),
+    }
+}
+
+fn repeat<'a, T>(
+    parser: &impl Parser<'a, T>,
+    mut input: &'a str,
+    mut out: Vec<T>,
+) -> PResult<'a, Vec<T>> {
+    loop {
+        match parser.parse(input) {
+            Ok((value, rest)) => {
+                out.push(value);
+                if rest.len() == input.len() {
+                    // the parser matched nothing; it would match nothing forever
+                    return Ok((out, rest));
+                }
+                input = rest;
+            }
+            Err(failure) if failure.is_fatal() => return Err(failure),
+            Err(_) => return Ok((out, input)),
+        }
+    }
+}
+
+/// `parser` as many times as it matches, possibly zero.
+pub fn many0<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Vec<T>> {
+    move |input: &'a str| repeat(&parser, input, vec![])
+}
+
+/// `parser` as many times as it matches, at least once.
+pub fn many1<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Vec<T>> {
+    move |input: &'a str| {
+        let (first, rest) = parser.parse(input)?;
+        if rest.len() == input.len() {
+            return Ok((vec![first], rest));
+        }
+        repeat(&parser, rest, vec![first])
+    }
+}
+
+/// Zero or more `item`s separated by `separator`, without a trailing separator.
+pub fn separated<'a, T, S>(
+    item: impl Parser<'a, T>,
+    separator: impl Parser<'a, S>,
+) -> impl Parser<'a, Vec<T>> {
+    move |input: &'a str| {
+        let mut out = vec![];
+        let mut input = match item.parse(input) {
+            Ok((value, rest)) => {
+                out.push(value);
+                rest
+            }
+            Err(failure) if failure.is_fatal() => return Err(failure),
+            Err(_) => return Ok((out, input)),
+        };
+        loop {
+            let Ok((_, after_separator)) = separator.parse(input) else {
+                return Ok((out, input));
+            };
+            // after a separator, an item is required
+            let (value, rest) = item.parse(after_separator)?;
+            if rest.len() == input.len() {
+                // separator and item matched nothing; they would match nothing forever
+                return Ok((out, input));
+            }
+            out.push(value);
+            input = rest;
+        }
+    }
+}
+
+/// `first` followed by `second`.
+pub fn pair<'a, A, B>(
+    first: impl Parser<'a, A>,
+    second: impl Parser<'a, B>,
+) -> impl Parser<'a, (A, B)> {
+    move |input: &'a str| {
+        let (a, rest) = first.parse(input)?;
+        let (b, rest) = second.parse(rest)?;
+        Ok(((a, b), rest))
+    }
+}
+
+/// `prefix` followed by `parser`, keeping the output of `parser`.
+pub fn preceded<'a, P, T>(
+    prefix: impl Parser<'a, P>,
+    parser: impl Parser<'a, T>,
+) -> impl Parser<'a, T> {
+    map(pair(prefix, parser), |(_, value)| value)
+}
+
+/// `parser` followed by `suffix`, keeping the output of `parser`.
+pub fn terminated<'a, T, S>(
+    parser: impl Parser<'a, T>,
+    suffix: impl Parser<'a, S>,
+) -> impl Parser<'a, T> {
+    map(pair(parser, suffix), |(value, _)| value)
+}
+
+/// `open`, `parser`, `close`, keeping the output of `parser`.
+pub fn delimited<'a, O, T, C>(
+    open: impl Parser<'a, O>,
+    parser: impl Parser<'a, T>,
+    close: impl Parser<'a, C>,
+) -> impl Parser<'a, T> {
+    preceded(open, terminated(parser, close))
+}
+
+/// A tuple of parsers with the same output. See [`alt`].
+pub trait Alternatives<'a, T> {
+    fn parse_first(&self, input: &'a str) -> PResult<'a, T>;
+}
+
+macro_rules! impl_alternatives {
+    ($($p:ident $index:tt),+) => {
+        impl<'a, T, $($p),+> Alternatives<'a, T> for ($($p,)+)
+        where
+            $($p: Parser<'a, T>),+
+        {
+            fn parse_first(&self, input: &'a str) -> PResult<'a, T> {
+                let mut failure: Option<Failure> = None;
+                $(
+                    match self.$index.parse(input) {
+                        Ok(ok) => return Ok(ok),
+                        Err(f) if f.is_fatal() => return Err(f),
+                        Err(f) => {
+                            failure = Some(match failure {
+                                Some(previous) => previous.merge(f),
+                                None => f,
+                            });
+                        }
+                    }
+                )+
+                Err(failure.expect("at least one alternative"))
+            }
+        }
+    };
+}
+
+impl_alternatives!(P0 0);
+impl_alternatives!(P0 0, P1 1);
+impl_alternatives!(P0 0, P1 1, P2 2);
+impl_alternatives!(P0 0, P1 1, P2 2, P3 3);
+impl_alternatives!(P0 0, P1 1, P2 2, P3 3, P4 4);
+impl_alternatives!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5);
+impl_alternatives!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6);
+impl_alternatives!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6, P7 7);
+
+/// The first of a tuple of parsers that matches.
+pub fn alt<'a, T>(alternatives: impl Alternatives<'a, T>) -> impl Parser<'a, T> {
+    move |input: &'a str| alternatives.parse_first(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Tree {
+        Leaf(u32),
+        List(Vec<Tree>),
+    }
+
+    fn tree(input: &str) -> PResult<'_, Tree> {
+        alt((
+            map(integer(), Tree::Leaf),
+            map(
+                delimited(literal("["), separated(tree, literal(",")), literal("]")),
+                Tree::List,
+            ),
+        ))
+        .parse(input)
+    }
+
+    fn error(err: anyhow::Error) -> ParseError {
+        err.downcast::<ParseError>().unwrap()
+    }
+
+    #[test]
+    fn test_primitives() {
+        assert_eq!(literal("ab").parse("abc"), Ok(("ab", "c")));
+        assert_eq!(one_of("xy").parse("yz"), Ok(('y', "z")));
+        assert_eq!(integer::<i32>().parse("-12,3"), Ok((-12, ",3")));
+        assert_eq!(integer::<u8>().parse("+7"), Ok((7, "")));
+        assert_eq!(identifier().parse("_a1 b"), Ok(("_a1", " b")));
+        assert_eq!(whitespace().parse(" \n x"), Ok((" \n ", "x")));
+        assert_eq!(
+            take_while1("digits", |ch| ch.is_ascii_digit()).parse("12ab"),
+            Ok(("12", "ab"))
+        );
+        assert!(literal("ab").parse("b").is_err());
+        assert!(integer::<i32>().parse("-").is_err());
+        assert!(identifier().parse("1a").is_err());
+    }
+
+    #[test]
+    fn test_combinators() {
+        let p = pair(
+            identifier(),
+            preceded(spaced(literal("=")), integer::<i32>()),
+        );
+        assert_eq!(p.parse("x = 5;"), Ok((("x", 5), ";")));
+
+        let p = terminated(optional(literal("-")), literal(">"));
+        assert_eq!(p.parse("->"), Ok((Some("-"), "")));
+        assert_eq!(p.parse(">"), Ok((None, "")));
+
+        assert_eq!(many0(one_of("ab")).parse("abc"), Ok((vec!['a', 'b'], "c")));
+        assert_eq!(many0(one_of("ab")).parse("c"), Ok((vec![], "c")));
+        assert_eq!(many0(whitespace()).parse("x"), Ok((vec![""], "x")));
+        assert!(many1(one_of("ab")).parse("c").is_err());
+
+        let p = separated(integer::<u32>(), literal(","));
+        assert_eq!(p.parse("1,2,3;"), Ok((vec![1, 2, 3], ";")));
+        assert_eq!(p.parse(";"), Ok((vec![], ";")));
+        assert!(p.parse("1,2,;").is_err());
+
+        let p = separated(many0(one_of("a")), whitespace());
+        assert_eq!(p.parse("aa b"), Ok((vec![vec!['a', 'a'], vec![]], "b")));
+        assert_eq!(p.parse(""), Ok((vec![vec![]], "")));
+
+        let p = try_map(identifier(), |s| match s {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(anyhow::anyhow!("bad state {s}")),
+        });
+        assert_eq!(p.parse("on"), Ok((true, "")));
+
+        let p = alt((value(literal("se"), 1), value(literal("s"), 2)));
+        assert_eq!(p.parse("sw"), Ok((2, "w")));
+    }
+
+    #[test]
+    fn test_recursive() {
+        assert_eq!(
+            parse_all("[1,[2,[]],3]\n", tree).unwrap(),
+            Tree::List(vec![
+                Tree::Leaf(1),
+                Tree::List(vec![Tree::Leaf(2), Tree::List(vec![])]),
+                Tree::Leaf(3),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        // the alternative that got furthest is reported, with everything expected there
+        let err = error(parse_all("[1,[2,x]]", tree).unwrap_err());
+        assert_eq!((err.line, err.column), (1, 7));
+        assert_eq!(err.message, "expected one of integer, \"[\", found 'x'");
+
+        let err = error(parse_all("[1]]", tree).unwrap_err());
+        assert_eq!(err.column, 4);
+        assert_eq!(err.message, "expected end of input, found ']'");
+
+        let err = error(parse_all("[1", tree).unwrap_err());
+        assert_eq!(err.message, "expected \"]\", found end of input");
+
+        // fatal errors are not backtracked over
+        let err = error(parse_all("[1,300000000000]", tree).unwrap_err());
+        assert_eq!(err.column, 4);
+        assert_eq!(
+            err.message,
+            "\"300000000000\": number too large to fit in target type"
+        );
+
+        let p = try_map(identifier(), |s| {
+            anyhow::ensure!(s == "on", "bad state {s}");
+            Ok(true)
+        });
+        let err = error(parse_all("on\nmaybe", many0(spaced(p))).unwrap_err());
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(err.message, "bad state maybe");
+    }
+}
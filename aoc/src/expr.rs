@@ -0,0 +1,416 @@
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use rustc_hash::FxHashMap;
+
+use crate::linalg::Fraction;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Number(i128),
+    Ident(String),
+    Op(Op),
+    LParen,
+    RParen,
+}
+
+/// Split an expression into tokens. Identifiers start with a letter or underscore; whitespace is
+/// ignored.
+pub fn tokenize(s: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        let token = match ch {
+            _ if ch.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '0'..='9' => {
+                let mut digits = String::new();
+                while let Some(ch) = chars.next_if(|ch| ch.is_ascii_digit()) {
+                    digits.push(ch);
+                }
+                tokens.push(Token::Number(digits.parse()?));
+                continue;
+            }
+            _ if ch.is_alphabetic() || ch == '_' => {
+                let mut ident = String::new();
+                while let Some(ch) = chars.next_if(|ch| ch.is_alphanumeric() || *ch == '_') {
+                    ident.push(ch);
+                }
+                tokens.push(Token::Ident(ident));
+                continue;
+            }
+            _ => bail!("unexpected character '{}'", ch),
+        };
+        chars.next();
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Number(i128),
+    Var(String),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
+/// A Pratt parser for infix expressions. Binary operators are only accepted if they have been
+/// given a precedence; a higher precedence binds tighter.
+#[derive(Debug, Clone, Default)]
+pub struct Parser {
+    operators: FxHashMap<Op, (u8, Assoc)>,
+}
+
+// binding power of unary minus: tighter than any binary operator
+const PREFIX_BINDING_POWER: u16 = 1000;
+
+impl Parser {
+    pub fn new() -> Self {
+        Parser::default()
+    }
+
+    /// The usual rules: * and / before + and -, all left associative.
+    pub fn arithmetic() -> Self {
+        Parser::new()
+            .with_operator(Op::Add, 1, Assoc::Left)
+            .with_operator(Op::Sub, 1, Assoc::Left)
+            .with_operator(Op::Mul, 2, Assoc::Left)
+            .with_operator(Op::Div, 2, Assoc::Left)
+    }
+
+    pub fn with_operator(mut self, op: Op, precedence: u8, assoc: Assoc) -> Self {
+        self.operators.insert(op, (precedence, assoc));
+        self
+    }
+
+    pub fn parse(&self, s: &str) -> Result<Expr> {
+        let tokens = tokenize(s)?;
+        let mut pos = 0;
+        let expr = self.parse_expr(&tokens, &mut pos, 0)?;
+        ensure!(pos == tokens.len(), "unexpected token {:?}", tokens[pos]);
+        Ok(expr)
+    }
+
+    fn parse_expr(&self, tokens: &[Token], pos: &mut usize, min_bp: u16) -> Result<Expr> {
+        let token = tokens.get(*pos).context("unexpected end of expression")?;
+        *pos += 1;
+        let mut lhs = match token {
+            Token::Number(n) => Expr::Number(*n),
+            Token::Ident(name) => Expr::Var(name.clone()),
+            Token::Op(Op::Sub) => Expr::Neg(Box::new(self.parse_expr(
+                tokens,
+                pos,
+                PREFIX_BINDING_POWER,
+            )?)),
+            Token::LParen => {
+                let expr = self.parse_expr(tokens, pos, 0)?;
+                ensure!(
+                    tokens.get(*pos) == Some(&Token::RParen),
+                    "missing closing parenthesis"
+                );
+                *pos += 1;
+                expr
+            }
+            _ => bail!("unexpected token {:?}", token),
+        };
+        while let Some(token) = tokens.get(*pos) {
+            let op = match token {
+                Token::Op(op) => *op,
+                Token::RParen => break,
+                _ => bail!("unexpected token {:?}", token),
+            };
+            let (precedence, assoc) = self
+                .operators
+                .get(&op)
+                .ok_or_else(|| anyhow!("unsupported operator {:?}", op))?;
+            let p = *precedence as u16 * 2;
+            let (left_bp, right_bp) = match assoc {
+                Assoc::Left => (p, p + 1),
+                Assoc::Right => (p + 1, p),
+            };
+            if left_bp < min_bp {
+                break;
+            }
+            *pos += 1;
+            let rhs = self.parse_expr(tokens, pos, right_bp)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+}
+
+/// Types that expressions can be evaluated as. Arithmetic is checked: None means overflow,
+/// division by zero, or a value that cannot be represented.
+pub trait Value: Copy {
+    fn from_i128(n: i128) -> Option<Self>;
+    fn neg(self) -> Option<Self>;
+    fn apply(op: Op, a: Self, b: Self) -> Option<Self>;
+}
+
+macro_rules! impl_value {
+    ($($t:ty)*) => {
+        $(
+            impl Value for $t {
+                fn from_i128(n: i128) -> Option<Self> {
+                    n.try_into().ok()
+                }
+
+                fn neg(self) -> Option<Self> {
+                    (0 as $t).checked_sub(self)
+                }
+
+                fn apply(op: Op, a: Self, b: Self) -> Option<Self> {
+                    match op {
+                        Op::Add => a.checked_add(b),
+                        Op::Sub => a.checked_sub(b),
+                        Op::Mul => a.checked_mul(b),
+                        Op::Div => a.checked_div(b),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_value! { i32 i64 i128 u32 u64 u128 usize }
+
+impl Value for Fraction {
+    fn from_i128(n: i128) -> Option<Self> {
+        Some(n.into())
+    }
+
+    fn neg(self) -> Option<Self> {
+        Some(-self)
+    }
+
+    fn apply(op: Op, a: Self, b: Self) -> Option<Self> {
+        match op {
+            Op::Add => Some(a + b),
+            Op::Sub => Some(a - b),
+            Op::Mul => Some(a * b),
+            Op::Div if b.is_zero() => None,
+            Op::Div => Some(a / b),
+        }
+    }
+}
+
+// a * x + b
+#[derive(Debug, Clone, Copy)]
+struct Linear {
+    a: Fraction,
+    b: Fraction,
+}
+
+impl Expr {
+    /// Evaluate the expression, looking up the value of each variable with vars.
+    pub fn eval<T: Value>(&self, vars: &impl Fn(&str) -> Option<T>) -> Result<T> {
+        match self {
+            Expr::Number(n) => T::from_i128(*n).context("number out of range"),
+            Expr::Var(name) => vars(name).ok_or_else(|| anyhow!("unknown variable {}", name)),
+            Expr::Neg(e) => e.eval(vars)?.neg().context("negation out of range"),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(vars)?, b.eval(vars)?);
+                T::apply(*op, a, b).ok_or_else(|| anyhow!("{:?} failed", op))
+            }
+        }
+    }
+
+    /// Replace variables by other expressions; variables for which f returns None are kept.
+    pub fn substitute(&self, f: &mut impl FnMut(&str) -> Option<Expr>) -> Expr {
+        match self {
+            Expr::Number(_) => self.clone(),
+            Expr::Var(name) => f(name).unwrap_or_else(|| self.clone()),
+            Expr::Neg(e) => Expr::Neg(Box::new(e.substitute(f))),
+            Expr::Binary(op, a, b) => {
+                Expr::Binary(*op, Box::new(a.substitute(f)), Box::new(b.substitute(f)))
+            }
+        }
+    }
+
+    fn linear(&self, unknown: &str) -> Result<Linear> {
+        let constant = |b| Linear {
+            a: Fraction::ZERO,
+            b,
+        };
+        Ok(match self {
+            Expr::Number(n) => constant((*n).into()),
+            Expr::Var(name) if name == unknown => Linear {
+                a: Fraction::ONE,
+                b: Fraction::ZERO,
+            },
+            Expr::Var(name) => bail!("unknown variable {}", name),
+            Expr::Neg(e) => {
+                let e = e.linear(unknown)?;
+                Linear { a: -e.a, b: -e.b }
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let (l, r) = (lhs.linear(unknown)?, rhs.linear(unknown)?);
+                match op {
+                    Op::Add => Linear {
+                        a: l.a + r.a,
+                        b: l.b + r.b,
+                    },
+                    Op::Sub => Linear {
+                        a: l.a - r.a,
+                        b: l.b - r.b,
+                    },
+                    Op::Mul if l.a.is_zero() => Linear {
+                        a: l.b * r.a,
+                        b: l.b * r.b,
+                    },
+                    Op::Mul if r.a.is_zero() => Linear {
+                        a: l.a * r.b,
+                        b: l.b * r.b,
+                    },
+                    Op::Mul => bail!("{} appears in both factors: not linear", unknown),
+                    Op::Div if !r.a.is_zero() => {
+                        bail!("{} appears in divisor: not linear", unknown)
+                    }
+                    Op::Div if r.b.is_zero() => bail!("division by zero"),
+                    Op::Div => Linear {
+                        a: l.a / r.b,
+                        b: l.b / r.b,
+                    },
+                }
+            }
+        })
+    }
+}
+
+/// Solve lhs = rhs for the variable unknown, which may appear any number of times as long as the
+/// equation is linear in it. All other variables must already have been substituted.
+pub fn solve_linear(lhs: &Expr, rhs: &Expr, unknown: &str) -> Result<Fraction> {
+    let (l, r) = (lhs.linear(unknown)?, rhs.linear(unknown)?);
+    // l.a * x + l.b = r.a * x + r.b
+    let a = l.a - r.a;
+    ensure!(!a.is_zero(), "no unique solution");
+    Ok((r.b - l.b) / a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_vars<T>(_: &str) -> Option<T> {
+        None
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("2 * (x_1+10)").unwrap(),
+            vec![
+                Token::Number(2),
+                Token::Op(Op::Mul),
+                Token::LParen,
+                Token::Ident("x_1".to_string()),
+                Token::Op(Op::Add),
+                Token::Number(10),
+                Token::RParen,
+            ]
+        );
+        assert!(tokenize("1 % 2").is_err());
+    }
+
+    #[test]
+    fn test_precedence() {
+        let eval = |p: &Parser, s| p.parse(s).unwrap().eval(&no_vars::<i64>).unwrap();
+
+        let p = Parser::arithmetic();
+        assert_eq!(eval(&p, "1 + 2 * 3 - 4"), 3);
+        assert_eq!(eval(&p, "(1 + 2) * 3"), 9);
+        assert_eq!(eval(&p, "10 - 3 - 2"), 5);
+        assert_eq!(eval(&p, "-2 * -3"), 6);
+
+        // same precedence for everything: strictly left to right
+        let p = Parser::new()
+            .with_operator(Op::Add, 1, Assoc::Left)
+            .with_operator(Op::Mul, 1, Assoc::Left);
+        assert_eq!(eval(&p, "1 + 2 * 3 + 4 * 5 + 6"), 71);
+
+        // addition before multiplication
+        let p = Parser::new()
+            .with_operator(Op::Add, 2, Assoc::Left)
+            .with_operator(Op::Mul, 1, Assoc::Left);
+        assert_eq!(eval(&p, "1 + 2 * 3 + 4 * 5 + 6"), 231);
+
+        let p = Parser::new().with_operator(Op::Sub, 1, Assoc::Right);
+        assert_eq!(eval(&p, "10 - 3 - 2"), 9);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let p = Parser::arithmetic();
+        assert!(p.parse("1 +").is_err());
+        assert!(p.parse("(1 + 2").is_err());
+        assert!(p.parse("1 + 2)").is_err());
+        assert!(p.parse("1 2").is_err());
+        assert!(Parser::new().parse("1 + 2").is_err());
+    }
+
+    #[test]
+    fn test_eval() {
+        let e = Parser::arithmetic().parse("x * 2 + y / 3").unwrap();
+        let vars = |name: &str| match name {
+            "x" => Some(5),
+            "y" => Some(7),
+            _ => None,
+        };
+        assert_eq!(e.eval::<u64>(&vars).unwrap(), 12);
+        assert_eq!(
+            e.eval::<Fraction>(&|name| vars(name).map(Fraction::from))
+                .unwrap(),
+            Fraction::new(37, 3)
+        );
+        assert!(e.eval(&no_vars::<u64>).is_err());
+
+        let e = Parser::arithmetic().parse("1 - 2").unwrap();
+        assert!(e.eval(&no_vars::<u64>).is_err());
+        assert_eq!(e.eval(&no_vars::<i32>).unwrap(), -1);
+        let e = Parser::arithmetic().parse("1 / 0").unwrap();
+        assert!(e.eval(&no_vars::<i32>).is_err());
+        assert!(e.eval(&no_vars::<Fraction>).is_err());
+    }
+
+    #[test]
+    fn test_substitute() {
+        let p = Parser::arithmetic();
+        let e = p.parse("a + b").unwrap();
+        let e = e.substitute(&mut |name| (name == "a").then(|| p.parse("2 * c").unwrap()));
+        assert_eq!(e, p.parse("2 * c + b").unwrap());
+    }
+
+    #[test]
+    fn test_solve_linear() {
+        let p = Parser::arithmetic();
+        let solve = |lhs, rhs| solve_linear(&p.parse(lhs).unwrap(), &p.parse(rhs).unwrap(), "x");
+        assert_eq!(solve("(4 + 2 * (x - 3)) / 4", "150").unwrap(), 301.into());
+        assert_eq!(solve("x + x", "x + 3").unwrap(), 3.into());
+        assert_eq!(solve("3 * x", "1").unwrap(), Fraction::new(1, 3));
+        assert!(solve("x * x", "4").is_err());
+        assert!(solve("1 / x", "4").is_err());
+        assert!(solve("x + 1", "x").is_err());
+        assert!(solve("x + y", "1").is_err());
+    }
+}
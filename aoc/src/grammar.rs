@@ -0,0 +1,470 @@
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Symbol {
+    Terminal(String),
+    Rule(usize),
+}
+
+/// A context-free grammar, written as one rule per line:
+///
+/// ```text
+/// 0: 1 2 | 2 1
+/// 1: "a"
+/// 2: "b" | "b" 2
+/// ```
+///
+/// Rule names are any words without whitespace, `:`, `|` or `"`. Terminals are quoted strings.
+/// An alternative may be empty. Rules may be recursive, including left recursive.
+#[derive(Debug, Clone, Default)]
+pub struct Grammar {
+    names: Vec<String>,
+    ids: FxHashMap<String, usize>,
+    // None if the rule is referenced but not (yet) defined
+    rules: Vec<Option<Vec<Vec<Symbol>>>>,
+}
+
+// An Earley item: rule, alternative, position within the alternative, and where in the input
+// the item started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Item {
+    rule: usize,
+    alt: usize,
+    dot: usize,
+    origin: usize,
+}
+
+impl Grammar {
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut grammar = Grammar::default();
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            grammar.define(line)?;
+        }
+        grammar.check()?;
+        Ok(grammar)
+    }
+
+    /// Add a rule, or replace an existing rule with the same name. On error, the grammar is left
+    /// unchanged.
+    pub fn set_rule(&mut self, line: &str) -> Result<()> {
+        let backup = self.clone();
+        let result = self.define(line).and_then(|_| self.check());
+        if result.is_err() {
+            *self = backup;
+        }
+        result
+    }
+
+    fn id(&mut self, name: &str) -> usize {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.rules.push(None);
+        id
+    }
+
+    fn define(&mut self, line: &str) -> Result<()> {
+        let (name, body) = line
+            .split_once(':')
+            .ok_or_else(|| anyhow!("missing ':' in rule '{}'", line))?;
+        let name = name.trim();
+        ensure!(is_name(name), "bad rule name '{}'", name);
+        let id = self.id(name);
+
+        let mut alternatives = vec![vec![]];
+        let mut chars = body.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                _ if ch.is_whitespace() => {}
+                '|' => alternatives.push(vec![]),
+                '"' => {
+                    let mut terminal = String::new();
+                    let mut terminated = false;
+                    for ch in chars.by_ref() {
+                        if ch == '"' {
+                            terminated = true;
+                            break;
+                        }
+                        terminal.push(ch);
+                    }
+                    ensure!(terminated, "unterminated terminal in rule '{}'", line);
+                    ensure!(!terminal.is_empty(), "empty terminal in rule '{}'", line);
+                    alternatives
+                        .last_mut()
+                        .unwrap()
+                        .push(Symbol::Terminal(terminal));
+                }
+                _ => {
+                    let mut word = String::from(ch);
+                    while let Some(ch) = chars.next_if(|ch| is_name_char(*ch)) {
+                        word.push(ch);
+                    }
+                    ensure!(is_name(&word), "bad rule name '{}' in '{}'", word, line);
+                    let symbol = Symbol::Rule(self.id(&word));
+                    alternatives.last_mut().unwrap().push(symbol);
+                }
+            }
+        }
+        self.rules[id] = Some(alternatives);
+        Ok(())
+    }
+
+    fn check(&self) -> Result<()> {
+        for (name, rule) in self.names.iter().zip(self.rules.iter()) {
+            ensure!(rule.is_some(), "rule '{}' is not defined", name);
+        }
+        Ok(())
+    }
+
+    fn start(&self, name: &str) -> Result<usize> {
+        self.ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow!("unknown rule '{}'", name))
+    }
+
+    fn alternatives(&self, rule: usize) -> &[Vec<Symbol>] {
+        self.rules[rule]
+            .as_deref()
+            .expect("rules checked on creation")
+    }
+
+    // Rules that can match the empty string
+    fn nullable(&self) -> Vec<bool> {
+        let mut nullable = vec![false; self.rules.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in 0..self.rules.len() {
+                if nullable[rule] {
+                    continue;
+                }
+                if self.alternatives(rule).iter().any(|alt| {
+                    alt.iter()
+                        .all(|sym| matches!(sym, Symbol::Rule(r) if nullable[*r]))
+                }) {
+                    nullable[rule] = true;
+                    changed = true;
+                }
+            }
+        }
+        nullable
+    }
+
+    /// Does the rule `start` match all of `input`? Uses an Earley parser, so any grammar works.
+    pub fn matches(&self, start: &str, input: &str) -> Result<bool> {
+        let start = self.start(start)?;
+        let input = input.as_bytes();
+        let nullable = self.nullable();
+        let mut chart: Vec<Vec<Item>> = vec![vec![]; input.len() + 1];
+        let mut seen: Vec<FxHashSet<Item>> = vec![FxHashSet::default(); input.len() + 1];
+        let mut add = |chart: &mut Vec<Vec<Item>>, pos: usize, item: Item| {
+            if seen[pos].insert(item) {
+                chart[pos].push(item);
+            }
+        };
+
+        for alt in 0..self.alternatives(start).len() {
+            let item = Item {
+                rule: start,
+                alt,
+                dot: 0,
+                origin: 0,
+            };
+            add(&mut chart, 0, item);
+        }
+        for pos in 0..=input.len() {
+            let mut i = 0;
+            while i < chart[pos].len() {
+                let item = chart[pos][i];
+                i += 1;
+                match self.alternatives(item.rule)[item.alt].get(item.dot) {
+                    // predict
+                    Some(Symbol::Rule(rule)) => {
+                        for alt in 0..self.alternatives(*rule).len() {
+                            let new = Item {
+                                rule: *rule,
+                                alt,
+                                dot: 0,
+                                origin: pos,
+                            };
+                            add(&mut chart, pos, new);
+                        }
+                        if nullable[*rule] {
+                            let new = Item {
+                                dot: item.dot + 1,
+                                ..item
+                            };
+                            add(&mut chart, pos, new);
+                        }
+                    }
+                    // scan
+                    Some(Symbol::Terminal(terminal)) => {
+                        if input[pos..].starts_with(terminal.as_bytes()) {
+                            let new = Item {
+                                dot: item.dot + 1,
+                                ..item
+                            };
+                            add(&mut chart, pos + terminal.len(), new);
+                        }
+                    }
+                    // complete
+                    None => {
+                        let mut j = 0;
+                        while j < chart[item.origin].len() {
+                            let parent = chart[item.origin][j];
+                            j += 1;
+                            let symbols = &self.alternatives(parent.rule)[parent.alt];
+                            if symbols.get(parent.dot) == Some(&Symbol::Rule(item.rule)) {
+                                let new = Item {
+                                    dot: parent.dot + 1,
+                                    ..parent
+                                };
+                                add(&mut chart, pos, new);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(chart[input.len()].iter().any(|item| {
+            item.rule == start
+                && item.origin == 0
+                && item.dot == self.alternatives(start)[item.alt].len()
+        }))
+    }
+
+    /// Number of distinct parse trees for `input`, starting from the rule `start`. Fails if there
+    /// are infinitely many.
+    pub fn count_derivations(&self, start: &str, input: &str) -> Result<u64> {
+        let start = self.start(start)?;
+        let mut counter = Counter {
+            grammar: self,
+            input: input.as_bytes(),
+            rules: FxHashMap::default(),
+            sequences: FxHashMap::default(),
+        };
+        counter.rule(start, 0, input.len())
+    }
+
+    /// All strings the rule `start` can produce, sorted and without duplicates. Fails if the rule
+    /// is recursive.
+    pub fn language(&self, start: &str) -> Result<Vec<String>> {
+        let start = self.start(start)?;
+        let mut cache = FxHashMap::default();
+        let strings = self.language_inner(start, &mut cache)?;
+        Ok(strings.into_iter().collect())
+    }
+
+    fn language_inner(
+        &self,
+        rule: usize,
+        cache: &mut FxHashMap<usize, Option<BTreeSet<String>>>,
+    ) -> Result<BTreeSet<String>> {
+        match cache.get(&rule) {
+            Some(Some(strings)) => return Ok(strings.clone()),
+            Some(None) => bail!("rule '{}' is recursive", self.names[rule]),
+            None => {}
+        }
+        cache.insert(rule, None);
+        let mut strings = BTreeSet::new();
+        for alt in self.alternatives(rule) {
+            let mut prefixes = BTreeSet::from([String::new()]);
+            for symbol in alt {
+                let suffixes = match symbol {
+                    Symbol::Terminal(terminal) => BTreeSet::from([terminal.clone()]),
+                    Symbol::Rule(r) => self.language_inner(*r, cache)?,
+                };
+                prefixes = prefixes
+                    .iter()
+                    .flat_map(|p| suffixes.iter().map(move |s| format!("{}{}", p, s)))
+                    .collect();
+            }
+            strings.extend(prefixes);
+        }
+        cache.insert(rule, Some(strings.clone()));
+        Ok(strings)
+    }
+}
+
+fn is_name_char(ch: char) -> bool {
+    !ch.is_whitespace() && !matches!(ch, ':' | '|' | '"')
+}
+
+fn is_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(is_name_char)
+}
+
+// Counts derivations of input[i..j], memoized per span. A None in the cache marks a span that is
+// being counted: running into it again means the grammar can loop without consuming input.
+struct Counter<'a> {
+    grammar: &'a Grammar,
+    input: &'a [u8],
+    rules: FxHashMap<(usize, usize, usize), Option<u64>>,
+    sequences: FxHashMap<(usize, usize, usize, usize, usize), u64>,
+}
+
+impl Counter<'_> {
+    fn rule(&mut self, rule: usize, i: usize, j: usize) -> Result<u64> {
+        match self.rules.get(&(rule, i, j)) {
+            Some(Some(count)) => return Ok(*count),
+            Some(None) => bail!(
+                "infinitely many derivations: rule '{}' derives itself",
+                self.grammar.names[rule]
+            ),
+            None => {}
+        }
+        self.rules.insert((rule, i, j), None);
+        let mut count: u64 = 0;
+        for alt in 0..self.grammar.alternatives(rule).len() {
+            let n = self.sequence(rule, alt, 0, i, j)?;
+            count = count.checked_add(n).context("too many derivations")?;
+        }
+        self.rules.insert((rule, i, j), Some(count));
+        Ok(count)
+    }
+
+    // Derivations of input[i..j] from the symbols alt[dot..] of a rule
+    fn sequence(&mut self, rule: usize, alt: usize, dot: usize, i: usize, j: usize) -> Result<u64> {
+        let key = (rule, alt, dot, i, j);
+        if let Some(count) = self.sequences.get(&key) {
+            return Ok(*count);
+        }
+        let grammar = self.grammar;
+        let count = match grammar.alternatives(rule)[alt].get(dot) {
+            None => u64::from(i == j),
+            Some(Symbol::Terminal(terminal)) => {
+                let end = i + terminal.len();
+                if end <= j && self.input[i..end] == *terminal.as_bytes() {
+                    self.sequence(rule, alt, dot + 1, end, j)?
+                } else {
+                    0
+                }
+            }
+            Some(Symbol::Rule(r)) => {
+                let mut count: u64 = 0;
+                for k in i..=j {
+                    // count the rest first: if it cannot match, there is no need to descend
+                    // into r, which avoids looping on left recursive rules
+                    let rest = self.sequence(rule, alt, dot + 1, k, j)?;
+                    if rest == 0 {
+                        continue;
+                    }
+                    let n = self.rule(*r, i, k)?;
+                    count = n
+                        .checked_mul(rest)
+                        .and_then(|n| count.checked_add(n))
+                        .context("too many derivations")?;
+                }
+                count
+            }
+        };
+        self.sequences.insert(key, count);
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAMMAR: &str = r#"
+0: 4 1 5
+1: 2 3 | 3 2
+2: 4 4 | 5 5
+3: 4 5 | 5 4
+4: "a"
+5: "b"
+"#;
+
+    #[test]
+    fn test_matches() {
+        let g = Grammar::parse(GRAMMAR).unwrap();
+        assert!(g.matches("0", "ababbb").unwrap());
+        assert!(g.matches("0", "abbbab").unwrap());
+        assert!(!g.matches("0", "bababa").unwrap());
+        assert!(!g.matches("0", "aaabbb").unwrap());
+        assert!(!g.matches("0", "aaaabbb").unwrap());
+        assert!(!g.matches("0", "").unwrap());
+        assert!(g.matches("6", "a").is_err());
+    }
+
+    #[test]
+    fn test_recursive_rules() {
+        // balanced parentheses, left recursive list of words
+        let g = Grammar::parse(
+            r#"
+parens: | "(" parens ")" parens
+list: list "," word | word
+word: "foo" | "bar"
+"#,
+        )
+        .unwrap();
+        assert!(g.matches("parens", "").unwrap());
+        assert!(g.matches("parens", "(()())()").unwrap());
+        assert!(!g.matches("parens", "(()").unwrap());
+        assert!(g.matches("list", "foo,bar,foo").unwrap());
+        assert!(!g.matches("list", "foo,").unwrap());
+    }
+
+    #[test]
+    fn test_set_rule() {
+        let mut g = Grammar::parse(GRAMMAR).unwrap();
+        assert!(!g.matches("1", "aaaa").unwrap());
+        g.set_rule("1: 2 | 2 1").unwrap();
+        assert!(g.matches("1", "aaaa").unwrap());
+        assert!(g.matches("1", "aabbaa").unwrap());
+        assert!(g.set_rule("1: 2 7").is_err());
+        assert!(g.set_rule("1: 2 \"").is_err());
+        assert!(g.matches("1", "aabbaa").unwrap());
+        assert!(!g.matches("1", "aab").unwrap());
+        assert!(g.matches("7", "a").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Grammar::parse("0 1 2").is_err());
+        assert!(Grammar::parse("0: 1").is_err());
+        assert!(Grammar::parse(r#"0: """#).is_err());
+        assert!(Grammar::parse(r#": "a""#).is_err());
+        let err = Grammar::parse(r#"0: "abc"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"unterminated terminal in rule '0: "abc'"#
+        );
+    }
+
+    #[test]
+    fn test_count_derivations() {
+        let g = Grammar::parse(GRAMMAR).unwrap();
+        assert_eq!(g.count_derivations("0", "ababbb").unwrap(), 1);
+        assert_eq!(g.count_derivations("0", "bababa").unwrap(), 0);
+
+        // sums of ones: the number of ways to bracket n terms is a Catalan number
+        let g = Grammar::parse(r#"e: e "+" e | "1""#).unwrap();
+        assert_eq!(g.count_derivations("e", "1").unwrap(), 1);
+        assert_eq!(g.count_derivations("e", "1+1+1").unwrap(), 2);
+        assert_eq!(g.count_derivations("e", "1+1+1+1+1").unwrap(), 14);
+
+        let g = Grammar::parse(r#"a: a | "x""#).unwrap();
+        assert!(g.matches("a", "x").unwrap());
+        assert!(g.count_derivations("a", "x").is_err());
+    }
+
+    #[test]
+    fn test_language() {
+        let g = Grammar::parse(GRAMMAR).unwrap();
+        assert_eq!(g.language("2").unwrap(), vec!["aa", "bb"]);
+        assert_eq!(g.language("0").unwrap().len(), 8);
+        assert!(g.language("0").unwrap().contains(&"ababbb".to_string()));
+
+        let g = Grammar::parse(r#"a: "x" | "x" a"#).unwrap();
+        assert!(g.language("a").is_err());
+    }
+}
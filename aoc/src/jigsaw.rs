@@ -0,0 +1,350 @@
+use anyhow::{bail, ensure, Result};
+use rustc_hash::FxHashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+
+use crate::{Direction, Orientation};
+
+/// A square tile of characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tile {
+    side: usize,
+    data: Vec<char>,
+}
+
+impl Tile {
+    pub fn parse(s: &str) -> Result<Self> {
+        let lines: Vec<&str> = s.lines().collect();
+        let side = lines.len();
+        ensure!(side > 0, "empty tile");
+        let mut data = Vec::with_capacity(side * side);
+        for line in lines {
+            ensure!(line.chars().count() == side, "tile is not square");
+            data.extend(line.chars());
+        }
+        Ok(Tile { side, data })
+    }
+
+    pub fn side(&self) -> usize {
+        self.side
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> char {
+        self.data[y * self.side + x]
+    }
+
+    pub fn reoriented(&self, orientation: Orientation) -> Tile {
+        let side = self.side as i32;
+        let mut data = vec![' '; self.data.len()];
+        for (i, ch) in self.data.iter().enumerate() {
+            let xy = orientation.apply(
+                ((i % self.side) as i32, (i / self.side) as i32).into(),
+                side,
+                side,
+            );
+            data[xy.y as usize * self.side + xy.x as usize] = *ch;
+        }
+        Tile {
+            side: self.side,
+            data,
+        }
+    }
+
+    /// The characters along one edge, read left to right (north and south edges) or top to bottom
+    /// (east and west edges).
+    pub fn edge(&self, dir: Direction) -> String {
+        let last = self.side - 1;
+        (0..self.side)
+            .map(|i| match dir {
+                Direction::North => self.get(i, 0),
+                Direction::East => self.get(last, i),
+                Direction::South => self.get(i, last),
+                Direction::West => self.get(0, i),
+            })
+            .collect()
+    }
+
+    /// The tile without its outermost rows and columns. Tiles of side 2 or less have no interior.
+    pub fn interior(&self) -> Result<Tile> {
+        ensure!(self.side > 2, "{0}x{0} tile has no interior", self.side);
+        let side = self.side - 2;
+        let data = (1..=side)
+            .flat_map(|y| (1..=side).map(move |x| (x, y)))
+            .map(|(x, y)| self.get(x, y))
+            .collect();
+        Ok(Tile { side, data })
+    }
+}
+
+impl Display for Tile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, row) in self.data.chunks(self.side).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", row.iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+/// An edge's signature does not depend on which way the edge is read, so two tiles that fit
+/// together (after flipping one of them, if needed) have an edge with the same signature.
+pub fn edge_signature(edge: &str) -> String {
+    let reversed: String = edge.chars().rev().collect();
+    reversed.min(edge.to_string())
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+/// A square jigsaw puzzle of square tiles, where neighbouring tiles have identical edges.
+pub struct Jigsaw<Id> {
+    pieces: Vec<(Id, Tile)>,
+    // [piece][orientation][direction]
+    edges: Vec<[[String; 4]; 8]>,
+    signature_counts: FxHashMap<String, usize>,
+    size: usize,
+}
+
+impl<Id: Copy + Eq + Hash> Jigsaw<Id> {
+    pub fn new(pieces: Vec<(Id, Tile)>) -> Result<Self> {
+        let size = (pieces.len() as f64).sqrt() as usize;
+        ensure!(
+            size * size == pieces.len(),
+            "number of pieces is not a square"
+        );
+        ensure!(size > 0, "no pieces");
+        let side = pieces[0].1.side();
+        ensure!(
+            pieces.iter().all(|(_, tile)| tile.side() == side),
+            "tiles differ in size"
+        );
+        let edges: Vec<_> = pieces
+            .iter()
+            .map(|(_, tile)| {
+                Orientation::ALL.map(|o| {
+                    let tile = tile.reoriented(o);
+                    DIRECTIONS.map(|dir| tile.edge(dir))
+                })
+            })
+            .collect();
+        let mut signature_counts = FxHashMap::default();
+        for e in edges.iter() {
+            for edge in e[0].iter() {
+                *signature_counts.entry(edge_signature(edge)).or_default() += 1;
+            }
+        }
+        Ok(Jigsaw {
+            pieces,
+            edges,
+            signature_counts,
+            size,
+        })
+    }
+
+    /// Number of pieces along each side of the assembled puzzle.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn is_unmatched(&self, edge: &str) -> bool {
+        self.signature_counts[&edge_signature(edge)] == 1
+    }
+
+    /// Number of edges of a piece that do not fit any other piece.
+    pub fn unmatched_edges(&self, id: Id) -> Option<usize> {
+        let i = self.pieces.iter().position(|(other, _)| *other == id)?;
+        Some(
+            self.edges[i][0]
+                .iter()
+                .filter(|edge| self.is_unmatched(edge))
+                .count(),
+        )
+    }
+
+    fn pieces_with_unmatched_edges(&self, count: usize) -> Vec<Id> {
+        self.pieces
+            .iter()
+            .filter(|(id, _)| self.unmatched_edges(*id) == Some(count))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Pieces with two unmatched edges. Assumes edges only match the edges they are placed next to.
+    pub fn corners(&self) -> Vec<Id> {
+        self.pieces_with_unmatched_edges(2)
+    }
+
+    /// Pieces along the border, excluding the corners.
+    pub fn border_pieces(&self) -> Vec<Id> {
+        self.pieces_with_unmatched_edges(1)
+    }
+
+    /// Assemble the puzzle. Returns the pieces row by row, each with the orientation it was placed
+    /// in. Pieces are tried in order, so the first corner that fits ends up in the top left.
+    pub fn solve(&self) -> Option<Vec<Vec<(Id, Orientation)>>> {
+        let mut placed = vec![];
+        let mut used = vec![false; self.pieces.len()];
+        if !self.solve_inner(&mut placed, &mut used) {
+            return None;
+        }
+        Some(
+            placed
+                .chunks(self.size)
+                .map(|row| {
+                    row.iter()
+                        .map(|(i, o)| (self.pieces[*i].0, Orientation::ALL[*o]))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    fn solve_inner(&self, placed: &mut Vec<(usize, usize)>, used: &mut [bool]) -> bool {
+        let index = placed.len();
+        if index == self.pieces.len() {
+            return true;
+        }
+        let (x, y) = (index % self.size, index / self.size);
+        let edge = |(i, o): (usize, usize), dir: usize| &self.edges[i][o][dir];
+        for i in 0..self.pieces.len() {
+            if used[i] {
+                continue;
+            }
+            for o in 0..Orientation::ALL.len() {
+                let [north, _, _, west] = &self.edges[i][o];
+                let fits_west = if x == 0 {
+                    self.is_unmatched(west)
+                } else {
+                    edge(placed[index - 1], 1) == west
+                };
+                let fits_north = if y == 0 {
+                    self.is_unmatched(north)
+                } else {
+                    edge(placed[index - self.size], 2) == north
+                };
+                if !fits_west || !fits_north {
+                    continue;
+                }
+                used[i] = true;
+                placed.push((i, o));
+                if self.solve_inner(placed, used) {
+                    return true;
+                }
+                placed.pop();
+                used[i] = false;
+            }
+        }
+        false
+    }
+
+    /// Combine the interiors of the placed pieces into one image.
+    pub fn stitch(&self, solution: &[Vec<(Id, Orientation)>]) -> Result<Tile> {
+        let mut rows: Vec<String> = vec![];
+        for solution_row in solution {
+            let mut interiors = vec![];
+            for (id, o) in solution_row {
+                let Some((_, tile)) = self.pieces.iter().find(|(other, _)| other == id) else {
+                    bail!("unknown piece");
+                };
+                interiors.push(tile.reoriented(*o).interior()?.to_string());
+            }
+            let interior_side = interiors.first().map_or(0, |s| s.lines().count());
+            for y in 0..interior_side {
+                rows.push(
+                    interiors
+                        .iter()
+                        .map(|s| s.lines().nth(y).expect("square tile"))
+                        .collect(),
+                );
+            }
+        }
+        Tile::parse(&rows.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile() {
+        let t = Tile::parse("####.\n#...#\n####.\n#.#..\n#..#.").unwrap();
+        assert_eq!(t.edge(Direction::North), "####.");
+        assert_eq!(t.edge(Direction::East), ".#...");
+        assert_eq!(t.edge(Direction::South), "#..#.");
+        assert_eq!(t.edge(Direction::West), "#####");
+        assert_eq!(t.interior().unwrap().to_string(), "...\n###\n.#.");
+        assert!(Tile::parse("##\n##").unwrap().interior().is_err());
+        assert!(Tile::parse("#").unwrap().interior().is_err());
+
+        let t = t.reoriented(Orientation::F270);
+        assert_eq!(t.edge(Direction::North), "#####");
+        assert_eq!(t.edge(Direction::East), "#..#.");
+        assert_eq!(t.edge(Direction::South), ".#...");
+        assert_eq!(t.edge(Direction::West), "####.");
+
+        assert!(Tile::parse("##\n#").is_err());
+    }
+
+    #[test]
+    fn test_edge_signature() {
+        assert_eq!(edge_signature("#.."), edge_signature("..#"));
+        assert_ne!(edge_signature("#.."), edge_signature(".#."));
+    }
+
+    // A 6x6 image cut into four 5x5 tiles: each tile has a 3x3 interior and a 1 cell wide border
+    // that is identical to its neighbours' borders.
+    const IMAGE: &str = "xoooox\noxoxoo\nooxoxx\nxxxoox\nooxxxo\noxxxxo";
+
+    fn pieces() -> Vec<(u32, Tile)> {
+        let tiles = [
+            (1, ".#.##\n.xoo.\n.oxo.\n#oox#\n.####", Orientation::R0),
+            (2, "#.#.#\n.oox.\n.xoo.\n#oxx.\n###.#", Orientation::R90),
+            (3, ".####\n#xxx.\n.oox#\n.oxx#\n###..", Orientation::R0),
+            (4, "###.#\n.oox#\n#xxo.\n#xxo#\n..#.#", Orientation::F180),
+        ];
+        // mix up and rotate the pieces a bit
+        [tiles[3], tiles[1], tiles[0], tiles[2]]
+            .iter()
+            .map(|(id, s, o)| (*id, Tile::parse(s).unwrap().reoriented(*o)))
+            .collect()
+    }
+
+    #[test]
+    fn test_corners() {
+        let jigsaw = Jigsaw::new(pieces()).unwrap();
+        assert_eq!(jigsaw.size(), 2);
+        let mut corners = jigsaw.corners();
+        corners.sort();
+        assert_eq!(corners, vec![1, 2, 3, 4]);
+        assert!(jigsaw.border_pieces().is_empty());
+        assert_eq!(jigsaw.unmatched_edges(1), Some(2));
+        assert_eq!(jigsaw.unmatched_edges(5), None);
+    }
+
+    #[test]
+    fn test_solve_and_stitch() {
+        let jigsaw = Jigsaw::new(pieces()).unwrap();
+        let solution = jigsaw.solve().unwrap();
+        let image = jigsaw.stitch(&solution).unwrap();
+        // the puzzle can be solved in any orientation
+        let found = Orientation::ALL
+            .iter()
+            .any(|o| image.reoriented(*o).to_string() == IMAGE);
+        assert!(found);
+    }
+
+    #[test]
+    fn test_new_errors() {
+        let mut p = pieces();
+        p.pop();
+        assert!(Jigsaw::new(p).is_err());
+        assert!(Jigsaw::<u32>::new(vec![]).is_err());
+    }
+}
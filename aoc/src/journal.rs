@@ -0,0 +1,259 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+
+/// What the site said about a submitted answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Correct,
+    TooHigh,
+    TooLow,
+    Wrong,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Verdict::Correct => "correct",
+            Verdict::TooHigh => "too-high",
+            Verdict::TooLow => "too-low",
+            Verdict::Wrong => "wrong",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for Verdict {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "correct" => Ok(Verdict::Correct),
+            "too-high" => Ok(Verdict::TooHigh),
+            "too-low" => Ok(Verdict::TooLow),
+            "wrong" => Ok(Verdict::Wrong),
+            _ => bail!("{}: unknown verdict", s),
+        }
+    }
+}
+
+/// One submitted answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub part: u32,
+    pub answer: String,
+    pub verdict: Verdict,
+}
+
+/// What the journal knows about an answer that has not been submitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    /// The answer is the accepted one.
+    Correct,
+    /// Nothing rules the answer out.
+    Unknown,
+    /// The answer is wrong, for the given reason.
+    Wrong(String),
+}
+
+/// Every answer submitted for a puzzle and the verdict on it, kept in `journal.txt` next to the
+/// puzzle's `Cargo.toml`, one `<part> <answer> <verdict>` per line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Journal {
+    entries: Vec<Entry>,
+}
+
+impl Journal {
+    /// Read the journal at `path`; a missing file is an empty journal.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(s) => s.parse().with_context(|| format!("{}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Journal::default()),
+            Err(err) => Err(anyhow!("{}: {}", path.display(), err)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_string())
+            .with_context(|| format!("{}: failed to write", path.display()))
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn record(&mut self, part: u32, answer: &str, verdict: Verdict) {
+        self.entries.push(Entry {
+            part,
+            answer: answer.to_string(),
+            verdict,
+        });
+    }
+
+    /// The accepted answer to `part`, if any.
+    pub fn correct(&self, part: u32) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|e| e.part == part && e.verdict == Verdict::Correct)
+            .map(|e| e.answer.as_str())
+    }
+
+    /// The range a numeric answer to `part` must be in, from the answers that were too low or
+    /// too high: both bounds are exclusive.
+    pub fn bounds(&self, part: u32) -> (Option<i128>, Option<i128>) {
+        let mut low = None;
+        let mut high = None;
+        for e in self.entries.iter().filter(|e| e.part == part) {
+            let Ok(value) = e.answer.parse::<i128>() else {
+                continue;
+            };
+            match e.verdict {
+                Verdict::TooLow => low = low.max(Some(value)),
+                Verdict::TooHigh => high = Some(high.map_or(value, |h: i128| h.min(value))),
+                _ => {}
+            }
+        }
+        (low, high)
+    }
+
+    /// What is known about `answer` to `part`.
+    pub fn check(&self, part: u32, answer: &str) -> Check {
+        if let Some(correct) = self.correct(part) {
+            return if correct == answer {
+                Check::Correct
+            } else {
+                Check::Wrong(format!("the accepted answer is {}", correct))
+            };
+        }
+        if let Some(e) = self
+            .entries
+            .iter()
+            .find(|e| e.part == part && e.answer == answer)
+        {
+            return Check::Wrong(format!("already submitted: {}", e.verdict));
+        }
+        if let Ok(value) = answer.parse::<i128>() {
+            match self.bounds(part) {
+                (Some(low), _) if value <= low => {
+                    return Check::Wrong(format!("too low: {} was too low", low));
+                }
+                (_, Some(high)) if value >= high => {
+                    return Check::Wrong(format!("too high: {} was too high", high));
+                }
+                _ => {}
+            }
+        }
+        Check::Unknown
+    }
+}
+
+/// The part a `run!` expression solves, from the name of the function it calls: `part_one` and
+/// variants like `part_one_fast` are part 1, and likewise for `part_two`. None if the expression
+/// mentions neither, or both.
+pub fn part_of(expr: &str) -> Option<u32> {
+    let mut parts = expr
+        .split(|ch: char| !ch.is_alphanumeric() && ch != '_')
+        .filter_map(|word| {
+            [("part_one", 1), ("part_two", 2)]
+                .into_iter()
+                .find(|(name, _)| {
+                    word.strip_prefix(name)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('_'))
+                })
+                .map(|(_, part)| part)
+        });
+    let part = parts.next()?;
+    parts.all(|other| other == part).then_some(part)
+}
+
+impl FromStr for Journal {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut entries = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = (|| {
+                let (part, rest) = line.split_once(' ').context("missing answer")?;
+                let (answer, verdict) = rest.rsplit_once(' ').context("missing verdict")?;
+                Ok::<_, anyhow::Error>(Entry {
+                    part: part.parse().context("bad part")?,
+                    answer: answer.to_string(),
+                    verdict: verdict.parse()?,
+                })
+            })()
+            .with_context(|| format!("line {}: {}", i + 1, line))?;
+            entries.push(entry);
+        }
+        Ok(Journal { entries })
+    }
+}
+
+impl Display for Journal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# <part> <answer> <verdict>")?;
+        for e in self.entries.iter() {
+            writeln!(f, "{} {} {}", e.part, e.answer, e.verdict)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JOURNAL: &str = "# <part> <answer> <verdict>
+1 300 too-high
+1 200 too-low
+1 250 too-high
+1 220 wrong
+2 ABC wrong
+";
+
+    #[test]
+    fn test_parse_and_display() {
+        let journal: Journal = JOURNAL.parse().unwrap();
+        assert_eq!(journal.entries().len(), 5);
+        assert_eq!(journal.entries()[4].answer, "ABC");
+        assert_eq!(journal.to_string(), JOURNAL);
+        assert!("1 200".parse::<Journal>().is_err());
+        assert!("x 200 wrong".parse::<Journal>().is_err());
+        assert!("1 200 maybe".parse::<Journal>().is_err());
+    }
+
+    #[test]
+    fn test_part_of() {
+        assert_eq!(part_of("part_one(input)"), Some(1));
+        assert_eq!(part_of("part_two(input, 25)"), Some(2));
+        assert_eq!(
+            part_of("aoc::variants!(input, part_two_naive, part_two_fast)"),
+            Some(2)
+        );
+        assert_eq!(part_of("solve(input)"), None);
+        assert_eq!(part_of("part_ones(input)"), None);
+        assert_eq!(part_of("both(part_one(input), part_two(input))"), None);
+    }
+
+    #[test]
+    fn test_check() {
+        let mut journal: Journal = JOURNAL.parse().unwrap();
+        assert_eq!(journal.bounds(1), (Some(200), Some(250)));
+        assert_eq!(journal.bounds(2), (None, None));
+        assert_eq!(journal.check(1, "210"), Check::Unknown);
+        assert!(matches!(journal.check(1, "220"), Check::Wrong(_)));
+        assert!(matches!(journal.check(1, "200"), Check::Wrong(_)));
+        assert!(matches!(journal.check(1, "260"), Check::Wrong(_)));
+        assert!(matches!(journal.check(2, "ABC"), Check::Wrong(_)));
+        assert_eq!(journal.check(2, "ABD"), Check::Unknown);
+
+        journal.record(1, "210", Verdict::Correct);
+        assert_eq!(journal.correct(1), Some("210"));
+        assert_eq!(journal.check(1, "210"), Check::Correct);
+        assert!(matches!(journal.check(1, "211"), Check::Wrong(_)));
+    }
+}
@@ -1,32 +1,64 @@
+extern crate self as aoc;
+
+pub mod bits;
 mod bounding_box;
+pub mod circuit;
+pub mod combinator;
 mod direction;
+pub mod expr;
+pub mod grammar;
 mod graph;
-mod parse;
+pub mod jigsaw;
+pub mod journal;
+pub mod linalg;
+pub mod math;
+mod memo;
+pub mod nested;
+mod ocr;
+mod orientation;
+pub mod parse;
+pub mod pattern;
+pub mod render;
 mod runner;
+pub mod visualise;
+pub mod vm;
 mod xy;
 
 pub use bounding_box::BoundingBox;
 pub use direction::Direction;
 pub use graph::Graph;
-pub use parse::parse1;
-pub use parse::parse2;
-pub use parse::parse3;
-pub use parse::parse4;
-pub use parse::parse5;
-pub use parse::parse6;
-pub use parse::parse7;
-pub use parse::parse8;
+pub use memo::Memo;
+pub use ocr::ocr;
+pub use ocr::ocr_points;
+pub use orientation::Orientation;
 pub use parse::parse_grid;
 pub use runner::run;
 pub use runner::run_with_expected_custom_check;
 pub use runner::run_with_expected_range;
 pub use runner::run_with_expected_value;
+pub use runner::variants;
+pub use runner::Variant;
 pub use xy::XY;
 
+pub use aoc_derive::FromRegex;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::parse::{attempt, convert_group};
+    pub use crate::runner::variant;
+    pub use anyhow;
+    pub use regex::Regex;
+}
+
 #[macro_export]
 macro_rules! run {
     ($expr: expr) => {{
-        aoc::run(file!(), || $expr)
+        aoc::run(
+            file!(),
+            env!("CARGO_MANIFEST_DIR"),
+            stringify!($expr),
+            || $expr,
+        )
     }};
     ($expr: expr, $expectation: literal) => {{
         aoc::run_with_expected_value(file!(), || $expr, $expectation)
@@ -36,41 +68,21 @@ macro_rules! run {
     }};
 }
 
+/// Run several implementations of one part and check that they agree, see [`variants`]:
+/// `aoc::run!(aoc::variants!(input, part_one, part_one_fast), 42)`.
 #[macro_export]
-macro_rules! run_custom_check {
-    ($expr: expr, $custom_check: expr) => {{
-        aoc::run_with_expected_custom_check(file!(), || $expr, $custom_check)
+macro_rules! variants {
+    ($input: expr, $($func: path),+ $(,)?) => {{
+        aoc::variants(
+            $input,
+            &[$(aoc::__private::variant(stringify!($func), &$func)),+],
+        )
     }};
 }
 
 #[macro_export]
-macro_rules! parse {
-    ($regex:expr, $haystack:expr, $to_a:expr) => {
-        aoc::parse1($regex, $haystack, $to_a)
-    };
-    ($regex:expr, $haystack:expr, $to_a:expr, $to_b:expr) => {
-        aoc::parse2($regex, $haystack, $to_a, $to_b)
-    };
-    ($regex:expr, $haystack:expr, $to_a:expr, $to_b:expr, $to_c:expr) => {
-        aoc::parse3($regex, $haystack, $to_a, $to_b, $to_c)
-    };
-    ($regex:expr, $haystack:expr, $to_a:expr, $to_b:expr, $to_c:expr, $to_d:expr) => {
-        aoc::parse4($regex, $haystack, $to_a, $to_b, $to_c, $to_d)
-    };
-    ($regex:expr, $haystack:expr, $to_a:expr, $to_b:expr, $to_c:expr, $to_d:expr, $to_e:expr) => {
-        aoc::parse5($regex, $haystack, $to_a, $to_b, $to_c, $to_d, $to_e)
-    };
-    ($regex:expr, $haystack:expr, $to_a:expr, $to_b:expr, $to_c:expr, $to_d:expr, $to_e:expr, $to_f:expr) => {
-        aoc::parse6($regex, $haystack, $to_a, $to_b, $to_c, $to_d, $to_e, $to_f)
-    };
-    ($regex:expr, $haystack:expr, $to_a:expr, $to_b:expr, $to_c:expr, $to_d:expr, $to_e:expr, $to_f:expr, $to_g:expr) => {
-        aoc::parse7(
-            $regex, $haystack, $to_a, $to_b, $to_c, $to_d, $to_e, $to_f, $to_g,
-        )
-    };
-    ($regex:expr, $haystack:expr, $to_a:expr, $to_b:expr, $to_c:expr, $to_d:expr, $to_e:expr, $to_f:expr, $to_g:expr, $to_h:expr) => {
-        aoc::parse8(
-            $regex, $haystack, $to_a, $to_b, $to_c, $to_d, $to_e, $to_f, $to_g, $to_h,
-        )
-    };
+macro_rules! run_custom_check {
+    ($expr: expr, $custom_check: expr) => {{
+        aoc::run_with_expected_custom_check(file!(), || $expr, $custom_check)
+    }};
 }
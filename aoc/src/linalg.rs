@@ -0,0 +1,495 @@
+use crate::math::gcd;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// An exact rational number, always kept in its normalised form (denominator > 0, numerator and
+/// denominator coprime).
+#[derive(PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Fraction {
+    num: i128,
+    den: i128,
+}
+
+impl Fraction {
+    pub const ZERO: Fraction = Fraction { num: 0, den: 1 };
+    pub const ONE: Fraction = Fraction { num: 1, den: 1 };
+
+    /// Create a new fraction num / den. Panics if den is zero.
+    pub fn new(num: i128, den: i128) -> Self {
+        assert_ne!(den, 0, "denominator is zero");
+        let g = gcd(num, den);
+        let sign = if den < 0 { -1 } else { 1 };
+        Fraction {
+            num: sign * num / g,
+            den: sign * den / g,
+        }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.num
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.den
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == 1
+    }
+
+    /// Return the fraction as an integer, if it is one.
+    pub fn to_integer(&self) -> Option<i128> {
+        if self.is_integer() {
+            Some(self.num)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<i128> for Fraction {
+    fn from(value: i128) -> Self {
+        Fraction { num: value, den: 1 }
+    }
+}
+
+macro_rules! impl_from_for_fraction {
+    ($($t: ty),*) => {
+        $(
+            impl From<$t> for Fraction {
+                fn from(value: $t) -> Self {
+                    Fraction { num: value.into(), den: 1 }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_for_fraction! { i8, i16, i32, i64, u8, u16, u32, u64 }
+
+impl Add for Fraction {
+    type Output = Fraction;
+
+    fn add(self, rhs: Fraction) -> Self::Output {
+        let g = gcd(self.den, rhs.den);
+        Fraction::new(
+            self.num * (rhs.den / g) + rhs.num * (self.den / g),
+            self.den / g * rhs.den,
+        )
+    }
+}
+
+impl Sub for Fraction {
+    type Output = Fraction;
+
+    fn sub(self, rhs: Fraction) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Mul for Fraction {
+    type Output = Fraction;
+
+    fn mul(self, rhs: Fraction) -> Self::Output {
+        // cross-cancel before multiplying to postpone overflow
+        let a = gcd(self.num, rhs.den).max(1);
+        let b = gcd(rhs.num, self.den).max(1);
+        Fraction::new(
+            (self.num / a) * (rhs.num / b),
+            (self.den / b) * (rhs.den / a),
+        )
+    }
+}
+
+impl Div for Fraction {
+    type Output = Fraction;
+
+    fn div(self, rhs: Fraction) -> Self::Output {
+        assert!(!rhs.is_zero(), "division by zero");
+        self * Fraction::new(rhs.den, rhs.num)
+    }
+}
+
+impl Neg for Fraction {
+    type Output = Fraction;
+
+    fn neg(self) -> Self::Output {
+        Fraction {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+}
+
+impl PartialOrd for Fraction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Fraction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.num * other.den).cmp(&(other.num * self.den))
+    }
+}
+
+impl Display for Fraction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+impl Debug for Fraction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+/// The result of solving a linear system A * x = b.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Solution {
+    /// The system has no solution.
+    None,
+    /// The system has exactly one solution.
+    Unique(Vec<Fraction>),
+    /// The system has infinitely many solutions; this is one of them, with all free variables set
+    /// to zero.
+    Infinite(Vec<Fraction>),
+}
+
+impl Solution {
+    /// Return the solution if it is unique and all its values are integers.
+    pub fn integral(&self) -> Option<Vec<i128>> {
+        match self {
+            Solution::Unique(values) => values.iter().map(|f| f.to_integer()).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// A dense matrix of exact rational numbers.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<Fraction>,
+}
+
+impl Matrix {
+    /// Create a rows x cols matrix filled with zeroes.
+    pub fn zero(rows: usize, cols: usize) -> Self {
+        Matrix {
+            rows,
+            cols,
+            data: vec![Fraction::ZERO; rows * cols],
+        }
+    }
+
+    /// Create an n x n identity matrix.
+    pub fn identity(n: usize) -> Self {
+        let mut m = Matrix::zero(n, n);
+        for i in 0..n {
+            m.set(i, i, Fraction::ONE);
+        }
+        m
+    }
+
+    /// Create a matrix from a list of rows. Panics if the rows are of different length.
+    pub fn from_rows<R, T>(rows: impl IntoIterator<Item = R>) -> Self
+    where
+        R: IntoIterator<Item = T>,
+        T: Into<Fraction>,
+    {
+        let mut data = vec![];
+        let mut row_count = 0;
+        for row in rows {
+            data.extend(row.into_iter().map(|value| value.into()));
+            row_count += 1;
+        }
+        let cols = data.len().checked_div(row_count).unwrap_or(0);
+        assert_eq!(cols * row_count, data.len(), "rows of different length");
+        Matrix {
+            rows: row_count,
+            cols,
+            data,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Fraction {
+        self.data[row * self.cols + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: Fraction) {
+        self.data[row * self.cols + col] = value;
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for col in 0..self.cols {
+            self.data.swap(a * self.cols + col, b * self.cols + col);
+        }
+    }
+
+    /// Bring the matrix to reduced row echelon form in place. Returns the pivot column of each
+    /// non-zero row.
+    fn reduce(&mut self) -> Vec<usize> {
+        let mut pivots = vec![];
+        let mut row = 0;
+        for col in 0..self.cols {
+            if row == self.rows {
+                break;
+            }
+            let Some(pivot_row) = (row..self.rows).find(|&r| !self.get(r, col).is_zero()) else {
+                continue;
+            };
+            if pivot_row != row {
+                self.swap_rows(pivot_row, row);
+            }
+            let pivot = self.get(row, col);
+            for c in col..self.cols {
+                self.set(row, c, self.get(row, c) / pivot);
+            }
+            for r in 0..self.rows {
+                let factor = self.get(r, col);
+                if r == row || factor.is_zero() {
+                    continue;
+                }
+                for c in col..self.cols {
+                    self.set(r, c, self.get(r, c) - factor * self.get(row, c));
+                }
+            }
+            pivots.push(col);
+            row += 1;
+        }
+        pivots
+    }
+
+    /// Solve A * x = b, where A is this matrix, using Gaussian elimination.
+    pub fn solve(&self, b: &[Fraction]) -> Solution {
+        assert_eq!(self.rows, b.len(), "b does not match number of rows");
+        let mut augmented = Matrix::zero(self.rows, self.cols + 1);
+        for (row, value) in b.iter().enumerate() {
+            for col in 0..self.cols {
+                augmented.set(row, col, self.get(row, col));
+            }
+            augmented.set(row, self.cols, *value);
+        }
+
+        let pivots = augmented.reduce();
+        if pivots.last() == Some(&self.cols) {
+            // a row of the form 0 = c where c != 0
+            return Solution::None;
+        }
+        let mut x = vec![Fraction::ZERO; self.cols];
+        for (row, &col) in pivots.iter().enumerate() {
+            x[col] = augmented.get(row, self.cols);
+        }
+        if pivots.len() == self.cols {
+            Solution::Unique(x)
+        } else {
+            Solution::Infinite(x)
+        }
+    }
+
+    /// The determinant of a square matrix.
+    pub fn determinant(&self) -> Fraction {
+        assert_eq!(self.rows, self.cols, "matrix not square");
+        let mut m = self.clone();
+        let mut det = Fraction::ONE;
+        for col in 0..m.cols {
+            let Some(pivot_row) = (col..m.rows).find(|&r| !m.get(r, col).is_zero()) else {
+                return Fraction::ZERO;
+            };
+            if pivot_row != col {
+                m.swap_rows(pivot_row, col);
+                det = -det;
+            }
+            let pivot = m.get(col, col);
+            det = det * pivot;
+            for r in col + 1..m.rows {
+                let factor = m.get(r, col) / pivot;
+                if factor.is_zero() {
+                    continue;
+                }
+                for c in col..m.cols {
+                    m.set(r, c, m.get(r, c) - factor * m.get(col, c));
+                }
+            }
+        }
+        det
+    }
+
+    /// The number of linearly independent rows.
+    pub fn rank(&self) -> usize {
+        self.clone().reduce().len()
+    }
+
+    /// Raise a square matrix to the given power by repeated squaring. Useful to jump ahead in
+    /// linear recurrences: if the state vector v evolves as v' = M * v, then after n steps it is
+    /// M^n * v.
+    pub fn pow(&self, exp: u64) -> Matrix {
+        assert_eq!(self.rows, self.cols, "matrix not square");
+        let mut result = Matrix::identity(self.rows);
+        let mut base = self.clone();
+        let mut exp = exp;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = &result * &base;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+
+    /// Multiply the matrix with a column vector.
+    pub fn mul_vec(&self, v: &[Fraction]) -> Vec<Fraction> {
+        assert_eq!(
+            self.cols,
+            v.len(),
+            "vector does not match number of columns"
+        );
+        (0..self.rows)
+            .map(|row| {
+                (0..self.cols).fold(Fraction::ZERO, |acc, col| acc + self.get(row, col) * v[col])
+            })
+            .collect()
+    }
+}
+
+impl Mul for &Matrix {
+    type Output = Matrix;
+
+    fn mul(self, rhs: &Matrix) -> Self::Output {
+        assert_eq!(self.cols, rhs.rows, "matrix dimensions do not match");
+        let mut m = Matrix::zero(self.rows, rhs.cols);
+        for row in 0..self.rows {
+            for col in 0..rhs.cols {
+                let value = (0..self.cols).fold(Fraction::ZERO, |acc, i| {
+                    acc + self.get(row, i) * rhs.get(i, col)
+                });
+                m.set(row, col, value);
+            }
+        }
+        m
+    }
+}
+
+impl Debug for Matrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows: Vec<_> = (0..self.rows)
+            .map(|row| &self.data[row * self.cols..(row + 1) * self.cols])
+            .collect();
+        write!(f, "{:?}", rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fractions(values: &[i128]) -> Vec<Fraction> {
+        values.iter().map(|&v| v.into()).collect()
+    }
+
+    #[test]
+    fn test_fraction() {
+        assert_eq!(Fraction::new(2, 4), Fraction::new(1, 2));
+        assert_eq!(Fraction::new(1, -2), Fraction::new(-1, 2));
+        assert_eq!(Fraction::new(0, -5), Fraction::ZERO);
+        assert_eq!(
+            Fraction::new(1, 2) + Fraction::new(1, 3),
+            Fraction::new(5, 6)
+        );
+        assert_eq!(
+            Fraction::new(1, 2) - Fraction::new(1, 3),
+            Fraction::new(1, 6)
+        );
+        assert_eq!(
+            Fraction::new(2, 3) * Fraction::new(3, 4),
+            Fraction::new(1, 2)
+        );
+        assert_eq!(
+            Fraction::new(2, 3) / Fraction::new(4, 3),
+            Fraction::new(1, 2)
+        );
+        assert!(Fraction::new(1, 3) < Fraction::new(1, 2));
+        assert!(Fraction::new(-1, 2) < Fraction::ZERO);
+        assert_eq!(Fraction::new(6, 3).to_integer(), Some(2));
+        assert_eq!(Fraction::new(7, 3).to_integer(), None);
+        assert_eq!(format!("{}", Fraction::new(-7, 3)), "-7/3");
+        assert_eq!(format!("{}", Fraction::from(4)), "4");
+    }
+
+    #[test]
+    fn test_solve_unique() {
+        // 2024/13 example: 94a + 22b = 8400, 34a + 67b = 5400
+        let m = Matrix::from_rows([[94, 22], [34, 67]]);
+        let solution = m.solve(&fractions(&[8400, 5400]));
+        assert_eq!(solution, Solution::Unique(fractions(&[80, 40])));
+        assert_eq!(solution.integral(), Some(vec![80, 40]));
+
+        let solution = m.solve(&fractions(&[8401, 5400]));
+        assert!(matches!(solution, Solution::Unique(_)));
+        assert_eq!(solution.integral(), None);
+    }
+
+    #[test]
+    fn test_solve_needs_row_swap() {
+        let m = Matrix::from_rows([[0, 1, 1], [1, 0, 1], [1, 1, 0]]);
+        let solution = m.solve(&fractions(&[5, 4, 3]));
+        assert_eq!(solution.integral(), Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_solve_none_and_infinite() {
+        let m = Matrix::from_rows([[1, 1], [2, 2]]);
+        assert_eq!(m.solve(&fractions(&[1, 3])), Solution::None);
+        assert_eq!(
+            m.solve(&fractions(&[1, 2])),
+            Solution::Infinite(fractions(&[1, 0]))
+        );
+        assert_eq!(m.solve(&fractions(&[1, 2])).integral(), None);
+    }
+
+    #[test]
+    fn test_determinant_and_rank() {
+        let m = Matrix::from_rows([[94, 22], [34, 67]]);
+        assert_eq!(m.determinant(), Fraction::from(94 * 67 - 22 * 34));
+        let m = Matrix::from_rows([[0, 1], [1, 0]]);
+        assert_eq!(m.determinant(), Fraction::from(-1));
+        let m = Matrix::from_rows([[2, 0, 1], [1, 3, 2], [1, 1, 2]]);
+        assert_eq!(m.determinant(), Fraction::from(6));
+        assert_eq!(m.rank(), 3);
+        let m = Matrix::from_rows([[1, 2, 3], [2, 4, 6], [1, 1, 1]]);
+        assert_eq!(m.determinant(), Fraction::ZERO);
+        assert_eq!(m.rank(), 2);
+    }
+
+    #[test]
+    fn test_pow() {
+        // Fibonacci: (F(n+1), F(n)) = M^n * (1, 0)
+        let m = Matrix::from_rows([[1, 1], [1, 0]]);
+        let v = m.pow(90).mul_vec(&fractions(&[1, 0]));
+        assert_eq!(v[1], Fraction::from(2880067194370816120_i64));
+        assert_eq!(m.pow(0), Matrix::identity(2));
+    }
+}
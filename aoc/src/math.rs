@@ -0,0 +1,467 @@
+use rustc_hash::FxHashMap;
+/// Primitive integer types accepted by `gcd` and `lcm`.
+pub trait Integer: Copy + PartialEq {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn abs(self) -> Self;
+    fn checked_abs(self) -> Option<Self>;
+    fn checked_div(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn wrapping_rem(self, rhs: Self) -> Self;
+}
+
+macro_rules! impl_integer_common {
+    ($t: ty) => {
+        const ZERO: Self = 0;
+        const ONE: Self = 1;
+
+        fn checked_div(self, rhs: Self) -> Option<Self> {
+            <$t>::checked_div(self, rhs)
+        }
+
+        fn checked_mul(self, rhs: Self) -> Option<Self> {
+            <$t>::checked_mul(self, rhs)
+        }
+
+        fn wrapping_rem(self, rhs: Self) -> Self {
+            <$t>::wrapping_rem(self, rhs)
+        }
+    };
+}
+
+macro_rules! impl_integer_unsigned {
+    ($($t: ty),*) => {
+        $(
+            impl Integer for $t {
+                impl_integer_common!($t);
+
+                fn abs(self) -> Self {
+                    self
+                }
+
+                fn checked_abs(self) -> Option<Self> {
+                    Some(self)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_integer_signed {
+    ($($t: ty),*) => {
+        $(
+            impl Integer for $t {
+                impl_integer_common!($t);
+
+                fn abs(self) -> Self {
+                    <$t>::abs(self)
+                }
+
+                fn checked_abs(self) -> Option<Self> {
+                    <$t>::checked_abs(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_unsigned! { u8, u16, u32, u64, u128, usize }
+impl_integer_signed! { i8, i16, i32, i64, i128, isize }
+
+// The gcd of a and b up to its sign. wrapping_rem is exact here: T::MIN % -1 is 0, but `%`
+// panics on it.
+fn gcd_signed<T: Integer>(a: T, b: T) -> T {
+    let (mut a, mut b) = (a, b);
+    while b != T::ZERO {
+        (a, b) = (b, a.wrapping_rem(b));
+    }
+    a
+}
+
+/// Greatest common divisor of a and b. Always non-negative; gcd(0, 0) == 0.
+///
+/// Panics if the result does not fit in T, which only happens for a signed T when it is
+/// |T::MIN|: gcd(T::MIN, 0) and gcd(T::MIN, T::MIN).
+pub fn gcd<T: Integer>(a: T, b: T) -> T {
+    gcd_signed(a, b).abs()
+}
+
+/// Least common multiple of a and b, or None if it does not fit in T. Always non-negative;
+/// lcm(n, 0) == 0.
+pub fn lcm<T: Integer>(a: T, b: T) -> Option<T> {
+    if a == T::ZERO || b == T::ZERO {
+        return Some(T::ZERO);
+    }
+    a.checked_div(gcd_signed(a, b))?
+        .checked_mul(b)?
+        .checked_abs()
+}
+
+/// Greatest common divisor of all numbers in an iterator (0 if the iterator is empty). Panics
+/// like [`gcd`].
+pub fn gcd_all<T: Integer>(iter: impl IntoIterator<Item = T>) -> T {
+    iter.into_iter().fold(T::ZERO, gcd)
+}
+
+/// Least common multiple of all numbers in an iterator (1 if the iterator is empty), or None if
+/// it does not fit in T.
+pub fn lcm_all<T: Integer>(iter: impl IntoIterator<Item = T>) -> Option<T> {
+    iter.into_iter().try_fold(T::ONE, lcm)
+}
+
+/// Extended Euclidean algorithm: returns (g, x, y) such that a * x + b * y == g == gcd(a, b).
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (g, x, y) = extended_gcd_i128(a.into(), b.into());
+    (g as i64, x as i64, y as i64)
+}
+
+// |x| <= |b| / g and |y| <= |a| / g, so this never overflows for i64 inputs.
+fn extended_gcd_i128(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// Calculate base^exp mod modulus.
+pub fn mod_pow(base: u64, exp: u64, modulus: u64) -> u64 {
+    if modulus == 1 {
+        return 0;
+    }
+    let modulus = modulus as u128;
+    let mut base = base as u128 % modulus;
+    let mut exp = exp;
+    let mut result = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exp >>= 1;
+    }
+    result as u64
+}
+
+/// Modular multiplicative inverse of a mod modulus, if a and modulus are coprime. The result is
+/// in the range [0, modulus). Returns None if modulus is not positive.
+pub fn mod_inverse(a: i64, modulus: i64) -> Option<i64> {
+    if modulus <= 0 {
+        return None;
+    }
+    let x = mod_inverse_i128(a.into(), modulus.into())?;
+    Some(x as i64)
+}
+
+fn mod_inverse_i128(a: i128, modulus: i128) -> Option<i128> {
+    let (g, x, _) = extended_gcd_i128(a.rem_euclid(modulus), modulus);
+    if g != 1 {
+        return None;
+    }
+    Some(x.rem_euclid(modulus))
+}
+
+/// Chinese Remainder Theorem: given a list of congruences x ≡ residue (mod modulus), find the
+/// smallest non-negative x satisfying all of them. The moduli do not have to be pairwise
+/// coprime.
+///
+/// Returns (x, lcm of all moduli), or None if the congruences are inconsistent, a modulus is not
+/// positive, or the lcm does not fit in an i64.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    // x < m <= i64::MAX, so none of the i128 arithmetic below can overflow
+    let mut x: i128 = 0;
+    let mut m: i128 = 1;
+    for &(residue, modulus) in congruences {
+        if modulus <= 0 {
+            return None;
+        }
+        let (r2, m2) = (i128::from(residue), i128::from(modulus));
+        let (g, p, _) = extended_gcd_i128(m, m2);
+        let diff = r2 - x;
+        if diff % g != 0 {
+            return None;
+        }
+        let lcm = m / g * m2;
+        if lcm > i128::from(i64::MAX) {
+            return None;
+        }
+        let k = (diff / g % (m2 / g)) * p % (m2 / g);
+        x = (x + m * k).rem_euclid(lcm);
+        m = lcm;
+    }
+    Some((x as i64, m as i64))
+}
+
+/// Discrete logarithm: find the smallest x >= 0 such that base^x ≡ target (mod modulus), using
+/// the baby-step giant-step algorithm. Returns None if base and modulus are not coprime, or if
+/// modulus is 0.
+pub fn discrete_log(base: u64, target: u64, modulus: u64) -> Option<u64> {
+    if modulus == 0 {
+        return None;
+    }
+    let target = target % modulus;
+    let n = (modulus as f64).sqrt().ceil() as u64;
+
+    // baby steps: base^j for j in 0..n, keep the smallest j for each value
+    let mut table: FxHashMap<u64, u64> = FxHashMap::default();
+    let mut value = 1 % modulus;
+    for j in 0..n {
+        table.entry(value).or_insert(j);
+        value = (value as u128 * base as u128 % modulus as u128) as u64;
+    }
+
+    // giant steps: target * base^(-n * i) for i in 0..=n
+    let inverse = mod_inverse_i128(base.into(), modulus.into())? as u64;
+    let factor = mod_pow(inverse, n, modulus);
+    let mut gamma = target;
+    for i in 0..=n {
+        if let Some(j) = table.get(&gamma) {
+            return Some(i * n + j);
+        }
+        gamma = (gamma as u128 * factor as u128 % modulus as u128) as u64;
+    }
+    None
+}
+
+/// Deterministic Miller-Rabin primality test, valid for all u64 values.
+pub fn is_prime(n: u64) -> bool {
+    const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    for p in WITNESSES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    let mut d = n - 1;
+    let mut s = 0;
+    while d.is_multiple_of(2) {
+        d /= 2;
+        s += 1;
+    }
+    'witness: for a in WITNESSES {
+        let mut x = mod_pow(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = (x as u128 * x as u128 % n as u128) as u64;
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// Prime factorisation of n as a sorted list of (prime, exponent) pairs. prime_factors(1) is
+/// empty.
+pub fn prime_factors(n: u64) -> Vec<(u64, u32)> {
+    debug_assert_ne!(n, 0);
+    let mut factors = vec![];
+    if is_prime(n) {
+        return vec![(n, 1)];
+    }
+    let mut n = n;
+    let mut p = 2;
+    while p <= n / p {
+        if n.is_multiple_of(p) {
+            let mut exp = 0;
+            while n.is_multiple_of(p) {
+                n /= p;
+                exp += 1;
+            }
+            factors.push((p, exp));
+            // don't trial divide all the way up to the square root of a large prime
+            if is_prime(n) {
+                break;
+            }
+        }
+        p += if p == 2 { 1 } else { 2 };
+    }
+    if n > 1 {
+        factors.push((n, 1));
+    }
+    factors
+}
+
+/// All divisors of n (including 1 and n), in ascending order.
+pub fn divisors(n: u64) -> Vec<u64> {
+    let mut divisors = vec![1];
+    for (p, exp) in prime_factors(n) {
+        let len = divisors.len();
+        let mut pk = 1;
+        for _ in 0..exp {
+            pk *= p;
+            for i in 0..len {
+                divisors.push(divisors[i] * pk);
+            }
+        }
+    }
+    divisors.sort();
+    divisors
+}
+
+/// Sum of all divisors of n (including 1 and n), or None if it does not fit in a u64.
+pub fn divisor_sum(n: u64) -> Option<u64> {
+    prime_factors(n)
+        .into_iter()
+        .try_fold(1u64, |product, (p, exp)| {
+            // 1 + p + p^2 + ... + p^exp
+            let mut sum = 1u64;
+            let mut pk = 1u64;
+            for _ in 0..exp {
+                pk = pk.checked_mul(p)?;
+                sum = sum.checked_add(pk)?;
+            }
+            product.checked_mul(sum)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(18, 12), 6);
+        assert_eq!(gcd(7, 13), 1);
+        assert_eq!(gcd(0, 5), 5);
+        assert_eq!(gcd(0, 0), 0);
+        assert_eq!(gcd(-12_i32, 18), 6);
+        assert_eq!(gcd(i64::MIN, -1), 1);
+        assert_eq!(gcd(i64::MIN, 6), 2);
+        assert_eq!(gcd_all([12_u64, 18, 27]), 3);
+    }
+
+    #[test]
+    fn test_lcm() {
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(0, 6), Some(0));
+        assert_eq!(lcm(-4_i64, 6), Some(12));
+        assert_eq!(lcm(u64::MAX, u64::MAX - 1), None);
+        assert_eq!(lcm(i64::MIN, -1), None);
+        assert_eq!(lcm(i64::MIN, i64::MIN), None);
+        assert_eq!(lcm(i64::MIN, 2), None);
+        assert_eq!(lcm(i64::MIN / 2, 2), Some(-(i64::MIN / 2)));
+        assert_eq!(lcm_all([2_usize, 3, 4, 5]), Some(60));
+        assert_eq!(lcm_all(Vec::<usize>::new()), Some(1));
+        assert_eq!(lcm_all([1_u8 << 4, 3, 5, 7]), None);
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        for (a, b) in [(240, 46), (46, 240), (3, 7), (-12, 18), (0, 5)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(a * x + b * y, g);
+        }
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        assert_eq!(mod_pow(2, 10, 1000), 24);
+        assert_eq!(mod_pow(5, 0, 7), 1);
+        assert_eq!(mod_pow(5, 3, 1), 0);
+        assert_eq!(mod_pow(u64::MAX, 2, u64::MAX - 1), 1);
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(6, 9), None);
+        assert_eq!(mod_inverse(3, 0), None);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[]), Some((0, 1)));
+
+        // non-coprime moduli
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+
+        // 2020/13 example: 17,x,13,19
+        assert_eq!(
+            crt(&[(0, 17), (13 - 2, 13), (19 - 3, 19)]),
+            Some((3417, 17 * 13 * 19))
+        );
+
+        // combined modulus beyond i64, and bad moduli
+        let big = 3037000501; // big * (big + 2) > i64::MAX, and the two are coprime
+        assert_eq!(crt(&[(1, big), (2, big + 2)]), None);
+        assert_eq!(
+            crt(&[(1, 4611686018427387847), (0, 2)]),
+            Some((4611686018427387848, 9223372036854775694))
+        );
+        assert_eq!(crt(&[(1, 0)]), None);
+        assert_eq!(crt(&[(1, -3)]), None);
+    }
+
+    #[test]
+    fn test_discrete_log() {
+        assert_eq!(discrete_log(7, 5764801, 20201227), Some(8));
+        assert_eq!(discrete_log(7, 17807724, 20201227), Some(11));
+        assert_eq!(discrete_log(2, 1, 11), Some(0));
+        assert_eq!(discrete_log(2, 0, 11), None);
+        assert_eq!(discrete_log(2, 3, 8), None);
+        assert_eq!(discrete_log(2, 3, 0), None);
+    }
+
+    #[test]
+    fn test_is_prime() {
+        let primes: Vec<_> = (0..30).filter(|n| is_prime(*n)).collect();
+        assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert!(is_prime(20201227));
+        assert!(is_prime(18446744073709551557));
+        assert!(!is_prime(18446744073709551559));
+        assert!(!is_prime(3215031751));
+    }
+
+    #[test]
+    fn test_prime_factors() {
+        assert_eq!(prime_factors(1), vec![]);
+        assert_eq!(prime_factors(2), vec![(2, 1)]);
+        assert_eq!(prime_factors(360), vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(prime_factors(20201227), vec![(20201227, 1)]);
+        assert_eq!(
+            prime_factors(18446744073709551557),
+            vec![(18446744073709551557, 1)]
+        );
+        assert_eq!(prime_factors(u64::MAX).len(), 7);
+        assert_eq!(prime_factors(2 * 4294967311), vec![(2, 1), (4294967311, 1)]);
+    }
+
+    #[test]
+    fn test_divisors() {
+        assert_eq!(divisors(1), vec![1]);
+        assert_eq!(divisors(12), vec![1, 2, 3, 4, 6, 12]);
+        assert_eq!(divisors(13), vec![1, 13]);
+        assert_eq!(divisor_sum(1), Some(1));
+        assert_eq!(divisor_sum(12), Some(28));
+        assert_eq!(divisor_sum(700000), Some(1968624));
+
+        // a prime factor above 2^32
+        assert_eq!(divisor_sum(2 * 4294967311), Some(3 * 4294967312));
+        assert_eq!(
+            divisor_sum(18446744073709551557),
+            Some(18446744073709551558)
+        );
+        assert_eq!(divisor_sum(3 << 62), None);
+    }
+}
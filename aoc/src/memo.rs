@@ -0,0 +1,138 @@
+use rustc_hash::FxHashMap;
+use std::hash::Hash;
+
+/// Cache of previously computed values, typically used to memoise a recursive function by its
+/// arguments.
+///
+/// The closure passed to `get_or_insert_with` is given the memo itself, so a memoised function
+/// can recurse through the same cache.
+#[derive(Debug)]
+pub struct Memo<K, V> {
+    cache: FxHashMap<K, V>,
+    hits: usize,
+    misses: usize,
+}
+
+impl<K, V> Memo<K, V>
+where
+    K: Eq + Hash,
+    V: Clone,
+{
+    /// Return the cached value for key, or compute it by calling f and cache the result.
+    pub fn get_or_insert_with<F>(&mut self, key: K, f: F) -> V
+    where
+        F: FnOnce(&mut Self) -> V,
+    {
+        if let Some(value) = self.cache.get(&key) {
+            self.hits += 1;
+            return value.clone();
+        }
+        self.misses += 1;
+        let value = f(self);
+        self.cache.insert(key, value.clone());
+        value
+    }
+
+    /// Fallible version of `get_or_insert_with`. Errors are not cached.
+    pub fn try_get_or_insert_with<F, E>(&mut self, key: K, f: F) -> Result<V, E>
+    where
+        F: FnOnce(&mut Self) -> Result<V, E>,
+    {
+        if let Some(value) = self.cache.get(&key) {
+            self.hits += 1;
+            return Ok(value.clone());
+        }
+        self.misses += 1;
+        let value = f(self)?;
+        self.cache.insert(key, value.clone());
+        Ok(value)
+    }
+
+    /// Look up a cached value without computing it. Does not affect the hit/miss statistics.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.cache.get(key)
+    }
+
+    /// Remove all cached values and reset the hit/miss statistics.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    /// Number of cached values.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// Number of lookups answered from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Number of lookups that required the value to be computed.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+}
+
+impl<K, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Memo {
+            cache: FxHashMap::default(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fib(n: u64, memo: &mut Memo<u64, u64>) -> u64 {
+        memo.get_or_insert_with(n, |memo| {
+            if n < 2 {
+                n
+            } else {
+                fib(n - 1, memo) + fib(n - 2, memo)
+            }
+        })
+    }
+
+    #[test]
+    fn test_recursion() {
+        let mut memo = Memo::default();
+        assert_eq!(fib(10, &mut memo), 55);
+        assert_eq!(memo.len(), 11);
+        assert_eq!(memo.misses(), 11);
+        assert_eq!(memo.hits(), 8);
+        assert_eq!(memo.get(&9), Some(&34));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut memo = Memo::default();
+        fib(10, &mut memo);
+        memo.clear();
+        assert!(memo.is_empty());
+        assert_eq!(memo.hits(), 0);
+        assert_eq!(memo.misses(), 0);
+        assert_eq!(fib(10, &mut memo), 55);
+    }
+
+    #[test]
+    fn test_errors_are_not_cached() {
+        let mut memo: Memo<u32, u32> = Memo::default();
+        assert!(memo.try_get_or_insert_with(1, |_| Err("error")).is_err());
+        assert!(memo.is_empty());
+        assert_eq!(memo.try_get_or_insert_with(1, |_| Ok::<_, ()>(2)), Ok(2));
+        assert_eq!(memo.try_get_or_insert_with(1, |_| Ok::<_, ()>(3)), Ok(2));
+        assert_eq!(memo.hits(), 1);
+        assert_eq!(memo.misses(), 2);
+    }
+}
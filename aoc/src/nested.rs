@@ -0,0 +1,388 @@
+use anyhow::{anyhow, bail, ensure, Result};
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Bracketed data: `[1,[2,[3]]]`, snailfish numbers, or JSON-like text with `{"key":value}`
+/// objects.
+///
+/// Leaves are whatever the leaf type parses them as; a quoted string is given to it with its
+/// quotes. Object keys must be quoted strings, and are stored without their quotes.
+///
+/// `==` is structural: `[[2]]` and `[2]` are not equal. For the element-wise order, where they
+/// are, use [`Nested::compare`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Nested<T> {
+    Leaf(T),
+    List(Vec<Nested<T>>),
+    Object(Vec<(String, Nested<T>)>),
+}
+
+impl<T: FromStr> FromStr for Nested<T> {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        ensure!(
+            parser.pos == parser.chars.len(),
+            "unexpected '{}' at position {}",
+            parser.chars[parser.pos],
+            parser.pos
+        );
+        Ok(value)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, ch: char) -> Result<()> {
+        match self.peek() {
+            Some(c) if c == ch => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => bail!("expected '{}', found '{}' at position {}", ch, c, self.pos),
+            None => bail!("expected '{}', found end of input", ch),
+        }
+    }
+
+    // Parse a comma separated sequence of items up to the closing delimiter
+    fn sequence<I>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<I>,
+    ) -> Result<Vec<I>> {
+        let mut items = vec![];
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                _ => {
+                    self.expect(close)?;
+                    return Ok(items);
+                }
+            }
+        }
+    }
+
+    fn token(&mut self) -> Result<String> {
+        let start = self.pos;
+        if self.peek() == Some('"') {
+            self.pos += 1;
+            while self.pos < self.chars.len() && self.chars[self.pos] != '"' {
+                if self.chars[self.pos] == '\\' {
+                    self.pos += 1;
+                }
+                self.pos += 1;
+            }
+            self.expect('"')?;
+        } else {
+            while self.pos < self.chars.len()
+                && !self.chars[self.pos].is_whitespace()
+                && !"[]{},:\"".contains(self.chars[self.pos])
+            {
+                self.pos += 1;
+            }
+        }
+        ensure!(self.pos > start, "expected a value at position {}", start);
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn value<T: FromStr>(&mut self) -> Result<Nested<T>> {
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                Ok(Nested::List(self.sequence(']', |p| p.value())?))
+            }
+            Some('{') => {
+                self.pos += 1;
+                Ok(Nested::Object(self.sequence('}', |p| {
+                    p.skip_whitespace();
+                    let start = p.pos;
+                    let key = p.token()?;
+                    let key = key
+                        .strip_prefix('"')
+                        .and_then(|k| k.strip_suffix('"'))
+                        .ok_or_else(|| anyhow!("expected a quoted key at position {}", start))?
+                        .to_string();
+                    p.expect(':')?;
+                    Ok((key, p.value()?))
+                })?))
+            }
+            Some(_) => {
+                let start = self.pos;
+                let token = self.token()?;
+                let leaf = token
+                    .parse()
+                    .map_err(|_| anyhow!("bad value '{}' at position {}", token, start))?;
+                Ok(Nested::Leaf(leaf))
+            }
+            None => bail!("unexpected end of input"),
+        }
+    }
+}
+
+impl<T> Nested<T> {
+    pub fn as_leaf(&self) -> Option<&T> {
+        match self {
+            Nested::Leaf(leaf) => Some(leaf),
+            _ => None,
+        }
+    }
+
+    fn children(&self) -> Vec<&Nested<T>> {
+        match self {
+            Nested::Leaf(_) => vec![],
+            Nested::List(items) => items.iter().collect(),
+            Nested::Object(entries) => entries.iter().map(|(_, value)| value).collect(),
+        }
+    }
+
+    /// The element at a path of indices, one per level; for objects, the index is that of the
+    /// entry.
+    pub fn get(&self, path: &[usize]) -> Option<&Nested<T>> {
+        let Some((first, rest)) = path.split_first() else {
+            return Some(self);
+        };
+        let child = match self {
+            Nested::Leaf(_) => None,
+            Nested::List(items) => items.get(*first),
+            Nested::Object(entries) => entries.get(*first).map(|(_, value)| value),
+        };
+        child?.get(rest)
+    }
+
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut Nested<T>> {
+        let Some((first, rest)) = path.split_first() else {
+            return Some(self);
+        };
+        let child = match self {
+            Nested::Leaf(_) => None,
+            Nested::List(items) => items.get_mut(*first),
+            Nested::Object(entries) => entries.get_mut(*first).map(|(_, value)| value),
+        };
+        child?.get_mut(rest)
+    }
+
+    /// Paths to all leaves, depth first, left to right. The length of a path is the depth of the
+    /// leaf.
+    pub fn leaf_paths(&self) -> Vec<Vec<usize>> {
+        fn inner<T>(node: &Nested<T>, path: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+            if let Nested::Leaf(_) = node {
+                out.push(path.clone());
+                return;
+            }
+            for (i, child) in node.children().into_iter().enumerate() {
+                path.push(i);
+                inner(child, path, out);
+                path.pop();
+            }
+        }
+        let mut out = vec![];
+        inner(self, &mut vec![], &mut out);
+        out
+    }
+
+    /// All leaves, depth first, left to right.
+    pub fn leaves(&self) -> Vec<&T> {
+        let mut out = vec![];
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            match node {
+                Nested::Leaf(leaf) => out.push(leaf),
+                _ => stack.extend(node.children().into_iter().rev()),
+            }
+        }
+        out
+    }
+
+    /// Nesting depth: 0 for a leaf, 1 for a list of leaves, and so on.
+    pub fn depth(&self) -> usize {
+        match self {
+            Nested::Leaf(_) => 0,
+            _ => {
+                1 + self
+                    .children()
+                    .into_iter()
+                    .map(|child| child.depth())
+                    .max()
+                    .unwrap_or(0)
+            }
+        }
+    }
+}
+
+impl<T: Ord> Nested<T> {
+    /// Compare element by element. A leaf compared to a list is treated as a list of just that
+    /// leaf. Shorter lists sort first if all their elements are equal. Objects compare by their
+    /// entries, and sort after leaves and lists.
+    ///
+    /// This is not `==`: `[[2]]` and `[2]` compare `Equal`, but are structurally different.
+    pub fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Nested::Leaf(a), Nested::Leaf(b)) => a.cmp(b),
+            (Nested::List(a), Nested::List(b)) => compare_lists(a, b),
+            (Nested::Leaf(_), Nested::List(b)) => compare_lists(std::slice::from_ref(self), b),
+            (Nested::List(a), Nested::Leaf(_)) => compare_lists(a, std::slice::from_ref(other)),
+            (Nested::Object(a), Nested::Object(b)) => {
+                for ((ka, va), (kb, vb)) in a.iter().zip(b.iter()) {
+                    let ord = ka.cmp(kb).then_with(|| va.compare(vb));
+                    if ord != Ordering::Equal {
+                        return ord;
+                    }
+                }
+                a.len().cmp(&b.len())
+            }
+            (Nested::Object(_), _) => Ordering::Greater,
+            (_, Nested::Object(_)) => Ordering::Less,
+        }
+    }
+}
+
+fn compare_lists<T: Ord>(a: &[Nested<T>], b: &[Nested<T>]) -> Ordering {
+    for (x, y) in a.iter().zip(b.iter()) {
+        let ord = x.compare(y);
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+impl<T: Display> Display for Nested<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Nested::Leaf(leaf) => write!(f, "{}", leaf),
+            Nested::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Nested::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "\"{}\":{}", key, value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Nested<i32> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("[]"), Nested::List(vec![]));
+        assert_eq!(parse("7"), Nested::Leaf(7));
+        assert_eq!(
+            parse("[1, [2,[]] ]"),
+            Nested::List(vec![
+                Nested::Leaf(1),
+                Nested::List(vec![Nested::Leaf(2), Nested::List(vec![])]),
+            ])
+        );
+        assert_eq!(
+            parse(r#"{"a":1,"b":[-2]}"#),
+            Nested::Object(vec![
+                ("a".to_string(), Nested::Leaf(1)),
+                ("b".to_string(), Nested::List(vec![Nested::Leaf(-2)])),
+            ])
+        );
+
+        assert!("[1,2".parse::<Nested<i32>>().is_err());
+        assert!("[1,,2]".parse::<Nested<i32>>().is_err());
+        assert!("[1]]".parse::<Nested<i32>>().is_err());
+        assert!("[x]".parse::<Nested<i32>>().is_err());
+        assert!("{a:1}".parse::<Nested<i32>>().is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        for s in ["[]", "[1,[2,[3,[4,[5,6,7]]]],8,9]", r#"{"a":[1,{}],"b":2}"#] {
+            assert_eq!(parse(s).to_string(), s);
+        }
+        let strings: Nested<String> = r#"["red",{"x":"a b"}]"#.parse().unwrap();
+        assert_eq!(strings.to_string(), r#"["red",{"x":"a b"}]"#);
+    }
+
+    #[test]
+    fn test_compare() {
+        let pairs = [
+            ("[1,1,3,1,1]", "[1,1,5,1,1]", Ordering::Less),
+            ("[[1],[2,3,4]]", "[[1],4]", Ordering::Less),
+            ("[9]", "[[8,7,6]]", Ordering::Greater),
+            ("[[4,4],4,4]", "[[4,4],4,4,4]", Ordering::Less),
+            ("[7,7,7,7]", "[7,7,7]", Ordering::Greater),
+            ("[]", "[3]", Ordering::Less),
+            ("[[[]]]", "[[]]", Ordering::Greater),
+            (
+                "[1,[2,[3,[4,[5,6,7]]]],8,9]",
+                "[1,[2,[3,[4,[5,6,0]]]],8,9]",
+                Ordering::Greater,
+            ),
+            ("[[2]]", "[2]", Ordering::Equal),
+        ];
+        for (a, b, expected) in pairs {
+            assert_eq!(parse(a).compare(&parse(b)), expected, "{} vs {}", a, b);
+        }
+        assert_ne!(parse("[[2]]"), parse("[2]"));
+    }
+
+    #[test]
+    fn test_paths() {
+        let mut n = parse("[[1,2],[[3]],4]");
+        assert_eq!(
+            n.leaf_paths(),
+            vec![vec![0, 0], vec![0, 1], vec![1, 0, 0], vec![2]]
+        );
+        assert_eq!(n.leaves(), vec![&1, &2, &3, &4]);
+        assert_eq!(n.depth(), 3);
+        assert_eq!(n.get(&[1, 0]), Some(&parse("[3]")));
+        assert_eq!(n.get(&[1, 0, 0]).and_then(|n| n.as_leaf()), Some(&3));
+        assert_eq!(n.get(&[2, 0]), None);
+        assert_eq!(n.get(&[5]), None);
+
+        *n.get_mut(&[1]).unwrap() = Nested::Leaf(0);
+        *n.get_mut(&[2]).unwrap() = parse("[5,6]");
+        assert_eq!(n.to_string(), "[[1,2],0,[5,6]]");
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::{anyhow, bail, Context, Result};
+use rustc_hash::FxHashSet;
+
+use crate::{BoundingBox, XY};
+
+// The 6 pixel high font (2016, 2019, 2021, 2022)
+const SMALL_FONT_LETTERS: &str = "ABCEFGHIJKLOPRSUYZ";
+const SMALL_FONT: &str = "\
+.##..###...##..####.####..##..#..#.###...##.#..#.#.....##..###..###...###.#..#.#...#.####
+#..#.#..#.#..#.#....#....#..#.#..#..#.....#.#.#..#....#..#.#..#.#..#.#....#..#.#...#....#
+#..#.###..#....###..###..#....####..#.....#.##...#....#..#.#..#.#..#.#....#..#..#.#....#.
+####.#..#.#....#....#....#.##.#..#..#.....#.#.#..#....#..#.###..###...##..#..#...#....#..
+#..#.#..#.#..#.#....#....#..#.#..#..#..#..#.#.#..#....#..#.#....#.#.....#.#..#...#...#...
+#..#.###...##..####.#.....###.#..#.###..##..#..#.####..##..#....#..#.###...##....#...####";
+
+// The 10 pixel high font (2018)
+const LARGE_FONT_LETTERS: &str = "ABCEFGHJKLNPRXZ";
+const LARGE_FONT: &str = "\
+..##....#####....####...######..######...####...#....#.....###..#....#..#.......#....#..#####...#####...#....#..######
+.#..#...#....#..#....#..#.......#.......#....#..#....#......#...#...#...#.......##...#..#....#..#....#..#....#.......#
+#....#..#....#..#.......#.......#.......#.......#....#......#...#..#....#.......##...#..#....#..#....#...#..#........#
+#....#..#....#..#.......#.......#.......#.......#....#......#...#.#.....#.......#.#..#..#....#..#....#...#..#.......#.
+#....#..#####...#.......#####...#####...#.......######......#...##......#.......#.#..#..#####...#####.....##.......#..
+######..#....#..#.......#.......#.......#..###..#....#......#...##......#.......#..#.#..#.......#..#......##......#...
+#....#..#....#..#.......#.......#.......#....#..#....#......#...#.#.....#.......#..#.#..#.......#...#....#..#....#....
+#....#..#....#..#.......#.......#.......#....#..#....#..#...#...#..#....#.......#...##..#.......#...#....#..#...#.....
+#....#..#....#..#....#..#.......#.......#...##..#....#..#...#...#...#...#.......#...##..#.......#....#..#....#..#.....
+#....#..#####....####...######..#........###.#..#....#...###....#....#..######..#....#..#.......#....#..#....#..######";
+
+fn lit_pixels(s: &str) -> FxHashSet<XY> {
+    s.lines()
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.chars()
+                .enumerate()
+                .filter(|(_, ch)| *ch == '#')
+                .map(move |(x, _)| (x as i32, y as i32).into())
+        })
+        .collect()
+}
+
+/// Split lit pixels (with x in [0, width) and y in [0, height)) into letters, separated by blank
+/// columns. Returns the x coordinate where each letter starts, and the letter drawn with '#' and
+/// '.'.
+fn split_letters(pixels: &FxHashSet<XY>, width: i32, height: i32) -> Vec<(i32, String)> {
+    let is_blank = |x| (0..height).all(|y| !pixels.contains(&(x, y).into()));
+    let mut letters = vec![];
+    let mut x = 0;
+    while x < width {
+        if is_blank(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && !is_blank(x) {
+            x += 1;
+        }
+        let letter = (0..height)
+            .map(|y| {
+                (start..x)
+                    .map(|x| {
+                        if pixels.contains(&(x, y).into()) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        letters.push((start, letter));
+    }
+    letters
+}
+
+/// Decode letters drawn in one of the Advent of Code block letter fonts (6 or 10 pixels high),
+/// given the coordinates of all lit pixels.
+pub fn ocr_points(points: impl IntoIterator<Item = XY>) -> Result<String> {
+    let points: Vec<XY> = points.into_iter().collect();
+    let bounds = BoundingBox::from_points(points.iter().copied()).context("no lit pixels")?;
+    let (min, max) = (bounds.top_left(), bounds.bottom_right());
+    let (width, height) = (max.x - min.x + 1, max.y - min.y + 1);
+    let (font_letters, font) = match height {
+        6 => (SMALL_FONT_LETTERS, SMALL_FONT),
+        10 => (LARGE_FONT_LETTERS, LARGE_FONT),
+        _ => bail!("unsupported letter height {height}"),
+    };
+    let font_width = font.lines().next().expect("non-empty font").len() as i32;
+    let font: Vec<(char, String)> = font_letters
+        .chars()
+        .zip(split_letters(&lit_pixels(font), font_width, height))
+        .map(|(ch, (_, glyph))| (ch, glyph))
+        .collect();
+
+    let pixels: FxHashSet<XY> = points
+        .iter()
+        .map(|xy| (xy.x - min.x, xy.y - min.y).into())
+        .collect();
+    split_letters(&pixels, width, height)
+        .into_iter()
+        .map(|(x, letter)| {
+            font.iter()
+                .find(|(_, glyph)| glyph == &letter)
+                .map(|(ch, _)| *ch)
+                .ok_or_else(|| anyhow!("unknown letter at x={x}:\n{letter}"))
+        })
+        .collect()
+}
+
+/// Decode letters drawn in one of the Advent of Code block letter fonts (6 or 10 pixels high).
+/// Lit pixels are drawn with '#', everything else is considered unlit.
+pub fn ocr(s: &str) -> Result<String> {
+    ocr_points(lit_pixels(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ocr_small_font() {
+        // 2022/10
+        let input = "\
+###..####.####.####.#..#.###..####..##..
+#..#.#.......#.#....#.#..#..#.#....#..#.
+#..#.###....#..###..##...###..###..#..#.
+###..#.....#...#....#.#..#..#.#....####.
+#.#..#....#....#....#.#..#..#.#....#..#.
+#..#.#....####.####.#..#.###..#....#..#.";
+        assert_eq!(ocr(input).unwrap(), "RFZEKBFA");
+
+        // 2021/13, with extra blank rows and columns
+        let input = "
+........................................
+.####.####.#....####...##..##..###..####.
+.#....#....#....#.......#.#..#.#..#.#....
+.###..###..#....###.....#.#....#..#.###..
+.#....#....#....#.......#.#.##.###..#....
+.#....#....#....#....#..#.#..#.#.#..#....
+.####.#....####.#.....##...###.#..#.#....
+";
+        assert_eq!(ocr(input).unwrap(), "EFLFJGRF");
+    }
+
+    #[test]
+    fn test_ocr_narrow_and_wide_letters() {
+        let input = "\
+###.#...#
+.#..#...#
+.#...#.#.
+.#....#..
+.#....#..
+###...#..";
+        assert_eq!(ocr(input).unwrap(), "IY");
+    }
+
+    #[test]
+    fn test_ocr_large_font() {
+        let input = "\
+#....#..######
+#....#.......#
+.#..#........#
+.#..#.......#.
+..##.......#..
+..##......#...
+.#..#....#....
+.#..#...#.....
+#....#..#.....
+#....#..######";
+        assert_eq!(ocr(input).unwrap(), "XZ");
+    }
+
+    #[test]
+    fn test_ocr_points() {
+        let a = ".##.\n#..#\n#..#\n####\n#..#\n#..#";
+        let points = lit_pixels(a)
+            .into_iter()
+            .map(|xy| xy + XY::from((100, -50)));
+        assert_eq!(ocr_points(points).unwrap(), "A");
+    }
+
+    #[test]
+    fn test_ocr_errors() {
+        assert!(ocr("").is_err());
+        assert!(ocr("#\n#\n#").is_err());
+        assert!(ocr("####\n####\n####\n####\n####\n####").is_err());
+    }
+}
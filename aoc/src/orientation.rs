@@ -0,0 +1,109 @@
+use crate::XY;
+
+/// One of the 8 ways to rotate and flip a rectangle (the dihedral group of the square).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Orientation {
+    /// Rotated clockwise N degrees.
+    R0,
+    R90,
+    R180,
+    R270,
+    /// Flipped along the Y axis (left becomes right), then rotated clockwise N degrees.
+    F0,
+    F90,
+    F180,
+    F270,
+}
+
+impl Orientation {
+    pub const ALL: [Orientation; 8] = [
+        Orientation::R0,
+        Orientation::R90,
+        Orientation::R180,
+        Orientation::R270,
+        Orientation::F0,
+        Orientation::F90,
+        Orientation::F180,
+        Orientation::F270,
+    ];
+
+    fn rotations(&self) -> usize {
+        match self {
+            Orientation::R0 | Orientation::F0 => 0,
+            Orientation::R90 | Orientation::F90 => 1,
+            Orientation::R180 | Orientation::F180 => 2,
+            Orientation::R270 | Orientation::F270 => 3,
+        }
+    }
+
+    fn is_flipped(&self) -> bool {
+        matches!(
+            self,
+            Orientation::F0 | Orientation::F90 | Orientation::F180 | Orientation::F270
+        )
+    }
+
+    /// Size of a width x height rectangle after it has been reoriented.
+    pub fn size(&self, width: i32, height: i32) -> (i32, i32) {
+        if self.rotations().is_multiple_of(2) {
+            (width, height)
+        } else {
+            (height, width)
+        }
+    }
+
+    /// Map a coordinate inside a width x height rectangle with its top left corner at (0, 0) to
+    /// its position after the rectangle has been reoriented.
+    pub fn apply(&self, xy: XY, width: i32, height: i32) -> XY {
+        let (mut x, mut y, mut w, mut h) = (xy.x, xy.y, width, height);
+        if self.is_flipped() {
+            x = w - 1 - x;
+        }
+        for _ in 0..self.rotations() {
+            (x, y) = (h - 1 - y, x);
+            (w, h) = (h, w);
+        }
+        (x, y).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reorient(s: &str, o: Orientation) -> String {
+        let rows: Vec<Vec<char>> = s.lines().map(|line| line.chars().collect()).collect();
+        let (w, h) = (rows[0].len() as i32, rows.len() as i32);
+        let (new_w, new_h) = o.size(w, h);
+        let mut out = vec![vec!['?'; new_w as usize]; new_h as usize];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, ch) in row.iter().enumerate() {
+                let xy = o.apply((x as i32, y as i32).into(), w, h);
+                out[xy.y as usize][xy.x as usize] = *ch;
+            }
+        }
+        out.into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_apply() {
+        let s = "012\n345\n678";
+        assert_eq!(reorient(s, Orientation::R0), s);
+        assert_eq!(reorient(s, Orientation::R90), "630\n741\n852");
+        assert_eq!(reorient(s, Orientation::R180), "876\n543\n210");
+        assert_eq!(reorient(s, Orientation::R270), "258\n147\n036");
+        assert_eq!(reorient(s, Orientation::F0), "210\n543\n876");
+        assert_eq!(reorient(s, Orientation::F90), "852\n741\n630");
+    }
+
+    #[test]
+    fn test_apply_non_square() {
+        assert_eq!(Orientation::R90.size(3, 1), (1, 3));
+        assert_eq!(reorient("abc", Orientation::R90), "a\nb\nc");
+        assert_eq!(reorient("abc", Orientation::R270), "c\nb\na");
+        assert_eq!(reorient("ab\ncd\nef", Orientation::F270), "ace\nbdf");
+    }
+}
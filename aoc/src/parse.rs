@@ -1,289 +1,385 @@
-use anyhow::{anyhow, ensure};
-use regex::Regex;
+use anyhow::anyhow;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
 use crate::{BoundingBox, XY};
 
-const REGEX_NOT_FOUND: &str = "regex not found in \"{haystack}\"";
+/// An error at a specific line and column of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+    /// The offending line.
+    pub text: String,
+    pub message: String,
+}
+
+impl ParseError {
+    /// An error at byte `offset` of `input`.
+    pub fn at(input: &str, offset: usize, message: impl Display) -> Self {
+        let offset = offset.min(input.len());
+        let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |i| offset + i);
+        ParseError {
+            line: input[..offset].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            text: input[line_start..line_end].to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Move the error down by `lines` lines, for text that started further into the input.
+    fn offset_lines(mut self, lines: usize) -> Self {
+        self.line += lines;
+        self
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let number = self.line.to_string();
+        let pad = " ".repeat(number.len());
+        writeln!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )?;
+        writeln!(f, "{} | {}", number, self.text)?;
+        write!(f, "{} | {}^", pad, " ".repeat(self.column - 1))
+    }
+}
+
+impl std::error::Error for ParseError {}
 
-pub fn parse1<'h, A, FA, EA>(regex: &Regex, haystack: &'h str, to_a: FA) -> anyhow::Result<A>
+/// Parse each line of the input with `f`. If `f` fails, the error says which line failed, and
+/// where on the line if `f` returned a `ParseError`.
+pub fn parse_lines<T, F>(input: &str, mut f: F) -> anyhow::Result<Vec<T>>
 where
-    FA: Fn(&'h str) -> Result<A, EA>,
-    EA: Display + Send + Sync,
+    F: FnMut(&str) -> anyhow::Result<T>,
 {
-    let (_, [a]) = regex
-        .captures(haystack)
-        .map(|caps| caps.extract())
-        .ok_or_else(|| anyhow!(REGEX_NOT_FOUND))?;
-    to_a(a).map_err(|err| anyhow!("group 1: \"{a}\": {err}"))
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            f(line).map_err(|err| {
+                let err = match err.downcast::<ParseError>() {
+                    Ok(err) => err.offset_lines(i),
+                    Err(err) => ParseError {
+                        line: i + 1,
+                        column: 1,
+                        text: line.to_string(),
+                        message: format!("{:#}", err),
+                    },
+                };
+                anyhow::Error::new(err)
+            })
+        })
+        .collect()
 }
 
-pub fn parse2<'h, A, FA, EA, B, FB, EB>(
-    regex: &Regex,
+/// Convert one capture group. Used by code generated by `#[derive(FromRegex)]`.
+#[doc(hidden)]
+pub fn convert_group<'h, T, E, F>(
     haystack: &'h str,
-    to_a: FA,
-    to_b: FB,
-) -> anyhow::Result<(A, B)>
+    caps: &Captures<'h>,
+    index: usize,
+    f: F,
+) -> anyhow::Result<T>
 where
-    FA: Fn(&'h str) -> Result<A, EA>,
-    FB: Fn(&'h str) -> Result<B, EB>,
-    EA: Display + Send + Sync,
-    EB: Display + Send + Sync,
+    F: FnOnce(&'h str) -> Result<T, E>,
+    E: Display,
 {
-    let (_, [a, b]) = regex
-        .captures(haystack)
-        .map(|caps| caps.extract())
-        .ok_or_else(|| anyhow!(REGEX_NOT_FOUND))?;
-    Ok((
-        to_a(a).map_err(|err| anyhow!("group 1: \"{a}\": {err}"))?,
-        to_b(b).map_err(|err| anyhow!("group 2: \"{b}\": {err}"))?,
-    ))
+    let group = caps
+        .get(index)
+        .ok_or_else(|| anyhow!("group {index}: did not participate in the match"))?;
+    let value = group.as_str();
+    f(value).map_err(|err| {
+        let message = format!("group {index}: \"{value}\": {err}");
+        anyhow::Error::new(ParseError::at(haystack, group.start(), message))
+    })
 }
 
-pub fn parse3<'h, A, FA, EA, B, FB, EB, C, FC, EC>(
-    regex: &Regex,
-    haystack: &'h str,
-    to_a: FA,
-    to_b: FB,
-    to_c: FC,
-) -> anyhow::Result<(A, B, C)>
+/// Split the input into sections separated by blank lines. Lines containing only whitespace
+/// count as blank, and `\r\n` line endings are accepted. Leading and trailing blank lines are
+/// ignored, so an input without any text has no sections.
+pub fn sections(input: &str) -> Vec<&str> {
+    let mut out = vec![];
+    let mut current: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        let text = line.trim_end_matches('\n').trim_end_matches('\r');
+        if text.trim().is_empty() {
+            out.extend(current.take().map(|(start, end)| &input[start..end]));
+        } else {
+            let start = current.map_or(offset, |(start, _)| start);
+            current = Some((start, offset + text.len()));
+        }
+        offset += line.len();
+    }
+    out.extend(current.map(|(start, end)| &input[start..end]));
+    out
+}
+
+/// A tuple of parsers, one per section. See [`parse_sections`].
+pub trait SectionParsers<'a> {
+    type Output;
+
+    /// The number of sections.
+    const COUNT: usize;
+
+    fn parse_each(self, input: &'a str, sections: &[&'a str]) -> anyhow::Result<Self::Output>;
+}
+
+macro_rules! impl_section_parsers {
+    ($count:expr; $($f:ident $t:ident $index:tt),+) => {
+        impl<'a, $($f, $t),+> SectionParsers<'a> for ($($f,)+)
+        where
+            $($f: FnOnce(&'a str) -> anyhow::Result<$t>),+
+        {
+            type Output = ($($t,)+);
+
+            const COUNT: usize = $count;
+
+            fn parse_each(
+                self,
+                input: &'a str,
+                sections: &[&'a str],
+            ) -> anyhow::Result<Self::Output> {
+                Ok(($(
+                    (self.$index)(sections[$index])
+                        .map_err(|err| section_error(input, sections[$index], $index, err))?,
+                )+))
+            }
+        }
+    };
+}
+
+impl_section_parsers!(1; F0 T0 0);
+impl_section_parsers!(2; F0 T0 0, F1 T1 1);
+impl_section_parsers!(3; F0 T0 0, F1 T1 1, F2 T2 2);
+impl_section_parsers!(4; F0 T0 0, F1 T1 1, F2 T2 2, F3 T3 3);
+impl_section_parsers!(5; F0 T0 0, F1 T1 1, F2 T2 2, F3 T3 3, F4 T4 4);
+
+/// Split the input into [`sections`] and parse each with the matching parser in the tuple
+/// `parsers`. If a parser fails, the error names the section, and where in the input it failed
+/// if the parser returned a `ParseError`.
+///
+/// ```ignore
+/// let (rules, updates) = parse_sections(input, (
+///     |s| parse_lines(s, str::parse::<Rule>),
+///     |s| parse_lines(s, |line| list::<u32>(line, ",")),
+/// ))?;
+/// ```
+pub fn parse_sections<'a, P>(input: &'a str, parsers: P) -> anyhow::Result<P::Output>
 where
-    FA: Fn(&'h str) -> Result<A, EA>,
-    FB: Fn(&'h str) -> Result<B, EB>,
-    FC: Fn(&'h str) -> Result<C, EC>,
-    EA: Display + Send + Sync,
-    EB: Display + Send + Sync,
-    EC: Display + Send + Sync,
+    P: SectionParsers<'a>,
 {
-    let (_, [a, b, c]) = regex
-        .captures(haystack)
-        .map(|caps| caps.extract())
-        .ok_or_else(|| anyhow!(REGEX_NOT_FOUND))?;
-    Ok((
-        to_a(a).map_err(|err| anyhow!("group 1: \"{a}\": {err}"))?,
-        to_b(b).map_err(|err| anyhow!("group 2: \"{b}\": {err}"))?,
-        to_c(c).map_err(|err| anyhow!("group 3: \"{c}\": {err}"))?,
-    ))
+    let sections = sections(input);
+    if sections.len() != P::COUNT {
+        return Err(anyhow!(
+            "expected {} sections, found {}",
+            P::COUNT,
+            sections.len()
+        ));
+    }
+    parsers.parse_each(input, &sections)
 }
 
-pub fn parse4<'h, A, FA, EA, B, FB, EB, C, FC, EC, D, FD, ED>(
-    regex: &Regex,
-    haystack: &'h str,
-    to_a: FA,
-    to_b: FB,
-    to_c: FC,
-    to_d: FD,
-) -> anyhow::Result<(A, B, C, D)>
+fn section_error(input: &str, section: &str, index: usize, err: anyhow::Error) -> anyhow::Error {
+    // sections are slices of input, so their offset is the distance between the pointers
+    let offset = section.as_ptr() as usize - input.as_ptr() as usize;
+    let err = match err.downcast::<ParseError>() {
+        Ok(err) => ParseError {
+            message: format!("section {}: {}", index + 1, err.message),
+            ..err.offset_lines(input[..offset].matches('\n').count())
+        },
+        Err(err) => ParseError::at(input, offset, format!("section {}: {:#}", index + 1, err)),
+    };
+    anyhow::Error::new(err)
+}
+
+/// Run `f`; lets generated code use `?` in an expression. Used by `#[derive(FromRegex)]`.
+#[doc(hidden)]
+pub fn attempt<T, F>(f: F) -> anyhow::Result<T>
 where
-    FA: Fn(&'h str) -> Result<A, EA>,
-    FB: Fn(&'h str) -> Result<B, EB>,
-    FC: Fn(&'h str) -> Result<C, EC>,
-    FD: Fn(&'h str) -> Result<D, ED>,
-    EA: Display + Send + Sync,
-    EB: Display + Send + Sync,
-    EC: Display + Send + Sync,
-    ED: Display + Send + Sync,
+    F: FnOnce() -> anyhow::Result<T>,
 {
-    let (_, [a, b, c, d]) = regex
-        .captures(haystack)
-        .map(|caps| caps.extract())
-        .ok_or_else(|| anyhow!(REGEX_NOT_FOUND))?;
-    Ok((
-        to_a(a).map_err(|err| anyhow!("group 1: \"{a}\": {err}"))?,
-        to_b(b).map_err(|err| anyhow!("group 2: \"{b}\": {err}"))?,
-        to_c(c).map_err(|err| anyhow!("group 3: \"{c}\": {err}"))?,
-        to_d(d).map_err(|err| anyhow!("group 4: \"{d}\": {err}"))?,
-    ))
+    f()
 }
 
-pub fn parse5<'h, A, FA, EA, B, FB, EB, C, FC, EC, D, FD, ED, E, FE, EE>(
-    regex: &Regex,
-    haystack: &'h str,
-    to_a: FA,
-    to_b: FB,
-    to_c: FC,
-    to_d: FD,
-    to_e: FE,
-) -> anyhow::Result<(A, B, C, D, E)>
+/// Types that can be picked out of a longer text. Implemented by `#[derive(FromRegex)]`.
+pub trait FindRegex: Sized {
+    /// The leftmost match at or after byte `start` of `haystack`, and its conversion. `state`
+    /// carries what earlier calls on the same haystack found; `start` must not decrease between
+    /// them.
+    fn find_at(
+        haystack: &str,
+        start: usize,
+        state: &mut FindState,
+    ) -> Option<(Range<usize>, anyhow::Result<Self>)>;
+}
+
+/// Where each regex of a [`FindRegex`] type matches next, so that [`find_all`] on an enum only
+/// searches again with the regex of the variant that matched, not with every variant's regex.
+#[derive(Debug, Default)]
+pub struct FindState {
+    next: Vec<Option<Option<Range<usize>>>>,
+}
+
+impl FindState {
+    /// The leftmost match of `regex`, the `index`th regex of the type, at or after `start`. Used
+    /// by code generated by `#[derive(FromRegex)]`.
+    #[doc(hidden)]
+    pub fn next_match(
+        &mut self,
+        index: usize,
+        regex: &Regex,
+        haystack: &str,
+        start: usize,
+    ) -> Option<Range<usize>> {
+        if self.next.len() <= index {
+            self.next.resize(index + 1, None);
+        }
+        match &self.next[index] {
+            // no match after an earlier start, so none after this one either
+            Some(None) => None,
+            Some(Some(range)) if range.start >= start => Some(range.clone()),
+            _ => {
+                let found = regex.find_at(haystack, start).map(|m| m.range());
+                self.next[index] = Some(found.clone());
+                found
+            }
+        }
+    }
+}
+
+/// Every non-overlapping match of `T` in `haystack`, from left to right.
+///
+/// ```ignore
+/// #[derive(aoc::FromRegex)]
+/// #[regex(r"mul\((\d+),(\d+)\)")]
+/// struct Mul(u32, u32);
+///
+/// let all: Vec<Mul> = aoc::parse::find_all("xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)")?;
+/// ```
+pub fn find_all<T: FindRegex>(haystack: &str) -> anyhow::Result<Vec<T>> {
+    let mut out = vec![];
+    let mut start = 0;
+    let mut state = FindState::default();
+    while start <= haystack.len() {
+        let Some((range, value)) = T::find_at(haystack, start, &mut state) else {
+            break;
+        };
+        out.push(value?);
+        start = if range.is_empty() {
+            // step past an empty match, staying on a char boundary
+            range.end
+                + haystack[range.end..]
+                    .chars()
+                    .next()
+                    .map_or(1, char::len_utf8)
+        } else {
+            range.end
+        };
+    }
+    Ok(out)
+}
+
+/// All integers in `s`, in order.
+///
+/// **A `-` directly in front of the digits is always taken as a minus sign**, so `"2-4"` is
+/// `[2, -4]`, and an error for unsigned `T`. Use [`unsigned_integers`] for input where `-` is a
+/// separator, like ranges.
+pub fn integers<T>(s: &str) -> anyhow::Result<Vec<T>>
 where
-    FA: Fn(&'h str) -> Result<A, EA>,
-    FB: Fn(&'h str) -> Result<B, EB>,
-    FC: Fn(&'h str) -> Result<C, EC>,
-    FD: Fn(&'h str) -> Result<D, ED>,
-    FE: Fn(&'h str) -> Result<E, EE>,
-    EA: Display + Send + Sync,
-    EB: Display + Send + Sync,
-    EC: Display + Send + Sync,
-    ED: Display + Send + Sync,
-    EE: Display + Send + Sync,
+    T: FromStr,
+    T::Err: Display,
 {
-    let (_, [a, b, c, d, e]) = regex
-        .captures(haystack)
-        .map(|caps| caps.extract())
-        .ok_or_else(|| anyhow!(REGEX_NOT_FOUND))?;
-    Ok((
-        to_a(a).map_err(|err| anyhow!("group 1: \"{a}\": {err}"))?,
-        to_b(b).map_err(|err| anyhow!("group 2: \"{b}\": {err}"))?,
-        to_c(c).map_err(|err| anyhow!("group 3: \"{c}\": {err}"))?,
-        to_d(d).map_err(|err| anyhow!("group 4: \"{d}\": {err}"))?,
-        to_e(e).map_err(|err| anyhow!("group 5: \"{e}\": {err}"))?,
-    ))
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    find_integers(s, REGEX.get_or_init(|| Regex::new(r"-?\d+").unwrap()))
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn parse6<'h, A, FA, EA, B, FB, EB, C, FC, EC, D, FD, ED, E, FE, EE, F, FF, EF>(
-    regex: &Regex,
-    haystack: &'h str,
-    to_a: FA,
-    to_b: FB,
-    to_c: FC,
-    to_d: FD,
-    to_e: FE,
-    to_f: FF,
-) -> anyhow::Result<(A, B, C, D, E, F)>
+/// All runs of digits in `s`, in order. Unlike [`integers`], a `-` is never part of the number,
+/// so `"2-4,6-8"` is `[2, 4, 6, 8]`.
+pub fn unsigned_integers<T>(s: &str) -> anyhow::Result<Vec<T>>
 where
-    FA: Fn(&'h str) -> Result<A, EA>,
-    FB: Fn(&'h str) -> Result<B, EB>,
-    FC: Fn(&'h str) -> Result<C, EC>,
-    FD: Fn(&'h str) -> Result<D, ED>,
-    FE: Fn(&'h str) -> Result<E, EE>,
-    FF: Fn(&'h str) -> Result<F, EF>,
-    EA: Display + Send + Sync,
-    EB: Display + Send + Sync,
-    EC: Display + Send + Sync,
-    ED: Display + Send + Sync,
-    EE: Display + Send + Sync,
-    EF: Display + Send + Sync,
+    T: FromStr,
+    T::Err: Display,
 {
-    let (_, [a, b, c, d, e, f]) = regex
-        .captures(haystack)
-        .map(|caps| caps.extract())
-        .ok_or_else(|| anyhow!(REGEX_NOT_FOUND))?;
-    Ok((
-        to_a(a).map_err(|err| anyhow!("group 1: \"{a}\": {err}"))?,
-        to_b(b).map_err(|err| anyhow!("group 2: \"{b}\": {err}"))?,
-        to_c(c).map_err(|err| anyhow!("group 3: \"{c}\": {err}"))?,
-        to_d(d).map_err(|err| anyhow!("group 4: \"{d}\": {err}"))?,
-        to_e(e).map_err(|err| anyhow!("group 5: \"{e}\": {err}"))?,
-        to_f(f).map_err(|err| anyhow!("group 6: \"{f}\": {err}"))?,
-    ))
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    find_integers(s, REGEX.get_or_init(|| Regex::new(r"\d+").unwrap()))
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn parse7<'h, A, FA, EA, B, FB, EB, C, FC, EC, D, FD, ED, E, FE, EE, F, FF, EF, G, FG, EG>(
-    regex: &Regex,
-    haystack: &'h str,
-    to_a: FA,
-    to_b: FB,
-    to_c: FC,
-    to_d: FD,
-    to_e: FE,
-    to_f: FF,
-    to_g: FG,
-) -> anyhow::Result<(A, B, C, D, E, F, G)>
+fn find_integers<T>(s: &str, regex: &Regex) -> anyhow::Result<Vec<T>>
 where
-    FA: Fn(&'h str) -> Result<A, EA>,
-    FB: Fn(&'h str) -> Result<B, EB>,
-    FC: Fn(&'h str) -> Result<C, EC>,
-    FD: Fn(&'h str) -> Result<D, ED>,
-    FE: Fn(&'h str) -> Result<E, EE>,
-    FF: Fn(&'h str) -> Result<F, EF>,
-    FG: Fn(&'h str) -> Result<G, EG>,
-    EA: Display + Send + Sync,
-    EB: Display + Send + Sync,
-    EC: Display + Send + Sync,
-    ED: Display + Send + Sync,
-    EE: Display + Send + Sync,
-    EF: Display + Send + Sync,
-    EG: Display + Send + Sync,
+    T: FromStr,
+    T::Err: Display,
 {
-    let (_, [a, b, c, d, e, f, g]) = regex
-        .captures(haystack)
-        .map(|caps| caps.extract())
-        .ok_or_else(|| anyhow!(REGEX_NOT_FOUND))?;
-    Ok((
-        to_a(a).map_err(|err| anyhow!("group 1: \"{a}\": {err}"))?,
-        to_b(b).map_err(|err| anyhow!("group 2: \"{b}\": {err}"))?,
-        to_c(c).map_err(|err| anyhow!("group 3: \"{c}\": {err}"))?,
-        to_d(d).map_err(|err| anyhow!("group 4: \"{d}\": {err}"))?,
-        to_e(e).map_err(|err| anyhow!("group 5: \"{e}\": {err}"))?,
-        to_f(f).map_err(|err| anyhow!("group 6: \"{f}\": {err}"))?,
-        to_g(g).map_err(|err| anyhow!("group 7: \"{g}\": {err}"))?,
-    ))
+    regex
+        .find_iter(s)
+        .map(|m| {
+            m.as_str().parse().map_err(|err| {
+                let message = format!("\"{}\": {}", m.as_str(), err);
+                anyhow::Error::new(ParseError::at(s, m.start(), message))
+            })
+        })
+        .collect()
 }
 
-#[allow(clippy::too_many_arguments)]
-#[allow(clippy::type_complexity)]
-pub fn parse8<
-    'h,
-    A,
-    FA,
-    EA,
-    B,
-    FB,
-    EB,
-    C,
-    FC,
-    EC,
-    D,
-    FD,
-    ED,
-    E,
-    FE,
-    EE,
-    F,
-    FF,
-    EF,
-    G,
-    FG,
-    EG,
-    H,
-    FH,
-    EH,
->(
-    regex: &Regex,
-    haystack: &'h str,
-    to_a: FA,
-    to_b: FB,
-    to_c: FC,
-    to_d: FD,
-    to_e: FE,
-    to_f: FF,
-    to_g: FG,
-    to_h: FH,
-) -> anyhow::Result<(A, B, C, D, E, F, G, H)>
+/// Exactly `N` integers from `s`, as [`integers`].
+///
+/// ```ignore
+/// let [x, y, dx, dy] = aoc::parse::integer_array::<i32, 4>("p=0,4 v=3,-3")?;
+/// ```
+pub fn integer_array<T, const N: usize>(s: &str) -> anyhow::Result<[T; N]>
 where
-    FA: Fn(&'h str) -> Result<A, EA>,
-    FB: Fn(&'h str) -> Result<B, EB>,
-    FC: Fn(&'h str) -> Result<C, EC>,
-    FD: Fn(&'h str) -> Result<D, ED>,
-    FE: Fn(&'h str) -> Result<E, EE>,
-    FF: Fn(&'h str) -> Result<F, EF>,
-    FG: Fn(&'h str) -> Result<G, EG>,
-    FH: Fn(&'h str) -> Result<H, EH>,
-    EA: Display + Send + Sync,
-    EB: Display + Send + Sync,
-    EC: Display + Send + Sync,
-    ED: Display + Send + Sync,
-    EE: Display + Send + Sync,
-    EF: Display + Send + Sync,
-    EG: Display + Send + Sync,
-    EH: Display + Send + Sync,
+    T: FromStr,
+    T::Err: Display,
 {
-    let (_, [a, b, c, d, e, f, g, h]) = regex
-        .captures(haystack)
-        .map(|caps| caps.extract())
-        .ok_or_else(|| anyhow!(REGEX_NOT_FOUND))?;
-    Ok((
-        to_a(a).map_err(|err| anyhow!("group 1: \"{a}\": {err}"))?,
-        to_b(b).map_err(|err| anyhow!("group 2: \"{b}\": {err}"))?,
-        to_c(c).map_err(|err| anyhow!("group 3: \"{c}\": {err}"))?,
-        to_d(d).map_err(|err| anyhow!("group 4: \"{d}\": {err}"))?,
-        to_e(e).map_err(|err| anyhow!("group 5: \"{e}\": {err}"))?,
-        to_f(f).map_err(|err| anyhow!("group 6: \"{f}\": {err}"))?,
-        to_g(g).map_err(|err| anyhow!("group 7: \"{g}\": {err}"))?,
-        to_h(h).map_err(|err| anyhow!("group 8: \"{h}\": {err}"))?,
-    ))
+    integers(s)?.try_into().map_err(|v: Vec<T>| {
+        anyhow::Error::new(ParseError::at(
+            s,
+            0,
+            format!("expected {} integers, found {}", N, v.len()),
+        ))
+    })
+}
+
+/// Split `s` on `delimiter` and convert each item, with surrounding whitespace removed. A
+/// delimiter of only whitespace splits on runs of whitespace. A blank `s` is an empty list.
+pub fn list<T>(s: &str, delimiter: &str) -> anyhow::Result<Vec<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if s.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    let items: Vec<&str> = if delimiter.trim().is_empty() {
+        s.split_whitespace().collect()
+    } else {
+        s.split(delimiter).map(str::trim).collect()
+    };
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            item.parse().map_err(|err| {
+                // items are slices of s, so their offset is the distance between the pointers
+                let offset = item.as_ptr() as usize - s.as_ptr() as usize;
+                let message = format!("item {}: \"{}\": {}", i + 1, item, err);
+                anyhow::Error::new(ParseError::at(s, offset, message))
+            })
+        })
+        .collect()
 }
 
 /// Parse an AOC input grid. These always look like this:
@@ -305,35 +401,419 @@ where
     if input.is_empty() {
         return Ok(BoundingBox::new((0, 0).into(), (0, 0).into()));
     }
-    let max_x = input.lines().next().expect("non-empty input").len();
-    for line in input.lines() {
-        ensure!(max_x == line.len());
+    let lines: Vec<&str> = input.lines().collect();
+    let max_x = lines[0].chars().count();
+    for (y, line) in lines.iter().enumerate() {
+        let len = line.chars().count();
+        if len != max_x {
+            return Err(ParseError {
+                line: y + 1,
+                column: len.min(max_x) + 1,
+                text: line.to_string(),
+                message: format!("expected {} columns, found {}", max_x, len),
+            }
+            .into());
+        }
     }
-    let mut x: i32 = 0;
-    let mut y: i32 = 0;
-    for ch in input.chars() {
-        if ch == '\n' {
-            x = 0;
-            y += 1;
-        } else {
-            f((x, y).into(), ch)?;
-            x += 1;
+    for (y, line) in lines.iter().enumerate() {
+        for (x, ch) in line.chars().enumerate() {
+            f((x as i32, y as i32).into(), ch).map_err(|err| ParseError {
+                line: y + 1,
+                column: x + 1,
+                text: line.to_string(),
+                message: format!("{:#}", err),
+            })?;
         }
     }
     Ok(BoundingBox::new(
         (0, 0).into(),
-        (max_x as i32 - 1, y).into(),
+        (max_x as i32 - 1, lines.len() as i32 - 1).into(),
     ))
 }
 
+/// Parse a grid into a map of cells, converting each character with `T::try_from`.
+///
+/// ```ignore
+/// let (cells, bounds): (HashMap<XY, Tile>, _) = aoc::parse::parse_grid_as(input)?;
+/// ```
+pub fn parse_grid_as<T, C>(input: &str) -> anyhow::Result<(C, BoundingBox)>
+where
+    T: TryFrom<char>,
+    T::Error: Display,
+    C: Default + Extend<(XY, T)>,
+{
+    let mut cells = C::default();
+    let bounds = parse_grid(input, |xy, ch| {
+        let cell = T::try_from(ch).map_err(|err| anyhow!("'{}': {}", ch, err))?;
+        cells.extend([(xy, cell)]);
+        Ok(())
+    })?;
+    Ok((cells, bounds))
+}
+
+/// The positions of `markers` in a grid, each of which must occur exactly once.
+///
+/// ```ignore
+/// let [start, end] = aoc::parse::grid_markers(input, ['S', 'E'])?;
+/// ```
+pub fn grid_markers<const N: usize>(input: &str, markers: [char; N]) -> anyhow::Result<[XY; N]> {
+    let mut found: [Option<XY>; N] = [None; N];
+    parse_grid(input, |xy, ch| {
+        if let Some(i) = markers.iter().position(|&marker| marker == ch) {
+            if let Some(first) = found[i] {
+                return Err(anyhow!(
+                    "duplicate '{}', first seen at {},{}",
+                    ch,
+                    first.x,
+                    first.y
+                ));
+            }
+            found[i] = Some(xy);
+        }
+        Ok(())
+    })?;
+    if let Some(i) = found.iter().position(Option::is_none) {
+        return Err(anyhow!("missing '{}'", markers[i]));
+    }
+    Ok(found.map(|xy| xy.expect("all markers found")))
+}
+
+/// The positions of each of `chars` in a grid, in reading order. Every character in `chars` is
+/// a key of the result, even if it does not occur.
+pub fn grid_positions(input: &str, chars: &str) -> anyhow::Result<HashMap<char, Vec<XY>>> {
+    let mut out: HashMap<char, Vec<XY>> = chars.chars().map(|ch| (ch, vec![])).collect();
+    parse_grid(input, |xy, ch| {
+        if let Some(positions) = out.get_mut(&ch) {
+            positions.push(xy);
+        }
+        Ok(())
+    })?;
+    Ok(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::FromRegex;
+
+    #[derive(Debug, PartialEq, FromRegex)]
+    #[regex(r"^(\w+) is (\d+) years old$")]
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    #[derive(Debug, PartialEq, FromRegex)]
+    #[regex(r"^(?<y>-?\d+),(?<x>-?\d+)$")]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Debug, PartialEq, FromRegex)]
+    enum Command {
+        #[regex(r"^forward (\d+)$")]
+        Forward(u32),
+        #[regex(r"^turn (left|right)$")]
+        Turn(#[parse_with(|s| Ok::<_, &str>(s == "left"))] bool),
+        #[regex(r"^(?:stop|halt)$")]
+        Stop,
+        #[regex(r"^jump (?<dx>\d+) (?<dy>\d+)$")]
+        Jump {
+            dx: u8,
+            #[parse_with(parse_double)]
+            dy: u8,
+        },
+    }
+
+    fn parse_double(s: &str) -> Result<u8, std::num::ParseIntError> {
+        s.parse::<u8>().map(|n| n * 2)
+    }
+
+    #[test]
+    fn test_derive_struct() {
+        assert_eq!(
+            "Alice is 42 years old".parse::<Person>().unwrap(),
+            Person {
+                name: "Alice".to_string(),
+                age: 42
+            }
+        );
+        assert_eq!("1,-2".parse::<Point>().unwrap(), Point { x: -2, y: 1 });
+    }
+
+    #[test]
+    fn test_derive_enum() {
+        assert_eq!("forward 5".parse::<Command>().unwrap(), Command::Forward(5));
+        assert_eq!("turn left".parse::<Command>().unwrap(), Command::Turn(true));
+        assert_eq!(
+            "turn right".parse::<Command>().unwrap(),
+            Command::Turn(false)
+        );
+        assert_eq!("halt".parse::<Command>().unwrap(), Command::Stop);
+        assert_eq!(
+            "jump 1 2".parse::<Command>().unwrap(),
+            Command::Jump { dx: 1, dy: 4 }
+        );
+    }
+
+    #[test]
+    fn test_derive_errors() {
+        let err = "Bob is 4000000000000 years old"
+            .parse::<Person>()
+            .unwrap_err()
+            .downcast::<ParseError>()
+            .unwrap();
+        assert_eq!(
+            err.message,
+            "group 2: \"4000000000000\": number too large to fit in target type"
+        );
+        assert_eq!(err.column, 8);
+        let err = "Bob is old".parse::<Person>().unwrap_err();
+        assert_eq!(err.to_string(), "regex not found in \"Bob is old\"");
+        let err = "backward 5".parse::<Command>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no variant of Command matches \"backward 5\""
+        );
+        let err = "jump 300 1".parse::<Command>().unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("line 1, column 6: group 1: \"300\""));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = ParseError::at("abc\ndéf x\nghi", 9, "bad x");
+        assert_eq!(err.line, 2);
+        assert_eq!(err.column, 5);
+        assert_eq!(err.text, "déf x");
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 5: bad x\n2 | déf x\n  |     ^"
+        );
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let input = "Alice is 42 years old\nBob is 7 years old\n";
+        let people: Vec<Person> = parse_lines(input, |line| line.parse()).unwrap();
+        assert_eq!(people.len(), 2);
+        assert_eq!(people[1].age, 7);
+
+        // the location of a ParseError is kept, and moved to the right line
+        let input = "Alice is 42 years old\nBob is 4000000000000 years old";
+        let err = parse_lines(input, |line| line.parse::<Person>())
+            .unwrap_err()
+            .downcast::<ParseError>()
+            .unwrap();
+        assert_eq!((err.line, err.column), (2, 8));
+        assert_eq!(err.text, "Bob is 4000000000000 years old");
+
+        // other errors point at the start of the line
+        let input = "1\n2\nx";
+        let err = parse_lines(input, |line| Ok(line.parse::<u32>()?))
+            .unwrap_err()
+            .downcast::<ParseError>()
+            .unwrap();
+        assert_eq!((err.line, err.column), (3, 1));
+        assert_eq!(err.message, "invalid digit found in string");
+    }
+
+    #[derive(Debug, PartialEq, FromRegex)]
+    enum Instruction {
+        #[regex(r"mul\((\d{1,3}),(\d{1,3})\)")]
+        Mul(u32, u32),
+        #[regex(r"do\(\)")]
+        Do,
+        #[regex(r"don't\(\)")]
+        Dont,
+    }
+
+    #[test]
+    fn test_find_all() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        assert_eq!(
+            find_all::<Instruction>(input).unwrap(),
+            vec![
+                Instruction::Mul(2, 4),
+                Instruction::Dont,
+                Instruction::Mul(5, 5),
+                Instruction::Mul(11, 8),
+                Instruction::Do,
+                Instruction::Mul(8, 5),
+            ]
+        );
+        assert!(find_all::<Instruction>("no instructions")
+            .unwrap()
+            .is_empty());
+        assert!(find_all::<Instruction>("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_state() {
+        let regex = Regex::new(r"\d").unwrap();
+        let mut state = FindState::default();
+        assert_eq!(state.next_match(1, &regex, "a1b2", 0), Some(1..2));
+        assert_eq!(state.next_match(1, &regex, "a1b2", 1), Some(1..2));
+        assert_eq!(state.next_match(1, &regex, "a1b2", 2), Some(3..4));
+        assert_eq!(state.next_match(1, &regex, "a1b2", 4), None);
+        assert_eq!(state.next_match(0, &regex, "a1b2", 0), Some(1..2));
+    }
+
+    #[derive(Debug, PartialEq, FromRegex)]
+    #[regex(r"<(\d+)>")]
+    struct Tag(u8);
+
+    #[test]
+    fn test_find_all_error() {
+        let err = find_all::<Tag>("<1> <2>\n<300>")
+            .unwrap_err()
+            .downcast::<ParseError>()
+            .unwrap();
+        assert_eq!((err.line, err.column), (2, 2));
+        assert_eq!(
+            err.message,
+            "group 1: \"300\": number too large to fit in target type"
+        );
+    }
+
+    #[derive(Debug, PartialEq, FromRegex)]
+    #[regex(r"(a*)")]
+    struct Run(#[parse_with(|s: &str| Ok::<_, &str>(s.len()))] usize);
+
+    #[test]
+    fn test_find_all_empty_matches() {
+        assert_eq!(
+            find_all::<Run>("aabä").unwrap(),
+            vec![Run(2), Run(0), Run(0), Run(0)]
+        );
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(integers::<i32>("p=0,4 v=3,-3").unwrap(), vec![0, 4, 3, -3]);
+        assert_eq!(integers::<i64>("1-3").unwrap(), vec![1, -3]);
+        assert!(integers::<u32>("none").unwrap().is_empty());
+
+        let err = integers::<u32>("x=5, y=-2")
+            .unwrap_err()
+            .downcast::<ParseError>()
+            .unwrap();
+        assert_eq!(err.column, 8);
+        assert_eq!(err.message, "\"-2\": invalid digit found in string");
+    }
+
+    #[test]
+    fn test_integers_ranges() {
+        // the dash in a range is taken as a minus sign...
+        assert_eq!(integers::<i32>("2-4,6-8").unwrap(), vec![2, -4, 6, -8]);
+        assert!(integers::<u32>("2-4,6-8").is_err());
+
+        // ... unless asked not to
+        assert_eq!(
+            unsigned_integers::<u32>("2-4,6-8").unwrap(),
+            vec![2, 4, 6, 8]
+        );
+        assert_eq!(unsigned_integers::<i32>("x=-5").unwrap(), vec![5]);
+    }
+
+    #[test]
+    fn test_integer_array() {
+        let [x, y] = integer_array::<i32, 2>("Sensor at x=2, y=-18").unwrap();
+        assert_eq!((x, y), (2, -18));
+
+        let err = integer_array::<i32, 3>("1 2").unwrap_err();
+        assert!(err.to_string().contains("expected 3 integers, found 2"));
+    }
+
+    #[test]
+    fn test_list() {
+        assert_eq!(list::<u32>("1, 2,3", ",").unwrap(), vec![1, 2, 3]);
+        assert_eq!(list::<u32>(" 7  6 4 ", " ").unwrap(), vec![7, 6, 4]);
+        assert_eq!(
+            list::<String>("a -> b -> c", "->").unwrap(),
+            vec!["a", "b", "c"]
+        );
+        assert!(list::<u32>("", ",").unwrap().is_empty());
+        assert!(list::<u32>("  ", " ").unwrap().is_empty());
+
+        let err = list::<u32>("1,2,x,4", ",")
+            .unwrap_err()
+            .downcast::<ParseError>()
+            .unwrap();
+        assert_eq!(err.column, 5);
+        assert_eq!(err.message, "item 3: \"x\": invalid digit found in string");
+    }
+
+    #[test]
+    fn test_sections() {
+        assert_eq!(sections("a\nb\n\nc\n"), vec!["a\nb", "c"]);
+        assert_eq!(sections("a\r\nb\r\n\r\nc\r\n"), vec!["a\r\nb", "c"]);
+        assert_eq!(sections("\n\na\n  \n\t\n\nb  \n\n\n"), vec!["a", "b  "]);
+        assert!(sections("").is_empty());
+        assert!(sections(" \n\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_sections() {
+        let input = "Alice is 42 years old\nBob is 7 years old\n\n1,2,3\n";
+        let (people, numbers) = parse_sections(
+            input,
+            (
+                |s| parse_lines(s, str::parse::<Person>),
+                |s| list::<u32>(s, ","),
+            ),
+        )
+        .unwrap();
+        assert_eq!(people.len(), 2);
+        assert_eq!(numbers, vec![1, 2, 3]);
+
+        // parsers may borrow from the input
+        let (first,) = parse_sections("\n  \nabc\n", (|s| Ok(s),)).unwrap();
+        assert_eq!(first, "abc");
+
+        let err = parse_sections(input, (|s| Ok(s),)).unwrap_err();
+        assert_eq!(err.to_string(), "expected 1 sections, found 2");
+    }
+
+    #[test]
+    fn test_parse_sections_errors() {
+        // a ParseError is moved to the right line of the whole input
+        let input = "1\n\nAlice is 42 years old\r\n\r\nBob is x years old\n";
+        let err = parse_sections(
+            input,
+            (
+                |s| list::<u32>(s, ","),
+                |s| parse_lines(s, str::parse::<Person>),
+                |s| parse_lines(s, str::parse::<Person>),
+            ),
+        )
+        .unwrap_err()
+        .downcast::<ParseError>()
+        .unwrap();
+        assert_eq!((err.line, err.column), (5, 1));
+        assert_eq!(
+            err.message,
+            "section 3: regex not found in \"Bob is x years old\""
+        );
+
+        // other errors point at the start of the section
+        let err = parse_sections(
+            "1\n\n2\n",
+            (|s| list::<u32>(s, ","), |_| Err::<(), _>(anyhow!("nope"))),
+        )
+        .unwrap_err()
+        .downcast::<ParseError>()
+        .unwrap();
+        assert_eq!((err.line, err.column), (3, 1));
+        assert_eq!(err.message, "section 2: nope");
+    }
 
     #[test]
     fn test_parse_grid() {
         let input = "ab\ncd\nef\n";
-        let bb = parse_grid(&input, |xy, ch| {
+        let bb = parse_grid(input, |xy, ch| {
             let expected = match ch {
                 'a' => (0, 0).into(),
                 'b' => (1, 0).into(),
@@ -353,21 +833,104 @@ mod tests {
     #[test]
     fn test_parse_grid_empty() {
         let input = "";
-        let bb = parse_grid(&input, |_, _| Ok(())).unwrap();
+        let bb = parse_grid(input, |_, _| Ok(())).unwrap();
         assert_eq!(bb, BoundingBox::new((0, 0).into(), (0, 0).into()));
     }
 
     #[test]
     fn test_parse_grid_different_length_lines() {
         let input = "..\n...\n";
-        assert!(parse_grid(&input, |_, _| Ok(())).is_err());
+        let err = parse_grid(input, |_, _| Ok(()))
+            .unwrap_err()
+            .downcast::<ParseError>()
+            .unwrap();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.message, "expected 2 columns, found 3");
+    }
+
+    #[test]
+    fn test_parse_grid_callback_error() {
+        let input = "..\n.#\n";
+        let err = parse_grid(input, |_, ch| {
+            anyhow::ensure!(ch == '.', "unexpected '{ch}'");
+            Ok(())
+        })
+        .unwrap_err()
+        .downcast::<ParseError>()
+        .unwrap();
+        assert_eq!((err.line, err.column), (2, 2));
+        assert_eq!(err.message, "unexpected '#'");
     }
 
     #[test]
     fn test_parse_grid_trailing_newline_does_not_matter() {
         let input = "...\n...\n...\n...\n";
-        let bb1 = parse_grid(&input, |_, _| Ok(())).unwrap();
-        let bb2 = parse_grid(&input.trim(), |_, _| Ok(())).unwrap();
+        let bb1 = parse_grid(input, |_, _| Ok(())).unwrap();
+        let bb2 = parse_grid(input.trim(), |_, _| Ok(())).unwrap();
         assert_eq!(bb1, bb2);
     }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Tile {
+        Wall,
+        Open,
+    }
+
+    impl TryFrom<char> for Tile {
+        type Error = &'static str;
+
+        fn try_from(ch: char) -> Result<Self, Self::Error> {
+            match ch {
+                '#' => Ok(Tile::Wall),
+                '.' | 'S' | 'E' => Ok(Tile::Open),
+                _ => Err("not a tile"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_grid_as() {
+        let input = "#S#\n..E\n";
+        let (cells, bounds): (HashMap<XY, Tile>, _) = parse_grid_as(input).unwrap();
+        assert_eq!(bounds, BoundingBox::new((0, 0).into(), (2, 1).into()));
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells[&(0, 0).into()], Tile::Wall);
+        assert_eq!(cells[&(2, 1).into()], Tile::Open);
+
+        let (cells, _): (Vec<(XY, Tile)>, _) = parse_grid_as(input).unwrap();
+        assert_eq!(cells[1], ((1, 0).into(), Tile::Open));
+
+        let err = parse_grid_as::<Tile, Vec<_>>("##\n#x")
+            .unwrap_err()
+            .downcast::<ParseError>()
+            .unwrap();
+        assert_eq!((err.line, err.column), (2, 2));
+        assert_eq!(err.message, "'x': not a tile");
+    }
+
+    #[test]
+    fn test_grid_markers() {
+        let [start, end] = grid_markers("#S#\n..E\n", ['S', 'E']).unwrap();
+        assert_eq!(start, (1, 0).into());
+        assert_eq!(end, (2, 1).into());
+
+        let err = grid_markers("#S#\n..S\n", ['S'])
+            .unwrap_err()
+            .downcast::<ParseError>()
+            .unwrap();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.message, "duplicate 'S', first seen at 1,0");
+
+        let err = grid_markers("#S#\n...\n", ['S', 'E']).unwrap_err();
+        assert_eq!(err.to_string(), "missing 'E'");
+    }
+
+    #[test]
+    fn test_grid_positions() {
+        let positions = grid_positions("a.b\nb.a\n", "abc").unwrap();
+        assert_eq!(positions.len(), 3);
+        assert_eq!(positions[&'a'], vec![(0, 0).into(), (2, 1).into()]);
+        assert_eq!(positions[&'b'], vec![(2, 0).into(), (0, 1).into()]);
+        assert!(positions[&'c'].is_empty());
+    }
 }
@@ -0,0 +1,214 @@
+use anyhow::{ensure, Result};
+use rustc_hash::FxHashSet;
+
+use crate::{BoundingBox, Orientation, XY};
+
+/// A 2D pattern of characters to search for in a grid. Wildcard cells match anything, including
+/// positions outside the grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    width: i32,
+    height: i32,
+    cells: Vec<(XY, char)>,
+}
+
+/// A pattern found in a grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// Top left corner of the (reoriented) pattern.
+    pub position: XY,
+    pub orientation: Orientation,
+    /// Grid coordinates of the pattern's non-wildcard cells.
+    pub cells: Vec<XY>,
+}
+
+impl Pattern {
+    /// Create a pattern from lines of text; the wildcard character matches anything. Rows and
+    /// columns of only wildcards along the edges are dropped: they match everywhere anyway.
+    pub fn parse(s: &str, wildcard: char) -> Result<Self> {
+        let mut cells: Vec<(XY, char)> = vec![];
+        for (y, line) in s.lines().enumerate() {
+            for (x, ch) in line.chars().enumerate() {
+                if ch != wildcard {
+                    cells.push(((x as i32, y as i32).into(), ch));
+                }
+            }
+        }
+        ensure!(!cells.is_empty(), "pattern only contains wildcards");
+        let min_x = cells.iter().map(|(xy, _)| xy.x).min().unwrap();
+        let min_y = cells.iter().map(|(xy, _)| xy.y).min().unwrap();
+        for (xy, _) in cells.iter_mut() {
+            *xy = (xy.x - min_x, xy.y - min_y).into();
+        }
+        let width = cells.iter().map(|(xy, _)| xy.x).max().unwrap() + 1;
+        let height = cells.iter().map(|(xy, _)| xy.y).max().unwrap() + 1;
+        Ok(Pattern {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn reoriented(&self, orientation: Orientation) -> Pattern {
+        let (width, height) = orientation.size(self.width, self.height);
+        let mut cells: Vec<_> = self
+            .cells
+            .iter()
+            .map(|(xy, ch)| (orientation.apply(*xy, self.width, self.height), *ch))
+            .collect();
+        cells.sort();
+        Pattern {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// All distinct orientations of the pattern. Symmetric patterns have fewer than 8, so that a
+    /// match is never reported twice.
+    pub fn orientations(&self) -> Vec<(Orientation, Pattern)> {
+        let mut out: Vec<(Orientation, Pattern)> = vec![];
+        for o in Orientation::ALL {
+            let p = self.reoriented(o);
+            if !out.iter().any(|(_, q)| *q == p) {
+                out.push((o, p));
+            }
+        }
+        out
+    }
+
+    /// Check if the pattern, as is, matches the grid with its top left corner at xy.
+    pub fn matches_at(&self, xy: XY, get: impl Fn(&XY) -> Option<char>) -> bool {
+        self.cells
+            .iter()
+            .all(|(offset, ch)| get(&(xy + offset)) == Some(*ch))
+    }
+
+    /// Find all matches, in all orientations, in a grid with the given bounds.
+    pub fn find_in(&self, bounds: &BoundingBox, get: impl Fn(&XY) -> Option<char>) -> Vec<Match> {
+        let (top_left, bottom_right) = (bounds.top_left(), bounds.bottom_right());
+        let mut matches = vec![];
+        for (orientation, pattern) in self.orientations() {
+            for y in top_left.y - pattern.height + 1..=bottom_right.y {
+                for x in top_left.x - pattern.width + 1..=bottom_right.x {
+                    let position = (x, y).into();
+                    if pattern.matches_at(position, &get) {
+                        matches.push(Match {
+                            position,
+                            orientation,
+                            cells: pattern.cells.iter().map(|(xy, _)| position + xy).collect(),
+                        });
+                    }
+                }
+            }
+        }
+        matches
+    }
+
+    /// Find all matches, in all orientations, in a grid drawn as lines of text.
+    pub fn find(&self, grid: &str) -> Vec<Match> {
+        let rows: Vec<Vec<char>> = grid.lines().map(|line| line.chars().collect()).collect();
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+        let bounds = BoundingBox::new((0, 0).into(), (width - 1, rows.len() as i32 - 1).into());
+        self.find_in(&bounds, |xy| {
+            let y = usize::try_from(xy.y).ok()?;
+            let x = usize::try_from(xy.x).ok()?;
+            rows.get(y)?.get(x).copied()
+        })
+    }
+}
+
+/// All grid cells covered by at least one of the matches.
+pub fn covered_cells(matches: &[Match]) -> FxHashSet<XY> {
+    matches
+        .iter()
+        .flat_map(|m| m.cells.iter().copied())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let p = Pattern::parse("M.S\n.A.", '.').unwrap();
+        assert_eq!((p.width(), p.height()), (3, 2));
+        assert_eq!(p.cells.len(), 3);
+        assert!(Pattern::parse("..\n..", '.').is_err());
+
+        let p = Pattern::parse("...\n.#.\n..#", '.').unwrap();
+        assert_eq!((p.width(), p.height()), (2, 2));
+        assert_eq!(p, Pattern::parse("#.\n.#", '.').unwrap());
+    }
+
+    #[test]
+    fn test_orientations() {
+        assert_eq!(Pattern::parse("ab", '.').unwrap().orientations().len(), 4);
+        assert_eq!(Pattern::parse("a", '.').unwrap().orientations().len(), 1);
+        assert_eq!(
+            Pattern::parse("ab\nc.", '.').unwrap().orientations().len(),
+            8
+        );
+        let p = Pattern::parse("M.S\n.A.\nM.S", '.').unwrap();
+        assert_eq!(p.orientations().len(), 4);
+    }
+
+    #[test]
+    fn test_find() {
+        let grid = "\
+.X..
+XAX.
+.X..
+....";
+        let p = Pattern::parse("X\nA", '.').unwrap();
+        let mut matches = p.find(grid);
+        matches.sort_by_key(|m| m.position);
+        assert_eq!(matches.len(), 4);
+        assert_eq!(matches[0].position, (0, 1).into());
+        assert_eq!(matches[0].orientation, Orientation::R270);
+        assert_eq!(matches[0].cells, vec![(0, 1).into(), (1, 1).into()]);
+        assert_eq!(covered_cells(&matches).len(), 5);
+    }
+
+    #[test]
+    fn test_find_wildcards_outside_grid() {
+        // inner wildcards may hang over the edge of the grid
+        let p = Pattern::parse("#?\n?#", '?').unwrap();
+        let matches = p.find("#");
+        assert!(matches.is_empty());
+        let matches = p.find("#.\n.#");
+        assert_eq!(matches.len(), 1);
+
+        // wildcard rows along the edge are trimmed, so the match is not reported once for the
+        // row being above the grid (R0) and once for it being below (R180)
+        let p = Pattern::parse("???\n#.#", '?').unwrap();
+        assert_eq!((p.width(), p.height()), (3, 1));
+        let matches = p.find("#.#");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].position, (0, 0).into());
+        assert_eq!(
+            matches[0].cells,
+            vec![(0, 0).into(), (1, 0).into(), (2, 0).into()]
+        );
+    }
+
+    #[test]
+    fn test_find_in() {
+        let grid: std::collections::HashMap<XY, char> =
+            [((10, 10).into(), '#'), ((11, 11).into(), '#')].into();
+        let bounds = BoundingBox::new((10, 10).into(), (11, 11).into());
+        let p = Pattern::parse("#.\n.#", '.').unwrap();
+        let matches = p.find_in(&bounds, |xy| grid.get(xy).copied());
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].position, (10, 10).into());
+    }
+}
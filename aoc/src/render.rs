@@ -0,0 +1,373 @@
+use anyhow::{bail, ensure, Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{BoundingBox, XY};
+
+/// A colour, as red, green and blue components.
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+
+/// Turns grids into frames: each cell becomes a scale x scale square of pixels, coloured by the
+/// palette function. Cells missing from a sparse grid are drawn in the background colour.
+pub struct Renderer<T> {
+    palette: Box<dyn Fn(&T) -> Rgb>,
+    background: Rgb,
+    scale: u32,
+    bounds: Option<BoundingBox>,
+}
+
+impl<T> Renderer<T> {
+    pub fn new(palette: impl Fn(&T) -> Rgb + 'static) -> Self {
+        Renderer {
+            palette: Box::new(palette),
+            background: BLACK,
+            scale: 1,
+            bounds: None,
+        }
+    }
+
+    pub fn with_background(mut self, background: Rgb) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: u32) -> Self {
+        assert!(scale > 0);
+        self.scale = scale;
+        self
+    }
+
+    /// Only draw the cells inside bounds. Without this, the frame is just large enough to fit all
+    /// cells, which makes the frame size change between steps of a simulation.
+    pub fn with_bounds(mut self, bounds: BoundingBox) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Render a sparse grid, e.g. the items of a HashMap<XY, T>.
+    pub fn cells(&self, cells: impl IntoIterator<Item = (XY, T)>) -> Frame {
+        let cells: Vec<_> = cells.into_iter().collect();
+        let bounds = match &self.bounds {
+            Some(bounds) => bounds.clone(),
+            None => match BoundingBox::from_points(cells.iter().map(|(xy, _)| *xy)) {
+                Some(bounds) => bounds,
+                None => return Frame::new(0, 0, self.background),
+            },
+        };
+        let (top_left, bottom_right) = (bounds.top_left(), bounds.bottom_right());
+        let width = (bottom_right.x - top_left.x + 1) as u32;
+        let height = (bottom_right.y - top_left.y + 1) as u32;
+        let mut frame = Frame::new(width * self.scale, height * self.scale, self.background);
+        for (xy, value) in cells.iter() {
+            if !bounds.contains(xy) {
+                continue;
+            }
+            let x = (xy.x - top_left.x) as u32;
+            let y = (xy.y - top_left.y) as u32;
+            frame.fill_square(x, y, self.scale, (self.palette)(value));
+        }
+        frame
+    }
+
+    /// Render a dense grid, stored as rows of cells.
+    pub fn rows<R: AsRef<[T]>>(&self, rows: &[R]) -> Frame {
+        let (x0, y0, width, height) = match &self.bounds {
+            Some(bounds) => {
+                let (a, b) = (bounds.top_left(), bounds.bottom_right());
+                (a.x, a.y, (b.x - a.x + 1) as u32, (b.y - a.y + 1) as u32)
+            }
+            None => {
+                let width = rows.iter().map(|row| row.as_ref().len()).max();
+                (0, 0, width.unwrap_or(0) as u32, rows.len() as u32)
+            }
+        };
+        let mut frame = Frame::new(width * self.scale, height * self.scale, self.background);
+        for y in 0..height {
+            for x in 0..width {
+                let Some(value) = usize::try_from(y as i32 + y0)
+                    .ok()
+                    .and_then(|y| rows.get(y))
+                    .zip(usize::try_from(x as i32 + x0).ok())
+                    .and_then(|(row, x)| row.as_ref().get(x))
+                else {
+                    continue;
+                };
+                frame.fill_square(x, y, self.scale, (self.palette)(value));
+            }
+        }
+        frame
+    }
+}
+
+impl Renderer<char> {
+    /// Render a grid drawn as lines of text.
+    pub fn text(&self, s: &str) -> Frame {
+        let rows: Vec<Vec<char>> = s.lines().map(|line| line.chars().collect()).collect();
+        self.rows(&rows)
+    }
+}
+
+impl Renderer<bool> {
+    /// Render a set of points: lit points in the foreground colour, everything else in the
+    /// background colour.
+    pub fn points(&self, points: impl IntoIterator<Item = XY>) -> Frame {
+        self.cells(points.into_iter().map(|xy| (xy, true)))
+    }
+}
+
+impl Default for Renderer<bool> {
+    fn default() -> Self {
+        Renderer::new(|lit| if *lit { WHITE } else { BLACK })
+    }
+}
+
+/// A rendered image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    width: u32,
+    height: u32,
+    pixels: Vec<Rgb>,
+}
+
+impl Frame {
+    pub fn new(width: u32, height: u32, background: Rgb) -> Self {
+        Frame {
+            width,
+            height,
+            pixels: vec![background; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Rgb {
+        assert!(x < self.width && y < self.height);
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Rgb) {
+        assert!(x < self.width && y < self.height);
+        self.pixels[(y * self.width + x) as usize] = color;
+    }
+
+    fn fill_square(&mut self, x: u32, y: u32, size: u32, color: Rgb) {
+        for dy in 0..size {
+            for dx in 0..size {
+                self.set_pixel(x * size + dx, y * size + dy, color);
+            }
+        }
+    }
+
+    fn rgb_bytes(&self) -> Vec<u8> {
+        self.pixels.iter().flatten().copied().collect()
+    }
+
+    pub fn write_png(&self, w: impl Write) -> Result<()> {
+        ensure!(self.width > 0 && self.height > 0, "empty frame");
+        let mut encoder = png::Encoder::new(w, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.rgb_bytes())?;
+        Ok(())
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let file = File::create(path).with_context(|| format!("{}", path.display()))?;
+        self.write_png(BufWriter::new(file))
+    }
+}
+
+enum Output {
+    Gif {
+        path: PathBuf,
+        delay: u16,
+        encoder: Option<(u32, u32, gif::Encoder<BufWriter<File>>)>,
+    },
+    Directory {
+        path: PathBuf,
+        count: usize,
+    },
+}
+
+/// Collects the frames of a simulation, one frame per call to record, either as an animated GIF
+/// or as a directory of numbered PNG files (0000.png, 0001.png, ...).
+pub struct Recorder {
+    output: Output,
+}
+
+impl Recorder {
+    /// Write an animated GIF, showing each frame for delay_ms milliseconds. The file is created
+    /// when the first frame is recorded.
+    pub fn gif(path: impl AsRef<Path>, delay_ms: u32) -> Self {
+        Recorder {
+            output: Output::Gif {
+                path: path.as_ref().to_path_buf(),
+                delay: (delay_ms / 10).try_into().unwrap_or(u16::MAX),
+                encoder: None,
+            },
+        }
+    }
+
+    /// Write each frame as a separate PNG file in the directory path, which is created if needed.
+    pub fn directory(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        std::fs::create_dir_all(path).with_context(|| format!("{}", path.display()))?;
+        Ok(Recorder {
+            output: Output::Directory {
+                path: path.to_path_buf(),
+                count: 0,
+            },
+        })
+    }
+
+    pub fn record(&mut self, frame: &Frame) -> Result<()> {
+        match &mut self.output {
+            Output::Gif {
+                path,
+                delay,
+                encoder,
+            } => {
+                if encoder.is_none() {
+                    let (Ok(width), Ok(height)) =
+                        (u16::try_from(frame.width), u16::try_from(frame.height))
+                    else {
+                        bail!("frame too large for GIF");
+                    };
+                    let file =
+                        File::create(&path).with_context(|| format!("{}", path.display()))?;
+                    let mut e = gif::Encoder::new(BufWriter::new(file), width, height, &[])?;
+                    e.set_repeat(gif::Repeat::Infinite)?;
+                    *encoder = Some((frame.width, frame.height, e));
+                }
+                let (width, height, encoder) = encoder.as_mut().unwrap();
+                ensure!(
+                    frame.width == *width && frame.height == *height,
+                    "frame size {}x{} differs from first frame {}x{}",
+                    frame.width,
+                    frame.height,
+                    width,
+                    height
+                );
+                let mut gif_frame = gif::Frame::from_rgb_speed(
+                    frame.width as u16,
+                    frame.height as u16,
+                    &frame.rgb_bytes(),
+                    10,
+                );
+                gif_frame.delay = *delay;
+                encoder.write_frame(&gif_frame)?;
+            }
+            Output::Directory { path, count } => {
+                frame.save_png(path.join(format!("{:04}.png", count)))?;
+                *count += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgb = [255, 0, 0];
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("aoc-render-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_points() {
+        let frame = Renderer::default().points([(-1, 5).into(), (1, 6).into()]);
+        assert_eq!((frame.width(), frame.height()), (3, 2));
+        assert_eq!(frame.pixel(0, 0), WHITE);
+        assert_eq!(frame.pixel(1, 0), BLACK);
+        assert_eq!(frame.pixel(2, 1), WHITE);
+
+        let frame = Renderer::default()
+            .with_bounds(BoundingBox::new((0, 0).into(), (3, 3).into()))
+            .points([(1, 1).into(), (9, 9).into()]);
+        assert_eq!((frame.width(), frame.height()), (4, 4));
+        assert_eq!(frame.pixel(1, 1), WHITE);
+        assert_eq!(frame.pixel(3, 3), BLACK);
+
+        let frame = Renderer::default().points([]);
+        assert_eq!((frame.width(), frame.height()), (0, 0));
+        assert!(frame.write_png(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_cells() {
+        let renderer = Renderer::new(|n: &u8| [*n, 0, 0]).with_background(WHITE);
+        let frame = renderer.cells([((0, 0).into(), 10), ((2, 0).into(), 20)]);
+        assert_eq!(frame.pixel(0, 0), [10, 0, 0]);
+        assert_eq!(frame.pixel(1, 0), WHITE);
+        assert_eq!(frame.pixel(2, 0), [20, 0, 0]);
+    }
+
+    #[test]
+    fn test_text_and_scale() {
+        let renderer =
+            Renderer::new(|ch: &char| if *ch == '#' { RED } else { BLACK }).with_scale(2);
+        let frame = renderer.text("#.\n.#");
+        assert_eq!((frame.width(), frame.height()), (4, 4));
+        assert_eq!(frame.pixel(0, 0), RED);
+        assert_eq!(frame.pixel(1, 1), RED);
+        assert_eq!(frame.pixel(2, 1), BLACK);
+        assert_eq!(frame.pixel(3, 3), RED);
+
+        let renderer = Renderer::new(|ch: &char| if *ch == '#' { RED } else { BLACK })
+            .with_bounds(BoundingBox::new((1, 0).into(), (2, 1).into()));
+        let frame = renderer.text("#.\n.#");
+        assert_eq!((frame.width(), frame.height()), (2, 2));
+        assert_eq!(frame.pixel(0, 1), RED);
+        assert_eq!(frame.pixel(1, 1), BLACK);
+    }
+
+    #[test]
+    fn test_write_png() {
+        let frame = Renderer::default().points([(0, 0).into()]);
+        let mut bytes = Vec::new();
+        frame.write_png(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn test_recorder() {
+        let renderer =
+            Renderer::default().with_bounds(BoundingBox::new((0, 0).into(), (2, 2).into()));
+
+        let dir = temp_path("frames");
+        let mut recorder = Recorder::directory(&dir).unwrap();
+        for i in 0..3 {
+            recorder.record(&renderer.points([(i, i).into()])).unwrap();
+        }
+        assert!(dir.join("0000.png").exists());
+        assert!(dir.join("0002.png").exists());
+        assert!(!dir.join("0003.png").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let path = temp_path("animation.gif");
+        let mut recorder = Recorder::gif(&path, 100);
+        for i in 0..3 {
+            recorder.record(&renderer.points([(i, i).into()])).unwrap();
+        }
+        assert!(recorder.record(&Frame::new(1, 1, BLACK)).is_err());
+        drop(recorder);
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..6], b"GIF89a");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
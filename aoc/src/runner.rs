@@ -1,53 +1,95 @@
+use crate::journal::{part_of, Check, Journal};
 use anyhow::{bail, Result};
 use atty::Stream;
+use std::cell::RefCell;
 use std::io::Write;
 use std::ops::RangeBounds;
+use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 static GLOBAL_COUNT: AtomicUsize = AtomicUsize::new(1);
 
+// With AOC_BENCHMARK set, stop timing a variant once its runs add up to this much, or after
+// MAX_RUNS runs.
+const BENCHMARK_BUDGET: Duration = Duration::from_millis(100);
+const MAX_RUNS: usize = 10;
+
+thread_local! {
+    // Timings from `variants`, printed with the next result.
+    static TIMINGS: RefCell<Vec<(String, Duration)>> = const { RefCell::new(Vec::new()) };
+}
+
 const ANSI_GREEN: &str = "\x1B[32m";
 const ANSI_RED: &str = "\x1B[1;31m";
 const ANSI_RESET: &str = "\x1B[0m";
 const ANSI_CYAN: &str = "\x1B[36m";
 const ANSI_YELLOW: &str = "\x1B[33m";
 
-pub fn run<T>(called_from: &str, func: impl FnOnce() -> Result<T>) -> Result<()>
+/// Run one part without an expected value. The answer is checked against the guess journal in
+/// `manifest_dir`, if any: the accepted answer is OK, answers known to be wrong fail. Which part
+/// `expr` solves is told by the function it calls, see [`part_of`]; if it can't be told, the
+/// answer is not checked.
+pub fn run<T>(
+    called_from: &str,
+    manifest_dir: &str,
+    expr: &str,
+    func: impl FnOnce() -> Result<T>,
+) -> Result<()>
 where
     T: std::fmt::Display,
     T: PartialOrd,
 {
-    print_label(called_from);
-    match func() {
-        Ok(value) => {
-            print_maybe(&value.to_string());
-            Ok(())
+    let part = part_of(expr);
+    print_label(called_from, part);
+    let value = match func() {
+        Ok(value) => value.to_string(),
+        Err(e) => {
+            print_error(&format!("{:#}", e));
+            return Err(e);
         }
+    };
+    let Some(part) = part else {
+        print_maybe(&value);
+        return Ok(());
+    };
+    let journal = match Journal::load(&Path::new(manifest_dir).join("journal.txt")) {
+        Ok(journal) => journal,
         Err(e) => {
-            print_error(&e.to_string());
-            Err(e)
+            print_error(&value);
+            return Err(e);
+        }
+    };
+    match journal.check(part, &value) {
+        Check::Correct => print_ok(&value),
+        Check::Unknown => print_maybe(&value),
+        Check::Wrong(reason) => {
+            print_error(&value);
+            bail!("answer {} is wrong: {}", value, reason);
         }
     }
+    Ok(())
 }
 
-pub fn run_with_expected_value<T>(
+pub fn run_with_expected_value<T, E>(
     called_from: &str,
     func: impl FnOnce() -> Result<T>,
-    expected_value: T,
+    expected_value: E,
 ) -> Result<()>
 where
     T: std::fmt::Display,
-    T: PartialEq,
+    T: PartialEq<E>,
+    E: std::fmt::Display,
 {
-    print_label(called_from);
+    print_label(called_from, None);
     let value = match func() {
         Ok(value) => value,
         Err(e) => {
-            print_error(&e.to_string());
+            print_error(&format!("{:#}", e));
             return Err(e);
         }
     };
-    if expected_value != value {
+    if value != expected_value {
         let msg = format!("{} \u{2260} {}", value, expected_value);
         print_error(&msg);
         bail!(format!("answer does not match expected value: {}", msg));
@@ -67,11 +109,11 @@ where
     R: RangeBounds<T>,
     R: std::fmt::Debug,
 {
-    print_label(called_from);
+    print_label(called_from, None);
     let value = match func() {
         Ok(value) => value,
         Err(e) => {
-            print_error(&e.to_string());
+            print_error(&format!("{:#}", e));
             return Err(e);
         }
     };
@@ -93,11 +135,11 @@ where
     T: std::fmt::Display,
     T: PartialEq,
 {
-    print_label(called_from);
+    print_label(called_from, None);
     let value = match func() {
         Ok(value) => value,
         Err(e) => {
-            print_error(&e.to_string());
+            print_error(&format!("{:#}", e));
             return Err(e);
         }
     };
@@ -109,8 +151,92 @@ where
     Ok(())
 }
 
-fn print_label(called_from: &str) {
-    let part_no = GLOBAL_COUNT.fetch_add(1, Ordering::SeqCst);
+/// A named implementation of a part, for [`variants`].
+pub type Variant<'a, T> = (&'a str, &'a dyn Fn(&str) -> Result<T>);
+
+#[doc(hidden)]
+pub fn variant<'a, T>(name: &'a str, func: &'a dyn Fn(&str) -> Result<T>) -> Variant<'a, T> {
+    (name, func)
+}
+
+/// Run several implementations of the same part on `input`, and fail unless they all agree. Each
+/// variant is timed, and the timings are printed with the result by the `run!` this is called
+/// from; set `AOC_BENCHMARK` to time the fastest of a few runs instead of a single one. Use the [`variants!`](crate::variants) macro to name the
+/// variants after the functions.
+pub fn variants<T>(input: &str, variants: &[Variant<T>]) -> Result<T>
+where
+    T: std::fmt::Display,
+    T: PartialEq,
+{
+    let mut results: Vec<(&str, T)> = vec![];
+    for (name, func) in variants {
+        let (value, elapsed) =
+            time(|| func(input)).map_err(|e| e.context(format!("variant {}", name)))?;
+        TIMINGS.with(|t| t.borrow_mut().push((name.to_string(), elapsed)));
+        results.push((name, value));
+    }
+    let Some((_, first)) = results.first() else {
+        bail!("no variants");
+    };
+    if results.iter().any(|(_, value)| value != first) {
+        let msg = results
+            .iter()
+            .map(|(name, value)| format!("{} = {}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+        bail!("variants disagree: {}", msg);
+    }
+    Ok(results.swap_remove(0).1)
+}
+
+// Run `func` once, or with AOC_BENCHMARK set until BENCHMARK_BUDGET or MAX_RUNS is reached, and
+// return the result of the first run and the duration of the fastest.
+fn time<T>(func: impl Fn() -> Result<T>) -> Result<(T, Duration)> {
+    let start = Instant::now();
+    let value = func()?;
+    let mut fastest = start.elapsed();
+    let mut total = fastest;
+    let runs = if is_benchmark() { MAX_RUNS } else { 1 };
+    for _ in 1..runs {
+        if total >= BENCHMARK_BUDGET {
+            break;
+        }
+        let start = Instant::now();
+        func()?;
+        let elapsed = start.elapsed();
+        fastest = fastest.min(elapsed);
+        total += elapsed;
+    }
+    Ok((value, fastest))
+}
+
+fn print_timings() {
+    let timings = TIMINGS.with(|t| std::mem::take(&mut *t.borrow_mut()));
+    let Some((_, reference)) = timings.first() else {
+        return;
+    };
+    let width = timings
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    for (i, (name, elapsed)) in timings.iter().enumerate() {
+        let reference = reference.as_secs_f64().max(f64::EPSILON);
+        let elapsed_secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        let comparison = match i {
+            0 => String::new(),
+            _ if elapsed_secs <= reference => format!("{:.2}x faster", reference / elapsed_secs),
+            _ => format!("{:.2}x slower", elapsed_secs / reference),
+        };
+        let line = format!("    {:width$} {:>12.3?} {}", name, elapsed, comparison);
+        println!("{}", line.trim_end());
+    }
+}
+
+// The label says `part`, if known, else how many parts have run so far.
+fn print_label(called_from: &str, part: Option<u32>) {
+    let count = GLOBAL_COUNT.fetch_add(1, Ordering::SeqCst);
+    let part_no = part.map_or(count, |part| part as usize);
     let label = if called_from.len() >= 7 {
         format!(
             "aoc-{}-{} part {}",
@@ -134,6 +260,7 @@ fn print_error(msg: &str) {
     } else {
         println!("{:20} [FAIL]", msg);
     }
+    print_timings();
 }
 
 fn print_ok(msg: &str) {
@@ -145,6 +272,7 @@ fn print_ok(msg: &str) {
     } else {
         println!("{:20} [ OK ]", msg);
     }
+    print_timings();
 }
 
 fn print_maybe(msg: &str) {
@@ -156,8 +284,58 @@ fn print_maybe(msg: &str) {
     } else {
         println!("{:20} [ ?? ]", msg);
     }
+    print_timings();
+}
+
+fn is_benchmark() -> bool {
+    let mode = std::env::var("AOC_BENCHMARK").unwrap_or_default();
+    !mode.is_empty() && mode != "0"
 }
 
 fn is_tty() -> bool {
     atty::is(Stream::Stdout)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(input: &str) -> Result<usize> {
+        Ok(input.lines().count())
+    }
+
+    fn count_newlines(input: &str) -> Result<usize> {
+        Ok(input.matches('\n').count())
+    }
+
+    fn fail(_: &str) -> Result<usize> {
+        bail!("no solution")
+    }
+
+    #[test]
+    fn test_variants() {
+        let input = "a\nb\nc\n";
+        assert_eq!(
+            variants(input, &[("count", &count), ("newlines", &count_newlines)]).unwrap(),
+            3
+        );
+        let timings = TIMINGS.with(|t| std::mem::take(&mut *t.borrow_mut()));
+        assert_eq!(
+            timings
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["count", "newlines"]
+        );
+
+        let err =
+            variants("a\nb", &[("count", &count), ("newlines", &count_newlines)]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "variants disagree: count = 2, newlines = 1"
+        );
+
+        let err = variants(input, &[("count", &count), ("fail", &fail)]).unwrap_err();
+        assert_eq!(format!("{:#}", err), "variant fail: no solution");
+    }
+}
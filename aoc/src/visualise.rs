@@ -0,0 +1,391 @@
+//! Terminal visualisation of simulations.
+//!
+//! Solutions call `frame` (or `frame_cells`) once per simulation step. This does nothing unless
+//! the AOC_VISUALISE environment variable is set, in which case each frame is drawn in place on
+//! stderr, with cells that changed since the previous frame highlighted.
+//!
+//! - AOC_VISUALISE=1: start running, AOC_VISUALISE=step: start paused
+//! - AOC_VISUALISE_FPS=n: frames per second (default 10)
+//!
+//! While running, type a command followed by enter: an empty line pauses or resumes, "n" steps
+//! one frame while paused, "+" and "-" change the frame rate, and "q" stops visualising.
+use std::io::{BufRead, Write};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use atty::Stream;
+
+use crate::{BoundingBox, XY};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Default,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Grey,
+}
+
+impl Color {
+    fn ansi_code(&self) -> u8 {
+        match self {
+            Color::Default => 39,
+            Color::Black => 30,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+            Color::Grey => 90,
+        }
+    }
+}
+
+fn default_palette(ch: char) -> Color {
+    match ch {
+        '.' | ' ' => Color::Grey,
+        '#' => Color::White,
+        _ => Color::Yellow,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    TogglePause,
+    Step,
+    Faster,
+    Slower,
+    Quit,
+}
+
+impl Command {
+    fn parse(line: &str) -> Option<Command> {
+        match line.trim() {
+            "" => Some(Command::TogglePause),
+            "n" => Some(Command::Step),
+            "+" => Some(Command::Faster),
+            "-" => Some(Command::Slower),
+            "q" => Some(Command::Quit),
+            _ => None,
+        }
+    }
+}
+
+struct Visualiser<W: Write> {
+    out: W,
+    commands: Receiver<Command>,
+    palette: fn(char) -> Color,
+    fps: u32,
+    paused: bool,
+    count: usize,
+    previous: Vec<Vec<char>>,
+}
+
+impl<W: Write> Visualiser<W> {
+    fn new(out: W, commands: Receiver<Command>, fps: u32, paused: bool) -> Self {
+        Visualiser {
+            out,
+            commands,
+            palette: default_palette,
+            fps: fps.max(1),
+            paused,
+            count: 0,
+            previous: vec![],
+        }
+    }
+
+    fn render(&mut self, grid: &str) -> String {
+        let mut s = String::new();
+        if self.count == 0 {
+            s.push_str("\x1B[2J");
+        }
+        s.push_str("\x1B[H");
+        let rows: Vec<Vec<char>> = grid.lines().map(|line| line.chars().collect()).collect();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, ch) in row.iter().enumerate() {
+                let changed =
+                    self.count > 0 && self.previous.get(y).and_then(|row| row.get(x)) != Some(ch);
+                let reverse = if changed { ";7" } else { "" };
+                let code = (self.palette)(*ch).ansi_code();
+                s.push_str(&format!("\x1B[{code}{reverse}m{ch}\x1B[0m"));
+            }
+            s.push_str("\x1B[K\n");
+        }
+        self.count += 1;
+        s.push_str(&format!(
+            "frame {} @ {} fps{}  (enter: pause/resume, n: step, +/-: speed, q: quit)\x1B[K\n\x1B[J",
+            self.count,
+            self.fps,
+            if self.paused { " [paused]" } else { "" }
+        ));
+        self.previous = rows;
+        s
+    }
+
+    fn apply(&mut self, command: Command) {
+        match command {
+            Command::TogglePause => self.paused = !self.paused,
+            Command::Faster => self.fps = self.fps.saturating_mul(2),
+            Command::Slower => self.fps = (self.fps / 2).max(1),
+            Command::Step | Command::Quit => {}
+        }
+    }
+
+    // Returns false if the user asked to stop visualising.
+    fn draw(&mut self, grid: &str) -> bool {
+        let s = self.render(grid);
+        if self.out.write_all(s.as_bytes()).is_err() || self.out.flush().is_err() {
+            return false;
+        }
+        loop {
+            let command = match self.commands.try_recv() {
+                Ok(command) => command,
+                Err(TryRecvError::Empty) if self.paused => match self.commands.recv() {
+                    Ok(command) => command,
+                    Err(_) => {
+                        self.paused = false;
+                        continue;
+                    }
+                },
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.paused = false;
+                    break;
+                }
+            };
+            match command {
+                Command::Quit => return false,
+                Command::Step if self.paused => return true,
+                command => self.apply(command),
+            }
+        }
+        std::thread::sleep(Duration::from_secs(1) / self.fps);
+        true
+    }
+}
+
+type Slot<W> = Mutex<Option<Visualiser<W>>>;
+
+// The visualiser asked for by `mode` and `fps`, the values of AOC_VISUALISE and
+// AOC_VISUALISE_FPS, drawing to `out` if it is a terminal. `commands` is only called, to start
+// reading commands, if visualisation is enabled.
+fn configure<W: Write>(
+    mode: &str,
+    fps: Option<&str>,
+    out: W,
+    is_tty: bool,
+    commands: impl FnOnce() -> Receiver<Command>,
+) -> Option<Visualiser<W>> {
+    if mode.is_empty() || mode == "0" || !is_tty {
+        return None;
+    }
+    let fps = fps.and_then(|s| s.parse().ok()).unwrap_or(10);
+    Some(Visualiser::new(out, commands(), fps, mode == "step"))
+}
+
+fn read_commands() -> Receiver<Command> {
+    let (tx, rx) = channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if let Some(command) = Command::parse(&line) {
+                if tx.send(command).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    rx
+}
+
+fn global() -> &'static Slot<std::io::Stderr> {
+    static VISUALISER: OnceLock<Slot<std::io::Stderr>> = OnceLock::new();
+    VISUALISER.get_or_init(|| {
+        Mutex::new(configure(
+            &std::env::var("AOC_VISUALISE").unwrap_or_default(),
+            std::env::var("AOC_VISUALISE_FPS").ok().as_deref(),
+            std::io::stderr(),
+            atty::is(Stream::Stderr),
+            read_commands,
+        ))
+    })
+}
+
+/// Check if visualisation is enabled, e.g. to skip expensive bookkeeping only needed for drawing.
+pub fn is_enabled() -> bool {
+    is_enabled_in(global())
+}
+
+fn is_enabled_in<W: Write>(slot: &Slot<W>) -> bool {
+    slot.lock().unwrap().is_some()
+}
+
+/// Set the function that picks the colour of each character. The default draws '.' in grey, '#' in
+/// white and everything else in yellow.
+pub fn set_palette(palette: fn(char) -> Color) {
+    if let Some(v) = global().lock().unwrap().as_mut() {
+        v.palette = palette;
+    }
+}
+
+/// Draw one frame of a simulation, given as lines of text. draw is only called if visualisation
+/// is enabled.
+pub fn frame(draw: impl FnOnce() -> String) {
+    frame_in(global(), draw);
+}
+
+fn frame_in<W: Write>(slot: &Slot<W>, draw: impl FnOnce() -> String) {
+    if !is_enabled_in(slot) {
+        return;
+    }
+    // build the frame before taking the lock: draw may call is_enabled or set_palette
+    let text = draw();
+    let mut guard = slot.lock().unwrap();
+    if let Some(v) = guard.as_mut() {
+        if !v.draw(&text) {
+            *guard = None;
+        }
+    }
+}
+
+/// Like `frame`, but for sparse grids. The frame is just large enough to fit all cells; missing
+/// cells are drawn as ' '.
+pub fn frame_cells<I>(draw: impl FnOnce() -> I)
+where
+    I: IntoIterator<Item = (XY, char)>,
+{
+    frame(|| cells_to_string(draw()));
+}
+
+fn cells_to_string(cells: impl IntoIterator<Item = (XY, char)>) -> String {
+    let cells: Vec<_> = cells.into_iter().collect();
+    let Some(bounds) = BoundingBox::from_points(cells.iter().map(|(xy, _)| *xy)) else {
+        return String::new();
+    };
+    let (min, max) = (bounds.top_left(), bounds.bottom_right());
+    let width = (max.x - min.x + 1) as usize;
+    let height = (max.y - min.y + 1) as usize;
+    let mut rows = vec![vec![' '; width]; height];
+    for (xy, ch) in cells {
+        rows[(xy.y - min.y) as usize][(xy.x - min.x) as usize] = ch;
+    }
+    rows.into_iter()
+        .map(|row| row.into_iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::Sender;
+
+    fn visualiser(paused: bool) -> (Visualiser<Vec<u8>>, Sender<Command>) {
+        let (tx, rx) = channel();
+        (Visualiser::new(vec![], rx, 1000, paused), tx)
+    }
+
+    fn configure_test(mode: &str, is_tty: bool) -> Option<Visualiser<Vec<u8>>> {
+        configure(mode, Some("1000"), vec![], is_tty, || channel().1)
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        for (mode, is_tty) in [("", true), ("0", true), ("1", false)] {
+            let slot = Mutex::new(configure_test(mode, is_tty));
+            assert!(!is_enabled_in(&slot));
+            frame_in(&slot, || {
+                panic!("draw called although visualisation is disabled")
+            });
+        }
+
+        let v = configure_test("step", true).unwrap();
+        assert_eq!(v.fps, 1000);
+        assert!(v.paused);
+        let v = configure("1", None, vec![], true, || channel().1).unwrap();
+        assert_eq!(v.fps, 10);
+        assert!(!v.paused);
+    }
+
+    #[test]
+    fn test_frame_calls_back_into_visualiser() {
+        let slot = Mutex::new(configure_test("1", true));
+        frame_in(&slot, || {
+            assert!(is_enabled_in(&slot));
+            "#".to_string()
+        });
+        let out = slot.lock().unwrap().take().unwrap().out;
+        assert!(String::from_utf8(out).unwrap().contains("frame 1"));
+    }
+
+    #[test]
+    fn test_command_parse() {
+        assert_eq!(Command::parse(""), Some(Command::TogglePause));
+        assert_eq!(Command::parse(" n "), Some(Command::Step));
+        assert_eq!(Command::parse("q"), Some(Command::Quit));
+        assert_eq!(Command::parse("foo"), None);
+    }
+
+    #[test]
+    fn test_render_highlights_changes() {
+        let (mut v, _tx) = visualiser(false);
+        let first = v.render("#.\n..");
+        assert!(first.starts_with("\x1B[2J\x1B[H"));
+        assert!(first.contains("\x1B[37m#\x1B[0m\x1B[90m.\x1B[0m"));
+        assert!(!first.contains(";7m"));
+
+        let second = v.render("#@\n..");
+        assert!(!second.starts_with("\x1B[2J"));
+        assert!(second.contains("\x1B[37m#\x1B[0m\x1B[33;7m@\x1B[0m"));
+        assert_eq!(second.matches(";7m").count(), 1);
+        assert!(second.contains("frame 2 @ 1000 fps"));
+    }
+
+    #[test]
+    fn test_draw_commands() {
+        let (mut v, tx) = visualiser(false);
+        tx.send(Command::Slower).unwrap();
+        assert!(v.draw("#"));
+        assert_eq!(v.fps, 500);
+
+        tx.send(Command::Faster).unwrap();
+        tx.send(Command::Faster).unwrap();
+        assert!(v.draw("#"));
+        assert_eq!(v.fps, 2000);
+
+        // a paused visualiser blocks until told to step or resume
+        let (mut v, tx) = visualiser(true);
+        tx.send(Command::Step).unwrap();
+        assert!(v.draw("#"));
+        assert!(v.paused);
+        tx.send(Command::TogglePause).unwrap();
+        assert!(v.draw("#"));
+        assert!(!v.paused);
+
+        tx.send(Command::Quit).unwrap();
+        assert!(!v.draw("#"));
+
+        // if the input goes away while paused, keep running
+        let (mut v, tx) = visualiser(true);
+        drop(tx);
+        assert!(v.draw("#"));
+        assert!(!v.paused);
+    }
+
+    #[test]
+    fn test_cells_to_string() {
+        let cells = [((1, 1).into(), '#'), ((3, 2).into(), '@')];
+        assert_eq!(cells_to_string(cells), "#  \n  @");
+        assert_eq!(cells_to_string([]), "");
+    }
+}
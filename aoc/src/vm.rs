@@ -0,0 +1,305 @@
+use anyhow::{anyhow, bail, Result};
+use rustc_hash::FxHashSet;
+
+/// What the machine should do after an instruction has executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Continue with the next instruction.
+    Next,
+    /// Jump relative to the current instruction.
+    Jump(isize),
+    /// Stop the machine.
+    Halt,
+}
+
+/// An instruction set for a `Machine`. State is whatever the instructions operate on, typically a
+/// struct of registers.
+pub trait Instruction: Sized + Clone {
+    type State;
+
+    /// Parse a single line of assembly.
+    fn parse(line: &str) -> Result<Self>;
+
+    /// Execute the instruction.
+    fn execute(&self, state: &mut Self::State) -> Result<Flow>;
+
+    /// Number of cycles the instruction takes to complete.
+    fn cycles(&self) -> usize {
+        1
+    }
+}
+
+/// Why `Machine::run` returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// The program halted, either explicitly or by moving outside the program.
+    Halted,
+    /// The instruction at this address has a breakpoint and has not been executed yet.
+    Breakpoint(usize),
+    /// The instruction at this address was about to be executed a second time.
+    Loop(usize),
+}
+
+/// A simple virtual machine: a program, a program counter and the instruction set's state.
+pub struct Machine<I: Instruction> {
+    program: Vec<I>,
+    pub state: I::State,
+    pc: isize,
+    cycle: usize,
+    breakpoints: FxHashSet<usize>,
+}
+
+impl<I> Clone for Machine<I>
+where
+    I: Instruction,
+    I::State: Clone,
+{
+    fn clone(&self) -> Self {
+        Machine {
+            program: self.program.clone(),
+            state: self.state.clone(),
+            pc: self.pc,
+            cycle: self.cycle,
+            breakpoints: self.breakpoints.clone(),
+        }
+    }
+}
+
+impl<I: Instruction> Machine<I> {
+    pub fn new(program: Vec<I>, state: I::State) -> Self {
+        Machine {
+            program,
+            state,
+            pc: 0,
+            cycle: 0,
+            breakpoints: FxHashSet::default(),
+        }
+    }
+
+    /// Create a new machine from a program with one instruction per line.
+    pub fn parse(input: &str, state: I::State) -> Result<Self> {
+        let program = input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| I::parse(line).map_err(|err| anyhow!("line {}: {err}", i + 1)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Machine::new(program, state))
+    }
+
+    pub fn program(&self) -> &[I] {
+        &self.program
+    }
+
+    /// Address of the next instruction to execute, or None if a jump went before the first
+    /// instruction.
+    pub fn pc(&self) -> Option<usize> {
+        usize::try_from(self.pc).ok()
+    }
+
+    /// Number of cycles executed so far.
+    pub fn cycle(&self) -> usize {
+        self.cycle
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.pc < 0 || self.pc as usize >= self.program.len()
+    }
+
+    /// Start over from the first instruction with new state. Breakpoints and patches are kept.
+    pub fn reset(&mut self, state: I::State) {
+        self.state = state;
+        self.pc = 0;
+        self.cycle = 0;
+    }
+
+    /// Replace the instruction at address pc, and return the previous instruction.
+    pub fn patch(&mut self, pc: usize, instr: I) -> I {
+        std::mem::replace(&mut self.program[pc], instr)
+    }
+
+    pub fn add_breakpoint(&mut self, pc: usize) {
+        self.breakpoints.insert(pc);
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: usize) {
+        self.breakpoints.remove(&pc);
+    }
+
+    /// Execute a single instruction.
+    pub fn step(&mut self) -> Result<()> {
+        self.step_traced(|_, _| {})
+    }
+
+    fn step_traced<F>(&mut self, mut trace: F) -> Result<()>
+    where
+        F: FnMut(usize, &I::State),
+    {
+        if self.is_halted() {
+            bail!("machine halted");
+        }
+        let instr = &self.program[self.pc as usize];
+        for _ in 0..instr.cycles() {
+            self.cycle += 1;
+            trace(self.cycle, &self.state);
+        }
+        match instr.execute(&mut self.state)? {
+            Flow::Next => self.pc += 1,
+            Flow::Jump(offset) => self.pc += offset,
+            Flow::Halt => self.pc = self.program.len() as isize,
+        }
+        Ok(())
+    }
+
+    fn run_inner<F>(&mut self, detect_loop: bool, mut trace: F) -> Result<Stop>
+    where
+        F: FnMut(usize, &I::State),
+    {
+        let mut visited = FxHashSet::default();
+        let mut first = true;
+        while !self.is_halted() {
+            let pc = self.pc as usize;
+            if !first && self.breakpoints.contains(&pc) {
+                return Ok(Stop::Breakpoint(pc));
+            }
+            if detect_loop && !visited.insert(pc) {
+                return Ok(Stop::Loop(pc));
+            }
+            first = false;
+            self.step_traced(&mut trace)?;
+        }
+        Ok(Stop::Halted)
+    }
+
+    /// Run until the program halts or a breakpoint is hit. A breakpoint on the current
+    /// instruction is ignored, so calling run again continues past it.
+    pub fn run(&mut self) -> Result<Stop> {
+        self.run_inner(false, |_, _| {})
+    }
+
+    /// Like `run`, but also stop before any instruction is executed a second time. Only
+    /// suitable for programs whose control flow does not depend on the state.
+    pub fn run_until_loop(&mut self) -> Result<Stop> {
+        self.run_inner(true, |_, _| {})
+    }
+
+    /// Like `run`, but call trace with the cycle number (starting at 1) and the current state
+    /// during every cycle, i.e. before the instruction being executed has updated the state.
+    pub fn run_traced<F>(&mut self, trace: F) -> Result<Stop>
+    where
+        F: FnMut(usize, &I::State),
+    {
+        self.run_inner(false, trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Op {
+        Nop(isize),
+        Acc(i32),
+        Jmp(isize),
+        Slow,
+        Hlt,
+    }
+
+    impl Instruction for Op {
+        type State = i32;
+
+        fn parse(line: &str) -> Result<Self> {
+            let (op, arg) = line.split_once(' ').unwrap_or((line, "0"));
+            match op {
+                "nop" => Ok(Op::Nop(arg.parse()?)),
+                "acc" => Ok(Op::Acc(arg.parse()?)),
+                "jmp" => Ok(Op::Jmp(arg.parse()?)),
+                "slow" => Ok(Op::Slow),
+                "hlt" => Ok(Op::Hlt),
+                _ => bail!("unknown instruction"),
+            }
+        }
+
+        fn execute(&self, acc: &mut i32) -> Result<Flow> {
+            match self {
+                Op::Nop(_) | Op::Slow => Ok(Flow::Next),
+                Op::Acc(n) => {
+                    *acc += n;
+                    Ok(Flow::Next)
+                }
+                Op::Jmp(offset) => Ok(Flow::Jump(*offset)),
+                Op::Hlt => Ok(Flow::Halt),
+            }
+        }
+
+        fn cycles(&self) -> usize {
+            match self {
+                Op::Slow => 3,
+                _ => 1,
+            }
+        }
+    }
+
+    const LOOPING: &str = "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6";
+
+    #[test]
+    fn test_parse() {
+        let m: Machine<Op> = Machine::parse("acc +1\njmp -1", 0).unwrap();
+        assert_eq!(m.program(), &[Op::Acc(1), Op::Jmp(-1)]);
+
+        let err = Machine::<Op>::parse("acc +1\nfoo", 0).err().unwrap();
+        assert_eq!(err.to_string(), "line 2: unknown instruction");
+    }
+
+    #[test]
+    fn test_run_until_loop() {
+        let mut m: Machine<Op> = Machine::parse(LOOPING, 0).unwrap();
+        assert_eq!(m.run_until_loop().unwrap(), Stop::Loop(1));
+        assert_eq!(m.state, 5);
+    }
+
+    #[test]
+    fn test_patch() {
+        let mut m: Machine<Op> = Machine::parse(LOOPING, 0).unwrap();
+        assert_eq!(m.patch(7, Op::Nop(-4)), Op::Jmp(-4));
+        assert_eq!(m.run_until_loop().unwrap(), Stop::Halted);
+        assert_eq!(m.state, 8);
+        assert!(m.is_halted());
+        assert!(m.step().is_err());
+    }
+
+    #[test]
+    fn test_jump_before_start() {
+        let mut m: Machine<Op> = Machine::parse("acc +1\njmp -2\nacc +2", 0).unwrap();
+        assert_eq!(m.run().unwrap(), Stop::Halted);
+        assert_eq!(m.state, 1);
+        assert_eq!(m.pc(), None);
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut m: Machine<Op> = Machine::parse("acc +1\nacc +2\nhlt\nacc +4", 0).unwrap();
+        m.add_breakpoint(1);
+        assert_eq!(m.run().unwrap(), Stop::Breakpoint(1));
+        assert_eq!(m.state, 1);
+        assert_eq!(m.pc(), Some(1));
+        assert_eq!(m.run().unwrap(), Stop::Halted);
+        assert_eq!(m.state, 3);
+
+        m.reset(0);
+        m.remove_breakpoint(1);
+        assert_eq!(m.run().unwrap(), Stop::Halted);
+        assert_eq!(m.state, 3);
+    }
+
+    #[test]
+    fn test_run_traced() {
+        let mut m: Machine<Op> = Machine::parse("acc +1\nslow\nacc +2", 0).unwrap();
+        let mut trace = vec![];
+        m.run_traced(|cycle, acc| trace.push((cycle, *acc)))
+            .unwrap();
+        assert_eq!(trace, vec![(1, 0), (2, 1), (3, 1), (4, 1), (5, 1)]);
+        assert_eq!(m.cycle(), 5);
+        assert_eq!(m.state, 3);
+    }
+}